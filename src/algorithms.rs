@@ -0,0 +1,365 @@
+//! SSH algorithm preferences of a profile, and the algorithms a connection ended up with.
+//!
+//! The SSH library doesn't tell which algorithms were negotiated: the first KEXINIT of the
+//! server is read as it goes by, then the choice is made again with the same rule (the
+//! first algorithm of the client that the server also offers).
+
+use std::borrow::Cow;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::models::{AlgorithmKind, AlgorithmSettings};
+use russh::{Preferred, cipher, compression, kex, keys::key, mac};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const KEXINIT: u8 = 20;
+/// Give up looking for the KEXINIT after this much data
+const MAX_SNIFFED: usize = 64 * 1024;
+
+/// Host key types the library verifies (its own list misses Ed25519)
+const HOST_KEYS: [key::Name; 7] = [
+    key::ED25519,
+    key::ECDSA_SHA2_NISTP256,
+    key::ECDSA_SHA2_NISTP384,
+    key::ECDSA_SHA2_NISTP521,
+    key::RSA_SHA2_512,
+    key::RSA_SHA2_256,
+    key::SSH_RSA,
+];
+
+/// Algorithms of a family this build can use, "none" ciphers and MACs left out
+pub fn supported(kind: AlgorithmKind) -> Vec<&'static str> {
+    let names: Vec<&'static str> = match kind {
+        AlgorithmKind::Kex => kex::ALL_KEX_ALGORITHMS.iter().map(|n| name(*n)).collect(),
+        AlgorithmKind::HostKey => HOST_KEYS.iter().map(|n| n.0).collect(),
+        AlgorithmKind::Cipher => cipher::ALL_CIPHERS.iter().map(|n| name(*n)).collect(),
+        AlgorithmKind::Mac => mac::ALL_MAC_ALGORITHMS.iter().map(|n| name(*n)).collect(),
+        // "none" is a real choice here
+        AlgorithmKind::Compression => {
+            return compression::ALL_COMPRESSION_ALGORITHMS
+                .iter()
+                .map(|n| name(*n))
+                .collect();
+        }
+    };
+    names
+        .into_iter()
+        .filter(|n| *n != "none" && *n != "clear")
+        .collect()
+}
+
+fn name<N: AsRef<str>>(n: &'static N) -> &'static str {
+    n.as_ref()
+}
+
+/// Lists of the profile for the SSH library; unknown names are skipped, and a family left
+/// empty keeps the library's choice
+pub fn preferred(settings: &AlgorithmSettings) -> Preferred {
+    let default = Preferred::DEFAULT;
+    let mut kex = pick(&settings.kex, |n| kex::Name::try_from(n).ok(), &default.kex);
+    // not algorithms: extensions announced in the same list, the server ones are no use to a client
+    let kex_list = kex.to_mut();
+    kex_list.retain(|n| !n.as_ref().starts_with("ext-info-") && !n.as_ref().starts_with("kex-strict-"));
+    kex_list.extend([
+        kex::EXTENSION_SUPPORT_AS_CLIENT,
+        kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT,
+    ]);
+    Preferred {
+        kex,
+        key: pick(
+            &settings.host_key,
+            |n| HOST_KEYS.into_iter().find(|k| k.0 == n),
+            &default.key,
+        ),
+        cipher: pick(
+            &settings.cipher,
+            |n| cipher::Name::try_from(n).ok(),
+            &default.cipher,
+        ),
+        mac: pick(&settings.mac, |n| mac::Name::try_from(n).ok(), &default.mac),
+        compression: pick(
+            &settings.compression,
+            |n| compression::Name::try_from(n).ok(),
+            &default.compression,
+        ),
+    }
+}
+
+fn pick<N: Clone>(
+    wanted: &[String],
+    parse: impl Fn(&str) -> Option<N>,
+    default: &[N],
+) -> Cow<'static, [N]> {
+    let names: Vec<N> = wanted.iter().filter_map(|n| parse(n)).collect();
+    if names.is_empty() {
+        Cow::Owned(default.to_vec())
+    } else {
+        Cow::Owned(names)
+    }
+}
+
+/// Name-lists of the server's KEXINIT, client to server direction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerAlgorithms {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher: Vec<String>,
+    pub mac: Vec<String>,
+    pub compression: Vec<String>,
+}
+
+/// The server's version line then its KEXINIT packet, once all of it was received
+pub fn server_kexinit(received: &[u8]) -> Option<ServerAlgorithms> {
+    // lines before the version are allowed (RFC 4253, 4.2)
+    let mut pos = 0;
+    loop {
+        let end = pos + received[pos..].iter().position(|&b| b == b'\n')?;
+        let line = &received[pos..end];
+        pos = end + 1;
+        if line.starts_with(b"SSH-") {
+            break;
+        }
+    }
+
+    let packet = &received[pos..];
+    let length = u32::from_be_bytes(packet.get(..4)?.try_into().ok()?) as usize;
+    let padding = *packet.get(4)? as usize;
+    let payload = packet
+        .get(5..4 + length)?
+        .get(..length.checked_sub(padding + 1)?)?;
+    if payload.first() != Some(&KEXINIT) {
+        return None;
+    }
+
+    // message number and cookie, then the name-lists
+    let mut rest = payload.get(17..)?;
+    let mut lists = Vec::new();
+    for _ in 0..8 {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let list = std::str::from_utf8(rest.get(4..4 + length)?).ok()?;
+        lists.push(
+            list.split(',')
+                .filter(|n| !n.is_empty())
+                .map(String::from)
+                .collect(),
+        );
+        rest = &rest[4 + length..];
+    }
+    // kex, host key, cipher c→s, cipher s→c, mac c→s, mac s→c, compression c→s, compression s→c
+    let mut lists = lists.into_iter();
+    let mut next = || lists.next().unwrap_or_default();
+    let (kex, host_key, cipher) = (next(), next(), next());
+    next();
+    let mac = next();
+    next();
+    let compression = next();
+    Some(ServerAlgorithms {
+        kex,
+        host_key,
+        cipher,
+        mac,
+        compression,
+    })
+}
+
+/// Algorithms a connection uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiated {
+    pub kex: String,
+    pub host_key: String,
+    pub cipher: String,
+    /// None with an AEAD cipher, which checks integrity itself
+    pub mac: Option<String>,
+    pub compression: String,
+}
+
+/// The choice of the client: its first algorithm the server also has, None when a family has none in common
+pub fn negotiate(ours: &Preferred, server: &ServerAlgorithms) -> Option<Negotiated> {
+    fn first<N: AsRef<str>>(ours: &[N], theirs: &[String]) -> Option<String> {
+        ours.iter()
+            .map(|n| n.as_ref())
+            .find(|n| theirs.iter().any(|t| t == n))
+            .map(String::from)
+    }
+    let cipher = first(&ours.cipher, &server.cipher)?;
+    let aead = cipher == cipher::CHACHA20_POLY1305.as_ref() || cipher == cipher::AES_256_GCM.as_ref();
+    Some(Negotiated {
+        kex: first(&ours.kex, &server.kex)?,
+        host_key: first(&ours.key, &server.host_key)?,
+        mac: if aead {
+            None
+        } else {
+            Some(first(&ours.mac, &server.mac)?)
+        },
+        cipher,
+        compression: first(&ours.compression, &server.compression)?,
+    })
+}
+
+impl std::fmt::Display for Negotiated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} · {} · {}", self.kex, self.host_key, self.cipher)?;
+        if let Some(mac) = &self.mac {
+            write!(f, " · {}", mac)?;
+        }
+        if self.compression != "none" {
+            write!(f, " · {}", self.compression)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stream of an SSH connection that keeps the server's algorithm lists as they go by
+pub struct KexSniffer<S> {
+    inner: S,
+    received: Vec<u8>,
+    watching: bool,
+    found: Arc<Mutex<Option<ServerAlgorithms>>>,
+}
+
+impl<S> KexSniffer<S> {
+    /// The lists are in the returned slot once the key exchange started
+    pub fn new(inner: S) -> (Self, Arc<Mutex<Option<ServerAlgorithms>>>) {
+        let found = Arc::new(Mutex::new(None));
+        let sniffer = Self {
+            inner,
+            received: Vec::new(),
+            watching: true,
+            found: found.clone(),
+        };
+        (sniffer, found)
+    }
+
+    fn watch(&mut self, data: &[u8]) {
+        self.received.extend_from_slice(data);
+        if let Some(server) = server_kexinit(&self.received) {
+            if let Ok(mut found) = self.found.lock() {
+                *found = Some(server);
+            }
+            self.watching = false;
+        } else if self.received.len() > MAX_SNIFFED {
+            self.watching = false;
+        }
+        if !self.watching {
+            self.received = Vec::new();
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for KexSniffer<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if self.watching && matches!(poll, Poll::Ready(Ok(()))) {
+            let data = buf.filled()[before..].to_vec();
+            self.watch(&data);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for KexSniffer<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, data)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AlgorithmPreset;
+
+    // version line then a KEXINIT packet with these name-lists
+    fn server_hello(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![KEXINIT];
+        payload.extend([7; 16]);
+        for list in lists {
+            payload.extend((list.len() as u32).to_be_bytes());
+            payload.extend(list.as_bytes());
+        }
+        payload.extend([0, 0, 0, 0, 0]);
+        let padding = 8 - (payload.len() + 5) % 8 + 4;
+        let mut hello = b"banner\r\nSSH-2.0-OpenSSH_9.6\r\n".to_vec();
+        hello.extend(((payload.len() + padding + 1) as u32).to_be_bytes());
+        hello.push(padding as u8);
+        hello.extend(payload);
+        hello.extend(vec![0; padding]);
+        hello
+    }
+
+    const OLD_SWITCH: [&str; 10] = [
+        "diffie-hellman-group14-sha1,diffie-hellman-group1-sha1",
+        "ssh-rsa",
+        "aes128-cbc,3des-cbc",
+        "aes128-cbc,3des-cbc",
+        "hmac-sha1",
+        "hmac-sha1",
+        "none",
+        "none",
+        "",
+        "",
+    ];
+
+    #[test]
+    fn kexinit_is_read_once_complete() {
+        let hello = server_hello(OLD_SWITCH);
+        assert_eq!(server_kexinit(&hello[..hello.len() - 1]), None);
+
+        let server = server_kexinit(&hello).unwrap();
+        assert_eq!(
+            server.kex,
+            ["diffie-hellman-group14-sha1", "diffie-hellman-group1-sha1"]
+        );
+        assert_eq!(server.host_key, ["ssh-rsa"]);
+        assert_eq!(server.mac, ["hmac-sha1"]);
+        assert_eq!(server.compression, ["none"]);
+    }
+
+    #[test]
+    fn old_equipment_needs_the_legacy_preset() {
+        let server = server_kexinit(&server_hello(OLD_SWITCH)).unwrap();
+        assert_eq!(
+            negotiate(&preferred(&AlgorithmSettings::default()), &server),
+            None
+        );
+
+        let legacy = negotiate(&preferred(&AlgorithmPreset::Legacy.settings()), &server).unwrap();
+        assert_eq!(legacy.kex, "diffie-hellman-group14-sha1");
+        assert_eq!(legacy.cipher, "aes128-cbc");
+        assert_eq!(legacy.mac.as_deref(), Some("hmac-sha1"));
+        assert_eq!(
+            legacy.to_string(),
+            "diffie-hellman-group14-sha1 · ssh-rsa · aes128-cbc · hmac-sha1"
+        );
+    }
+
+    #[test]
+    fn presets_only_name_supported_algorithms() {
+        for preset in AlgorithmPreset::ALL {
+            let settings = preset.settings();
+            for kind in AlgorithmKind::ALL {
+                let supported = supported(kind);
+                for name in settings.list(kind) {
+                    assert!(supported.contains(&name.as_str()), "{} ({:?})", name, preset);
+                }
+            }
+        }
+        let ours = preferred(&AlgorithmPreset::Hardened.settings());
+        assert_eq!(ours.cipher.len(), 3);
+        assert!(ours.kex.contains(&kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT));
+    }
+}
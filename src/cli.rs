@@ -0,0 +1,335 @@
+//! Command line: sessions opened straight from a script, a desktop launcher or another tool,
+//! without going through the dashboard.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use zeroize::Zeroizing;
+
+use crate::destination::{self, Destination};
+use crate::folders;
+use crate::models::{Profile, ProfileStore, Settings};
+use crate::ui::theme::ThemeChoice;
+
+pub const USAGE: &str = "\
+Utilisation :
+  rustty                              ouvre le tableau de bord
+  rustty [options] utilisateur@hôte[:port]
+  rustty [options] ssh://utilisateur@hôte[:port]
+  rustty [options] --profile NOM      ouvre le profil enregistré NOM
+  rustty [options] --group DOSSIER    ouvre tous les profils du dossier
+  rustty --list-profiles              affiche les profils et quitte
+
+Options :
+  -p PORT                 port, si la destination n'en donne pas
+  --theme NOM             thème des terminaux (Dracula, Nord, ...)
+  -h, --help              affiche cette aide
+
+Le mot de passe est demandé dans le terminal, ou lu sur la première ligne de l'entrée standard.";
+
+/// What the command line asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Dashboard,
+    Launch(Launch),
+    ListProfiles,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Destination(Destination),
+    Profile(String),
+    Group(String),
+}
+
+/// Sessions to open, and what the options change in their profiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct Launch {
+    pub target: Target,
+    pub port: Option<u16>,
+    pub theme: Option<ThemeChoice>,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut target: Option<Target> = None;
+    let mut port = None;
+    let mut theme = None;
+    let mut list = false;
+
+    while let Some(arg) = args.next() {
+        // "--profile=web" and "-p2222" as well as "--profile web" and "-p 2222"
+        let (flag, attached) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ if arg.len() > 2 && arg.starts_with('-') && !arg.starts_with("--") && arg.is_char_boundary(2) => {
+                (arg[..2].to_string(), Some(arg[2..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            attached
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} attend une valeur", name))
+        };
+
+        let mut set_target = |new: Target| match target {
+            Some(_) => Err("Une seule destination, --profile ou --group à la fois".to_string()),
+            None => {
+                target = Some(new);
+                Ok(())
+            }
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--list-profiles" => list = true,
+            "--profile" => set_target(Target::Profile(value("--profile")?))?,
+            "--group" => set_target(Target::Group(value("--group")?))?,
+            "-p" => port = Some(destination::validate_port(&value("-p")?)?),
+            // the connection has no key authentication nor forwarding yet: refused rather than ignored
+            "-i" | "-L" | "-R" | "-D" => {
+                return Err(format!(
+                    "{} n'est pas encore pris en charge (clé privée et redirections de port à venir)",
+                    flag
+                ));
+            }
+            "--theme" => theme = Some(parse_theme(&value("--theme")?)?),
+            _ if arg.starts_with('-') => return Err(format!("Option inconnue : {}", arg)),
+            _ => set_target(Target::Destination(destination::parse(&arg)?))?,
+        }
+    }
+
+    match target {
+        _ if list => Ok(Command::ListProfiles),
+        Some(target) => Ok(Command::Launch(Launch {
+            target,
+            port,
+            theme,
+        })),
+        None if port.is_some() || theme.is_some() => {
+            Err("Les options demandent une destination, --profile ou --group".into())
+        }
+        None => Ok(Command::Dashboard),
+    }
+}
+
+// "dracula", "TokyoNight", "tokyo night": the name of the enum or the one shown in the app
+fn parse_theme(name: &str) -> Result<ThemeChoice, String> {
+    let simplify = |s: &str| s.to_lowercase().replace([' ', '-', '_'], "");
+    let wanted = simplify(name);
+    ThemeChoice::ALL
+        .into_iter()
+        .find(|theme| simplify(&format!("{:?}", theme)) == wanted || simplify(&theme.to_string()) == wanted)
+        .ok_or_else(|| {
+            let names: Vec<String> = ThemeChoice::ALL.iter().map(|t| format!("{:?}", t)).collect();
+            format!("Thème inconnu : {} (au choix : {})", name, names.join(", "))
+        })
+}
+
+/// Profiles of the sessions to open, with the options applied
+pub fn resolve(launch: &Launch, profiles: &[Profile], settings: &Settings) -> Result<Vec<Profile>, String> {
+    let mut sessions = match &launch.target {
+        Target::Destination(destination) => {
+            // like ssh, the local user name when none is given
+            let username = destination
+                .user
+                .clone()
+                .or_else(|| std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok())
+                .ok_or("Utilisateur manquant : utilisez utilisateur@hôte")?;
+            vec![Profile {
+                name: destination.host.clone(),
+                ip: destination.host.clone(),
+                port: destination.port.or(launch.port).unwrap_or(22).to_string(),
+                username,
+                terminal_count: 1,
+                ..Profile::default()
+            }]
+        }
+        Target::Profile(name) => {
+            let profile = profiles
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("Aucun profil nommé « {} » (voir --list-profiles)", name))?;
+            vec![folders::resolve(profile, settings)]
+        }
+        Target::Group(name) => {
+            let folder = folders::normalize(name);
+            let sessions: Vec<Profile> = profiles
+                .iter()
+                .filter(|p| folders::is_within(&p.group, &folder))
+                .map(|p| folders::resolve(p, settings))
+                .collect();
+            if sessions.is_empty() {
+                return Err(format!("Aucun profil dans le dossier {}", folder));
+            }
+            sessions
+        }
+    };
+
+    for session in &mut sessions {
+        // a destination already took it into account
+        if let Some(port) = launch.port
+            && !matches!(launch.target, Target::Destination(_))
+        {
+            session.port = port.to_string();
+        }
+        if let Some(theme) = launch.theme {
+            session.theme = theme;
+        }
+        if session.username.is_empty() {
+            return Err(format!("Profil « {} » sans nom d'utilisateur", session.name));
+        }
+    }
+    Ok(sessions)
+}
+
+/// One line per profile, tab separated: folder, name, user@host:port, tags
+pub fn list_profiles() {
+    let (store, profiles, error) = ProfileStore::load();
+    if let Some(error) = error {
+        eprintln!("rustty: {}", error);
+    }
+    // piped into head or grep: stop quietly once the reader is gone
+    let mut out = std::io::stdout().lock();
+    for profile in &profiles {
+        let profile = folders::resolve(profile, &store.settings);
+        let written = writeln!(
+            out,
+            "{}\t{}\t{}@{}:{}\t{}",
+            profile.group,
+            profile.name,
+            profile.username,
+            profile.ip,
+            profile.port,
+            profile.tags.join(",")
+        );
+        if written.is_err() {
+            break;
+        }
+    }
+}
+
+/// Sessions of the command line and their password, before any window opens
+pub fn prepare(launch: &Launch) -> Result<(Vec<Profile>, Zeroizing<String>), String> {
+    let (store, profiles, error) = ProfileStore::load();
+    if let Some(error) = error {
+        eprintln!("rustty: {}", error);
+    }
+    let sessions = resolve(launch, &profiles, &store.settings)?;
+    // only SSH logs in with a password
+    if !sessions.iter().any(|s| s.protocol.needs_login()) {
+        return Ok((sessions, Zeroizing::default()));
+    }
+    let prompt = match &sessions[..] {
+        [session] => format!("Mot de passe de {}@{} : ", session.username, session.ip),
+        _ => format!("Mot de passe des {} sessions : ", sessions.len()),
+    };
+    let password = read_password(&prompt)?;
+    Ok((sessions, password))
+}
+
+/// Asked on the terminal, or read from the first line of stdin when it isn't one
+pub fn read_password(prompt: &str) -> Result<Zeroizing<String>, String> {
+    if std::io::stdin().is_terminal() {
+        return rpassword::prompt_password(prompt)
+            .map(Zeroizing::new)
+            .map_err(|e| format!("Lecture du mot de passe impossible : {}", e));
+    }
+    let mut line = Zeroizing::new(String::new());
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("Lecture du mot de passe impossible : {}", e))?;
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Command, String> {
+        parse(line.split_whitespace().map(String::from))
+    }
+
+    fn launch(line: &str) -> Launch {
+        match args(line) {
+            Ok(Command::Launch(launch)) => launch,
+            other => panic!("{}: {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(args(""), Ok(Command::Dashboard));
+        assert_eq!(args("--list-profiles"), Ok(Command::ListProfiles));
+        assert_eq!(args("-h"), Ok(Command::Help));
+        assert!(args("--bogus").is_err());
+        assert!(args("-p 22").is_err(), "options without a target");
+        assert!(args("host1 host2").is_err());
+        assert!(args("--profile").is_err());
+    }
+
+    #[test]
+    fn options() {
+        let parsed = launch("-p2222 --theme=tokyo-night admin@web");
+        assert_eq!(parsed.port, Some(2222));
+        assert_eq!(parsed.theme, Some(ThemeChoice::TokyoNight));
+        assert_eq!(launch("--group prod/eu").target, Target::Group("prod/eu".into()));
+
+        assert!(args("-p 0 web").is_err());
+        assert!(args("--theme neon web").is_err());
+    }
+
+    #[test]
+    fn key_and_forwarding_options_are_refused() {
+        for line in [
+            "-i ~/.ssh/id_ed25519 admin@web",
+            "-L 8080:localhost:80 admin@web",
+            "-R8080:localhost:80 admin@web",
+            "-D [::1]:1080 admin@web",
+        ] {
+            let err = args(line).unwrap_err();
+            assert!(err.contains("pas encore pris en charge"), "{}: {}", line, err);
+        }
+    }
+
+    #[test]
+    fn targets_become_sessions() {
+        let mut settings = Settings::default();
+        settings.folders.insert(
+            "PROD".into(),
+            crate::models::FolderDefaults {
+                username: Some("ops".into()),
+                ..Default::default()
+            },
+        );
+        let profiles = vec![
+            Profile {
+                name: "web".into(),
+                ip: "10.0.0.1".into(),
+                group: "PROD/EU".into(),
+                ..Profile::default()
+            },
+            Profile {
+                name: "db".into(),
+                ip: "10.0.0.2".into(),
+                username: "postgres".into(),
+                group: "PROD".into(),
+                ..Profile::default()
+            },
+        ];
+
+        let sessions = resolve(&launch("-p 2200 --profile WEB"), &profiles, &settings).unwrap();
+        assert_eq!((sessions[0].username.as_str(), sessions[0].port.as_str()), ("ops", "2200"));
+        assert_eq!(resolve(&launch("--group prod"), &profiles, &settings).unwrap().len(), 2);
+        assert!(resolve(&launch("--group dev"), &profiles, &settings).is_err());
+        assert!(resolve(&launch("--profile nope"), &profiles, &settings).is_err());
+
+        // the port of the destination wins over -p
+        let sessions = resolve(&launch("-p 2200 root@[::1]:22"), &profiles, &settings).unwrap();
+        assert_eq!((sessions[0].ip.as_str(), sessions[0].port.as_str()), ("::1", "22"));
+    }
+}
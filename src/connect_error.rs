@@ -0,0 +1,123 @@
+//! Why a connection failed, with what the user can do about it.
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectError {
+    /// The name doesn't resolve
+    Dns { host: String, detail: String },
+    /// Nothing listens on the port
+    Refused { host: String, port: u16 },
+    /// No answer within the connect timeout of the profile
+    Timeout { host: String, port: u16 },
+    /// Any other network error (unreachable host, reset...)
+    Network { host: String, port: u16, detail: String },
+    /// Key exchange failed, e.g. no algorithm in common
+    Negotiation(String),
+    /// The server key differs from the one recorded for this host
+    HostKeyMismatch {
+        host: String,
+        fingerprint: String,
+        known_hosts: PathBuf,
+        line: usize,
+    },
+    /// The server allows none of the methods this client knows
+    NoAuthMethods { user: String },
+    /// Credentials refused. russh 0.45 only returns whether an attempt succeeded, not the
+    /// methods the server would still accept, so only the methods tried can be shown
+    AuthRejected { user: String, tried: Vec<String> },
+    /// Session channel or shell refused
+    ChannelOpen(String),
+    PtyRefused,
+}
+
+impl ConnectError {
+    /// What to check or change, shown under the message
+    pub fn suggestion(&self) -> String {
+        match self {
+            ConnectError::Dns { .. } => {
+                "Vérifiez l'orthographe du nom d'hôte, ou utilisez son adresse IP.".into()
+            }
+            ConnectError::Refused { port, .. } => format!(
+                "Le serveur est joignable mais rien n'écoute sur le port {} : vérifiez le port et que le service est démarré.",
+                port
+            ),
+            ConnectError::Timeout { .. } => {
+                "Vérifiez le VPN, le pare-feu et l'adresse, ou augmentez le délai de connexion (page Réseau).".into()
+            }
+            ConnectError::Network { .. } => "Vérifiez la connexion réseau et la route vers l'hôte.".into(),
+            ConnectError::Negotiation(_) => {
+                "Pour un équipement ancien, choisissez le préréglage « Équipement ancien » (page Avancé).".into()
+            }
+            ConnectError::HostKeyMismatch { known_hosts, line, .. } => format!(
+                "Si la clé a changé légitimement (réinstallation), supprimez la ligne {} de {} ; sinon ne vous connectez pas.",
+                line,
+                known_hosts.display()
+            ),
+            ConnectError::NoAuthMethods { .. } => {
+                "Le serveur n'accepte aucune méthode connue de RustTy : demandez à l'administrateur d'autoriser le mot de passe.".into()
+            }
+            ConnectError::AuthRejected { .. } => {
+                "Vérifiez le nom d'utilisateur et le mot de passe (ou l'entrée du coffre).".into()
+            }
+            ConnectError::ChannelOpen(_) => {
+                "Le compte a peut-être un shell désactivé ou une limite de sessions atteinte.".into()
+            }
+            ConnectError::PtyRefused => {
+                "Le serveur interdit les terminaux pour ce compte (PermitTTY no ou restriction de clé).".into()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectError::Dns { host, detail } => write!(f, "Nom d'hôte inconnu : {} ({})", host, detail),
+            ConnectError::Refused { host, port } => write!(f, "Connexion refusée par {}:{}", host, port),
+            ConnectError::Timeout { host, port } => write!(f, "Pas de réponse de {}:{}", host, port),
+            ConnectError::Network { host, port, detail } => {
+                write!(f, "Connexion à {}:{} impossible : {}", host, port, detail)
+            }
+            ConnectError::Negotiation(detail) => write!(f, "Négociation SSH impossible : {}", detail),
+            ConnectError::HostKeyMismatch { host, fingerprint, .. } => write!(
+                f,
+                "La clé de {} a changé (SHA256:{}) : possible usurpation du serveur",
+                host, fingerprint
+            ),
+            ConnectError::NoAuthMethods { user } => {
+                write!(f, "Aucune méthode d'authentification possible pour {}", user)
+            }
+            ConnectError::AuthRejected { user, tried } => {
+                write!(f, "Authentification refusée pour {} (essayé : {})", user, tried.join(", "))
+            }
+            ConnectError::ChannelOpen(detail) => write!(f, "Ouverture de la session refusée : {}", detail),
+            ConnectError::PtyRefused => write!(f, "Le serveur a refusé le terminal (PTY)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_name_the_target() {
+        let refused = ConnectError::Refused {
+            host: "10.0.0.1".into(),
+            port: 2222,
+        };
+        assert_eq!(refused.to_string(), "Connexion refusée par 10.0.0.1:2222");
+        assert!(refused.suggestion().contains("port 2222"));
+
+        let rejected = ConnectError::AuthRejected {
+            user: "admin".into(),
+            tried: vec!["password".into()],
+        };
+        assert_eq!(rejected.to_string(), "Authentification refusée pour admin (essayé : password)");
+        assert!(rejected.suggestion().contains("mot de passe (ou l'entrée du coffre)"));
+
+        let kex = ConnectError::Negotiation(russh::Error::NoCommonKexAlgo.to_string());
+        assert!(kex.suggestion().contains("Équipement ancien"));
+    }
+}
@@ -0,0 +1,161 @@
+//! Steps of an SSH connection, reported as they happen for the dashboard.
+
+use iced::futures::channel::mpsc;
+
+use crate::messages::{Message, SshMessage};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Resolve,
+    Tcp,
+    Kex,
+    HostKey,
+    /// One attempt per authentication method
+    Auth(String),
+    Channel,
+    Pty,
+    Shell,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Resolve => f.write_str("Résolution du nom"),
+            Step::Tcp => f.write_str("Connexion TCP"),
+            Step::Kex => f.write_str("Échange de clés"),
+            Step::HostKey => f.write_str("Vérification de la clé d'hôte"),
+            Step::Auth(method) => write!(f, "Authentification ({})", method),
+            Step::Channel => f.write_str("Ouverture du canal"),
+            Step::Pty => f.write_str("Terminal (PTY)"),
+            Step::Shell => f.write_str("Shell"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepState {
+    Running,
+    /// With what came out of it, if worth showing (address, fingerprint...)
+    Done(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    Started(String),
+    Step(Step, StepState),
+    /// The shell runs: nothing left to show
+    Finished,
+}
+
+/// Connection being established, as shown on the dashboard
+#[derive(Debug, Clone)]
+pub struct ConnectProgress {
+    pub target: String,
+    pub steps: Vec<(Step, StepState)>,
+}
+
+impl ConnectProgress {
+    pub fn new(target: String) -> Self {
+        Self {
+            target,
+            steps: Vec::new(),
+        }
+    }
+
+    /// A step seen again (running, then done) keeps its place
+    pub fn update(&mut self, step: Step, state: StepState) {
+        match self.steps.iter_mut().find(|(s, _)| *s == step) {
+            Some((_, current)) => *current = state,
+            None => self.steps.push((step, state)),
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|(_, state)| matches!(state, StepState::Failed(_)))
+    }
+}
+
+/// Sends the steps of one connection to the app, and traces them
+#[derive(Clone)]
+pub struct Reporter {
+    id: uuid::Uuid,
+    sender: mpsc::Sender<Message>,
+    /// Span of the connection, for the steps reported from outside of it (host key check)
+    span: tracing::Span,
+}
+
+impl Reporter {
+    pub fn start(target: String, sender: mpsc::Sender<Message>) -> Self {
+        let mut reporter = Self {
+            id: uuid::Uuid::new_v4(),
+            sender,
+            span: tracing::Span::current(),
+        };
+        reporter.send(Update::Started(target));
+        reporter
+    }
+
+    pub fn running(&mut self, step: Step) {
+        tracing::debug!(parent: &self.parent(), %step, "Étape commencée");
+        self.send(Update::Step(step, StepState::Running));
+    }
+
+    pub fn done(&mut self, step: Step, detail: impl Into<String>) {
+        let detail = detail.into();
+        tracing::info!(parent: &self.parent(), %step, %detail, "Étape réussie");
+        self.send(Update::Step(step, StepState::Done(detail)));
+    }
+
+    pub fn failed(&mut self, step: Step, reason: impl ToString) {
+        let reason = reason.to_string();
+        tracing::warn!(parent: &self.parent(), %step, "Étape échouée : {}", reason);
+        self.send(Update::Step(step, StepState::Failed(reason)));
+    }
+
+    pub fn finished(&mut self) {
+        self.send(Update::Finished);
+    }
+
+    /// The current span (channel...), or the connection when there is none
+    fn parent(&self) -> tracing::Span {
+        let current = tracing::Span::current();
+        if current.is_none() {
+            self.span.clone()
+        } else {
+            current
+        }
+    }
+
+    fn send(&mut self, update: Update) {
+        let _ = self
+            .sender
+            .try_send(Message::Ssh(SshMessage::Progress(self.id, update)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_keep_their_order() {
+        let mut progress = ConnectProgress::new("admin@srv:22".into());
+        progress.update(Step::Resolve, StepState::Running);
+        progress.update(Step::Tcp, StepState::Running);
+        progress.update(Step::Resolve, StepState::Done("10.0.0.1".into()));
+        progress.update(
+            Step::Auth("password".into()),
+            StepState::Failed("refusé".into()),
+        );
+
+        assert_eq!(
+            progress.steps[0],
+            (Step::Resolve, StepState::Done("10.0.0.1".into()))
+        );
+        assert_eq!(progress.steps.len(), 3);
+        assert!(progress.failed());
+    }
+}
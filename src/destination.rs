@@ -0,0 +1,167 @@
+//! Destinations typed by hand: `user@host`, `user@host:port`, `[ipv6]:port`, `ssh://user@host:port`.
+//! Also checks the address and port fields of the profile form.
+
+use std::net::{IpAddr, Ipv6Addr};
+
+/// Where to connect, as parsed from the quick-connect bar or the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    pub user: Option<String>,
+    /// Host name or address, IPv6 without brackets
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+        match (self.host.contains(':'), self.port) {
+            (true, Some(port)) => write!(f, "[{}]:{}", self.host, port),
+            (false, Some(port)) => write!(f, "{}:{}", self.host, port),
+            (_, None) => write!(f, "{}", self.host),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Destination, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Destination vide".into());
+    }
+
+    let rest = match input.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("ssh") => rest.trim_end_matches('/'),
+        Some((scheme, _)) => return Err(format!("Schéma non pris en charge : {}://", scheme)),
+        None => input,
+    };
+
+    // the last @ separates the user, the URI form may add ";parameters" after it
+    let (user, address) = match rest.rsplit_once('@') {
+        Some((user, address)) => {
+            let user = user.split(';').next().unwrap_or_default();
+            if user.is_empty() {
+                return Err("Nom d'utilisateur vide avant @".into());
+            }
+            (Some(user.to_string()), address)
+        }
+        None => (None, rest),
+    };
+
+    let (host, port) = if let Some(bracketed) = address.strip_prefix('[') {
+        let (host, after) = bracketed
+            .split_once(']')
+            .ok_or_else(|| format!("Crochet fermant manquant : {}", address))?;
+        if host.parse::<Ipv6Addr>().is_err() {
+            return Err(format!("Adresse IPv6 invalide : {}", host));
+        }
+        let port = match after {
+            "" => None,
+            _ => Some(after.strip_prefix(':').ok_or_else(|| format!("Port attendu après ] : {}", after))?),
+        };
+        (host, port)
+    } else if address.matches(':').count() > 1 {
+        // bare IPv6, no room for a port
+        (address, None)
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (address, None),
+        }
+    };
+
+    validate_host(host)?;
+    Ok(Destination {
+        user,
+        host: host.to_string(),
+        port: port.map(validate_port).transpose()?,
+    })
+}
+
+/// An IP address, or a host name made of letters, digits, '-' and '_'
+pub fn validate_host(host: &str) -> Result<(), String> {
+    if host.is_empty() {
+        return Err("Hôte manquant".into());
+    }
+    if host.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+    let name = host.strip_suffix('.').unwrap_or(host);
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if name.len() <= 253 && name.split('.').all(valid_label) {
+        Ok(())
+    } else {
+        Err(format!("Hôte invalide : {}", host))
+    }
+}
+
+/// A port between 1 and 65535
+pub fn validate_port(port: &str) -> Result<u16, String> {
+    match port.trim().parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ => Err(format!("Port invalide : {}", port)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn destination(user: Option<&str>, host: &str, port: Option<u16>) -> Destination {
+        Destination {
+            user: user.map(String::from),
+            host: host.into(),
+            port,
+        }
+    }
+
+    #[test]
+    fn accepted_forms() {
+        assert_eq!(parse("web01").unwrap(), destination(None, "web01", None));
+        assert_eq!(parse(" admin@10.0.0.5 ").unwrap(), destination(Some("admin"), "10.0.0.5", None));
+        assert_eq!(parse("admin@db.example.com:2222").unwrap(), destination(Some("admin"), "db.example.com", Some(2222)));
+        assert_eq!(parse("[fe80::1]:2200").unwrap(), destination(None, "fe80::1", Some(2200)));
+        assert_eq!(parse("root@[::1]").unwrap(), destination(Some("root"), "::1", None));
+        assert_eq!(parse("2001:db8::7").unwrap(), destination(None, "2001:db8::7", None));
+        assert_eq!(parse("SSH://deploy@host:22/").unwrap(), destination(Some("deploy"), "host", Some(22)));
+        assert_eq!(
+            parse("ssh://me;fingerprint=SHA256-abc@host").unwrap(),
+            destination(Some("me"), "host", None)
+        );
+        assert_eq!(parse("user@corp@bastion").unwrap(), destination(Some("user@corp"), "bastion", None));
+    }
+
+    #[test]
+    fn rejected_forms() {
+        for input in [
+            "",
+            "@host",
+            "host:0",
+            "host:70000",
+            "host:ssh",
+            "[::1",
+            "[::1]22",
+            "[not-v6]:22",
+            "telnet://host",
+            "bad host",
+            "-host",
+            "user@",
+        ] {
+            assert!(parse(input).is_err(), "{:?} should be refused", input);
+        }
+    }
+
+    #[test]
+    fn displayed_as_typed() {
+        for input in ["admin@host:2222", "[fe80::1]:22", "host"] {
+            assert_eq!(parse(input).unwrap().to_string(), input);
+        }
+    }
+}
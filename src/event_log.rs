@@ -0,0 +1,276 @@
+//! What the app did (connections, sessions, files), kept in memory for the event log window.
+//! Filled by a tracing layer: every traced event, with the spans it happened in.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+use tracing::Subscriber;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Oldest events are dropped past this count
+const CAPACITY: usize = 2000;
+
+static EVENTS: Mutex<EventLog> = Mutex::new(EventLog::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Debug and trace events
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub const ALL: [Level; 4] = [Level::Debug, Level::Info, Level::Warn, Level::Error];
+}
+
+impl From<tracing::Level> for Level {
+    fn from(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "AVERT",
+            Level::Error => "ERREUR",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub message: String,
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:<6} {}",
+            self.time.format("%H:%M:%S%.3f"),
+            self.level,
+            self.message
+        )
+    }
+}
+
+pub struct EventLog {
+    events: VecDeque<Event>,
+}
+
+impl EventLog {
+    pub const fn new() -> Self {
+        Self {
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, level: Level, message: String) {
+        if self.events.len() == CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(Event {
+            time: Local::now(),
+            level,
+            message,
+        });
+    }
+
+    /// Events at `min` or above, oldest first
+    pub fn filtered(&self, min: Level) -> Vec<Event> {
+        self.events
+            .iter()
+            .filter(|e| e.level >= min)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Feeds a log with the events that pass the filter of the subscriber
+pub struct EventLogLayer {
+    events: &'static Mutex<EventLog>,
+}
+
+impl EventLogLayer {
+    /// The log shown by the event log window
+    pub fn global() -> Self {
+        Self { events: &EVENTS }
+    }
+}
+
+/// Fields of a span, formatted once when it is created
+struct SpanFields(String);
+
+impl<S> Layer<S> for EventLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = FieldsVisitor::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = FieldsVisitor {
+                fields: std::mem::take(fields),
+                ..Default::default()
+            };
+            values.record(&mut visitor);
+            *fields = visitor.fields;
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = String::new();
+        // connection{host=..}:session{window=..}: message
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                line.push_str(span.name());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>()
+                    && !fields.is_empty()
+                {
+                    let _ = write!(line, "{{{}}}", fields);
+                }
+                line.push_str(": ");
+            }
+        }
+        // the SSH library and the other crates say where they speak from
+        if !metadata.target().starts_with("rustty") {
+            let _ = write!(line, "[{}] ", metadata.target());
+        }
+        let mut fields = FieldsVisitor::default();
+        event.record(&mut fields);
+        line.push_str(&fields.message);
+        if !fields.fields.is_empty() {
+            let _ = write!(line, " ({})", fields.fields);
+        }
+        if let Ok(mut events) = self.events.lock() {
+            events.push((*metadata.level()).into(), line);
+        }
+    }
+}
+
+/// The message of an event apart, its other fields as "name=value"
+#[derive(Default)]
+struct FieldsVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldsVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+            return;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={}", field.name(), value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+            return;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={:?}", field.name(), value);
+    }
+}
+
+pub fn events(min: Level) -> Vec<Event> {
+    EVENTS
+        .lock()
+        .map(|events| events.filtered(min))
+        .unwrap_or_default()
+}
+
+pub fn clear() {
+    if let Ok(mut events) = EVENTS.lock() {
+        events.events.clear();
+    }
+}
+
+/// One line per event, for the clipboard
+pub fn to_text(events: &[Event]) -> String {
+    events.iter().map(|e| format!("{}\n", e)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn events_carry_their_spans_and_fields() {
+        let events: &'static Mutex<EventLog> = Box::leak(Box::new(Mutex::new(EventLog::new())));
+        let subscriber = tracing_subscriber::registry().with(EventLogLayer { events });
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("connection", host = "srv", port = 22);
+            let _entered = span.enter();
+            tracing::warn!(
+                step = "kex",
+                "Négociation impossible : {}",
+                "aucun algorithme"
+            );
+        });
+
+        let logged = events.lock().unwrap().filtered(Level::Debug);
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].level, Level::Warn);
+        assert_eq!(
+            logged[0].message,
+            "connection{host=srv port=22}: Négociation impossible : aucun algorithme (step=kex)"
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_events_and_filters_by_level() {
+        let mut log = EventLog::new();
+        for i in 0..CAPACITY {
+            log.push(Level::Info, format!("info {}", i));
+        }
+        log.push(Level::Error, "échec".into());
+
+        let all = log.filtered(Level::Info);
+        assert_eq!(all.len(), CAPACITY);
+        assert_eq!(all[0].message, "info 1");
+
+        let errors = log.filtered(Level::Warn);
+        assert_eq!(errors.len(), 1);
+        assert!(to_text(&errors).ends_with("ERREUR échec\n"));
+    }
+}
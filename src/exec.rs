@@ -0,0 +1,445 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use russh::{ChannelMsg, client, keys::key};
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+use crate::connect_error::ConnectError;
+use crate::models::Profile;
+use crate::ssh::RejectedKey;
+
+/// Folder where the results of a remote command are exported
+pub fn exports_dir() -> PathBuf {
+    crate::storage::data_dir().join("exec-results")
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Longest a command may run: `tail -f`, a pager or a prompt would never end by themselves
+pub const RUN_TIMEOUT: Duration = Duration::from_secs(300);
+/// How often a running command looks at the cancel flag
+const CANCEL_POLL: Duration = Duration::from_millis(200);
+
+/// Profile or group a one-off command runs on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecTarget {
+    /// (profile id, label shown in the picker)
+    Profile(uuid::Uuid, String),
+    Group(String),
+}
+
+impl ExecTarget {
+    /// Every profile, then every folder (parent folders included)
+    pub fn all(profiles: &[Profile]) -> Vec<Self> {
+        let mut targets: Vec<Self> = profiles
+            .iter()
+            .map(|p| ExecTarget::Profile(p.id, format!("{} ({})", p.name, p.ip)))
+            .collect();
+        let mut groups: Vec<&str> = profiles
+            .iter()
+            .flat_map(|p| crate::folders::lineage(&p.group))
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        targets.extend(groups.into_iter().map(|g| ExecTarget::Group(g.to_string())));
+        targets
+    }
+
+    /// Profiles the command runs on, sub-folders included for a group
+    pub fn profiles<'a>(&self, profiles: &'a [Profile]) -> Vec<&'a Profile> {
+        profiles
+            .iter()
+            .filter(|p| match self {
+                ExecTarget::Profile(id, _) => p.id == *id,
+                ExecTarget::Group(group) => crate::folders::is_within(&p.group, group),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ExecTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecTarget::Profile(_, label) => write!(f, "Profil : {}", label),
+            ExecTarget::Group(group) => write!(f, "Groupe : {}", group.to_uppercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Outcome of a command on one host
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecResult {
+    pub profile: String,
+    pub host: String,
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// None when the server did not report it (connection lost, killed by a signal)
+    pub exit_status: Option<u32>,
+    /// Connection or authentication failure (the command did not run), or why it was stopped
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// Still waiting for the host
+    #[serde(skip)]
+    pub running: bool,
+}
+
+impl ExecResult {
+    /// Placeholder shown until the host answers
+    pub fn pending(profile: &Profile, command: &str) -> Self {
+        Self {
+            profile: profile.name.clone(),
+            host: format!("{}:{}", profile.ip, profile.port),
+            command: command.to_string(),
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_status: None,
+            error: None,
+            duration_ms: 0,
+            running: true,
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        !self.running && self.error.is_none() && self.exit_status == Some(0)
+    }
+}
+
+// exec connections have no window to feed, the output is read from the channel
+struct ExecHandler {
+    /// Host and port the server key is recorded for
+    host: String,
+    port: u16,
+    /// Why the server key was refused, read back when the connection fails
+    rejected_key: RejectedKey,
+}
+
+#[async_trait]
+impl client::Handler for ExecHandler {
+    type Error = russh::Error;
+
+    // same known_hosts check as the interactive sessions
+    async fn check_server_key(&mut self, key: &key::PublicKey) -> Result<bool, Self::Error> {
+        match crate::ssh::verify_server_key(&self.rejected_key, &self.host, self.port, key) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                tracing::error!("{}", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// What the command sent back on its channel
+#[derive(Debug, Default)]
+struct Output {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    exit_status: Option<u32>,
+    /// Cancelled or too long: the channel was closed before the command ended
+    stopped: Option<String>,
+}
+
+impl Output {
+    fn push(&mut self, msg: ChannelMsg) {
+        match msg {
+            ChannelMsg::Data { data } => self.stdout.extend_from_slice(&data),
+            // extended data type 1 is stderr
+            ChannelMsg::ExtendedData { data, ext: 1 } => self.stderr.extend_from_slice(&data),
+            ChannelMsg::ExitStatus { exit_status } => self.exit_status = Some(exit_status),
+            _ => {}
+        }
+    }
+
+    /// Decoded once complete: a character can be split across two packets
+    fn apply_to(self, result: &mut ExecResult) {
+        result.stdout = String::from_utf8_lossy(&self.stdout).into_owned();
+        result.stderr = String::from_utf8_lossy(&self.stderr).into_owned();
+        result.exit_status = self.exit_status;
+        result.error = self.stopped;
+    }
+}
+
+/// Run `command` without a shell nor a PTY on its own connection to the profile's host.
+/// Setting `cancel`, or running longer than RUN_TIMEOUT, closes the channel and keeps the output so far.
+pub async fn run(
+    profile: Profile,
+    password: Zeroizing<String>,
+    command: String,
+    cancel: Arc<AtomicBool>,
+) -> ExecResult {
+    let started = Instant::now();
+    let mut result = ExecResult::pending(&profile, &command);
+    result.running = false;
+
+    match exec(&profile, password, &command, &cancel).await {
+        Ok(output) => output.apply_to(&mut result),
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+async fn exec(
+    profile: &Profile,
+    password: Zeroizing<String>,
+    command: &str,
+    cancel: &AtomicBool,
+) -> Result<Output, ConnectError> {
+    let config = Arc::new(crate::ssh::client_config(profile));
+    let port = profile.port.parse().unwrap_or(22);
+    let rejected_key = RejectedKey::default();
+    let handler = ExecHandler {
+        host: profile.ip.clone(),
+        port,
+        rejected_key: rejected_key.clone(),
+    };
+
+    // bounded even when the profile has no connection timeout: a group run waits for every host
+    let mut handle = tokio::time::timeout(CONNECT_TIMEOUT, async {
+        let stream = crate::net::connect(&profile.ip, port, &profile.network).await?;
+        client::connect_stream(config, stream, handler)
+            .await
+            .map_err(|e| crate::ssh::handshake_error(&rejected_key, &profile.ip, port, e))
+    })
+    .await
+    .map_err(|_| ConnectError::Timeout {
+        host: profile.ip.clone(),
+        port,
+    })??;
+
+    crate::ssh::authenticate_password(&mut handle, profile, password.as_str()).await?;
+
+    let mut output = Output::default();
+    // cancelled while connecting: the command is not started
+    if cancel.load(Ordering::Relaxed) {
+        output.stopped = Some("Annulée".into());
+        let _ = handle.disconnect(russh::Disconnect::ByApplication, "", "").await;
+        return Ok(output);
+    }
+
+    let mut channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| ConnectError::ChannelOpen(e.to_string()))?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| ConnectError::ChannelOpen(format!("commande refusée ({})", e)))?;
+
+    // the channel ends once the server closes it, after the exit status
+    let started = Instant::now();
+    output.stopped = loop {
+        if cancel.load(Ordering::Relaxed) {
+            break Some("Annulée".into());
+        }
+        if started.elapsed() >= RUN_TIMEOUT {
+            break Some(format!("Arrêtée après {} s sans se terminer", RUN_TIMEOUT.as_secs()));
+        }
+        match tokio::time::timeout(CANCEL_POLL, channel.wait()).await {
+            Ok(Some(msg)) => output.push(msg),
+            Ok(None) => break None,
+            Err(_) => {}
+        }
+    };
+    if output.stopped.is_some() {
+        let _ = channel.close().await;
+    }
+
+    let _ = handle
+        .disconnect(russh::Disconnect::ByApplication, "", "")
+        .await;
+    Ok(output)
+}
+
+/// Human readable report, one block per host
+pub fn to_text(results: &[ExecResult]) -> String {
+    let mut out = String::new();
+    for r in results {
+        let status = match (&r.error, r.exit_status) {
+            (Some(e), _) => format!("erreur : {}", e),
+            (None, Some(code)) => format!("code de sortie {}", code),
+            (None, None) => "code de sortie inconnu".into(),
+        };
+        out.push_str(&format!("=== {} ({}) — {} — {} ms\n", r.profile, r.host, status, r.duration_ms));
+        out.push_str(&format!("$ {}\n", r.command));
+        if !r.stdout.is_empty() {
+            out.push_str("--- stdout ---\n");
+            out.push_str(r.stdout.trim_end());
+            out.push('\n');
+        }
+        if !r.stderr.is_empty() {
+            out.push_str("--- stderr ---\n");
+            out.push_str(r.stderr.trim_end());
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// One line per host, stdout and stderr kept whole in quoted fields
+pub fn to_csv(results: &[ExecResult]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let mut out = String::from("profile,host,command,exit_status,error,duration_ms,stdout,stderr\n");
+    for r in results {
+        let line = [
+            field(&r.profile),
+            field(&r.host),
+            field(&r.command),
+            r.exit_status.map(|code| code.to_string()).unwrap_or_default(),
+            field(r.error.as_deref().unwrap_or_default()),
+            r.duration_ms.to_string(),
+            field(&r.stdout),
+            field(&r.stderr),
+        ];
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Content of an export file in the given format
+pub fn render(results: &[ExecResult], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Text => Ok(to_text(results)),
+        ExportFormat::Csv => Ok(to_csv(results)),
+        ExportFormat::Json => serde_json::to_string_pretty(results).map_err(|e| e.to_string()),
+    }
+}
+
+/// Write the results in <exports folder>/rustty-exec-<date>-<time>.(txt|csv|json)
+pub async fn export(results: Vec<ExecResult>, format: ExportFormat) -> Result<PathBuf, String> {
+    let content = render(&results, format)?;
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let dir = exports_dir();
+    let path = dir.join(format!("rustty-exec-{}.{}", stamp, format.extension()));
+
+    let io_err = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    tokio::fs::create_dir_all(&dir).await.map_err(io_err)?;
+    tokio::fs::write(&path, content).await.map_err(io_err)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh::CryptoVec;
+
+    fn result(name: &str, command: &str) -> ExecResult {
+        let profile = Profile {
+            name: name.into(),
+            ip: "10.0.0.1".into(),
+            port: "22".into(),
+            ..Profile::default()
+        };
+        ExecResult::pending(&profile, command)
+    }
+
+    #[test]
+    fn output_is_collected_per_stream() {
+        let mut output = Output::default();
+        output.push(ChannelMsg::Data { data: CryptoVec::from_slice(b"caf\xc3") });
+        output.push(ChannelMsg::ExtendedData { data: CryptoVec::from_slice(b"warn\n"), ext: 1 });
+        output.push(ChannelMsg::Data { data: CryptoVec::from_slice(b"\xa9\n") });
+        // only type 1 is stderr
+        output.push(ChannelMsg::ExtendedData { data: CryptoVec::from_slice(b"other"), ext: 2 });
+        output.push(ChannelMsg::ExitStatus { exit_status: 3 });
+        output.push(ChannelMsg::Eof);
+
+        let mut r = result("web", "uptime");
+        r.running = false;
+        output.apply_to(&mut r);
+        assert_eq!(r.stdout, "café\n");
+        assert_eq!(r.stderr, "warn\n");
+        assert_eq!(r.exit_status, Some(3));
+        assert!(!r.success());
+    }
+
+    #[test]
+    fn stopped_commands_keep_their_output() {
+        let mut output = Output::default();
+        output.push(ChannelMsg::Data { data: CryptoVec::from_slice(b"line 1\n") });
+        output.stopped = Some("Annulée".into());
+
+        let mut r = result("web", "tail -f /var/log/syslog");
+        r.running = false;
+        output.apply_to(&mut r);
+        assert_eq!(r.stdout, "line 1\n");
+        assert_eq!(r.error.as_deref(), Some("Annulée"));
+        assert!(!r.success());
+    }
+
+    fn finished() -> Vec<ExecResult> {
+        let mut ok = result("web", "df -h, /");
+        ok.running = false;
+        ok.stdout = "line \"1\"\nline 2\n".into();
+        ok.exit_status = Some(0);
+        ok.duration_ms = 12;
+        let mut failed = result("db", "df -h, /");
+        failed.running = false;
+        failed.error = Some("Échec d'authentification".into());
+        failed.duration_ms = 40;
+        vec![ok, failed]
+    }
+
+    #[test]
+    fn text_report() {
+        assert_eq!(
+            render(&finished(), ExportFormat::Text).unwrap(),
+            "=== web (10.0.0.1:22) — code de sortie 0 — 12 ms\n\
+             $ df -h, /\n\
+             --- stdout ---\n\
+             line \"1\"\nline 2\n\n\
+             === db (10.0.0.1:22) — erreur : Échec d'authentification — 40 ms\n\
+             $ df -h, /\n\n"
+        );
+    }
+
+    #[test]
+    fn csv_report_quotes_fields() {
+        assert_eq!(
+            render(&finished(), ExportFormat::Csv).unwrap(),
+            "profile,host,command,exit_status,error,duration_ms,stdout,stderr\n\
+             web,10.0.0.1:22,\"df -h, /\",0,,12,\"line \"\"1\"\"\nline 2\n\",\n\
+             db,10.0.0.1:22,\"df -h, /\",,Échec d'authentification,40,,\n"
+        );
+    }
+
+    #[test]
+    fn json_report_skips_the_ui_state() {
+        let json = render(&finished(), ExportFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json[0]["stdout"], "line \"1\"\nline 2\n");
+        assert_eq!(json[0]["exit_status"], 0);
+        assert_eq!(json[1]["exit_status"], serde_json::Value::Null);
+        assert_eq!(json[1]["error"], "Échec d'authentification");
+        assert!(json[0].get("running").is_none());
+    }
+}
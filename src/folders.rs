@@ -0,0 +1,245 @@
+//! Folders of profiles: `Profile.group` holds a path such as "PROD/EU/DB"
+
+use crate::models::{FolderDefaults, Profile, Settings};
+
+/// Folder of the profiles saved without one
+pub const DEFAULT_FOLDER: &str = "DEFAUT";
+pub const SEPARATOR: char = '/';
+
+/// "prod / eu//" -> "PROD/EU", an empty path becomes DEFAUT
+pub fn normalize(path: &str) -> String {
+    let parts: Vec<String> = path
+        .split(SEPARATOR)
+        .map(|part| part.trim().to_uppercase())
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        DEFAULT_FOLDER.to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+/// `path` is `folder` or one of its sub-folders
+pub fn is_within(path: &str, folder: &str) -> bool {
+    path == folder
+        || path
+            .strip_prefix(folder)
+            .is_some_and(|rest| rest.starts_with(SEPARATOR))
+}
+
+/// Last part of the path, shown in the tree
+pub fn name(path: &str) -> &str {
+    path.rsplit(SEPARATOR).next().unwrap_or(path)
+}
+
+/// "PROD/EU/DB" -> "PROD/EU/DB", "PROD/EU", "PROD"
+pub fn lineage(path: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(path), |p| p.rsplit_once(SEPARATOR).map(|(parent, _)| parent))
+}
+
+/// Defaults of a folder, each value taken from the nearest folder that sets it
+pub fn defaults_for(settings: &Settings, path: &str) -> FolderDefaults {
+    let mut defaults = FolderDefaults::default();
+    for folder in lineage(path) {
+        if let Some(own) = settings.folders.get(folder) {
+            defaults.theme = defaults.theme.or(own.theme);
+            defaults.username = defaults.username.or_else(|| own.username.clone());
+        }
+    }
+    defaults
+}
+
+/// The profile with what it inherits from its folders
+pub fn resolve(profile: &Profile, settings: &Settings) -> Profile {
+    let defaults = defaults_for(settings, &profile.group);
+    let mut resolved = profile.clone();
+    if resolved.username.is_empty()
+        && let Some(username) = defaults.username
+    {
+        resolved.username = username;
+    }
+    if resolved.inherit_theme
+        && let Some(theme) = defaults.theme
+    {
+        resolved.theme = theme;
+    }
+    resolved
+}
+
+/// Move `folder` and its sub-folders under the new path, defaults included.
+/// Returns the number of profiles moved.
+pub fn rename(profiles: &mut [Profile], settings: &mut Settings, folder: &str, new_path: &str) -> usize {
+    let new_path = normalize(new_path);
+    let moved_path = |path: &str| format!("{}{}", new_path, &path[folder.len()..]);
+
+    let mut moved = 0;
+    for profile in profiles.iter_mut().filter(|p| is_within(&p.group, folder)) {
+        profile.group = moved_path(&profile.group);
+        moved += 1;
+    }
+
+    let keys: Vec<String> = settings
+        .folders
+        .keys()
+        .filter(|k| is_within(k, folder))
+        .cloned()
+        .collect();
+    for key in keys {
+        if let Some(defaults) = settings.folders.remove(&key) {
+            settings.folders.insert(moved_path(&key), defaults);
+        }
+    }
+    moved
+}
+
+/// A folder of the tree with its sub-folders and the indices of its profiles
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FolderNode {
+    pub path: String,
+    pub folders: Vec<FolderNode>,
+    pub profiles: Vec<usize>,
+}
+
+impl FolderNode {
+    /// Profiles of this folder and of its sub-folders
+    pub fn count(&self) -> usize {
+        self.profiles.len() + self.folders.iter().map(FolderNode::count).sum::<usize>()
+    }
+
+    fn child(&mut self, path: &str) -> &mut FolderNode {
+        let index = match self.folders.iter().position(|f| f.path == path) {
+            Some(index) => index,
+            None => {
+                self.folders.push(FolderNode {
+                    path: path.to_string(),
+                    ..FolderNode::default()
+                });
+                self.folders.len() - 1
+            }
+        };
+        &mut self.folders[index]
+    }
+
+    fn sort(&mut self) {
+        self.folders.sort_by(|a, b| a.path.cmp(&b.path));
+        self.folders.iter_mut().for_each(FolderNode::sort);
+    }
+}
+
+/// Tree of the profiles kept by `filter`; folders without any of them are left out
+pub fn tree(profiles: &[Profile], filter: impl Fn(&Profile) -> bool) -> FolderNode {
+    let mut root = FolderNode::default();
+    for (index, profile) in profiles.iter().enumerate().filter(|(_, p)| filter(p)) {
+        let mut node = &mut root;
+        let mut path = String::new();
+        for part in profile.group.split(SEPARATOR) {
+            if !path.is_empty() {
+                path.push(SEPARATOR);
+            }
+            path.push_str(part);
+            node = node.child(&path);
+        }
+        node.profiles.push(index);
+    }
+    root.sort();
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::theme::ThemeChoice;
+
+    fn profile(name: &str, group: &str) -> Profile {
+        Profile {
+            name: name.into(),
+            group: group.into(),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(normalize(" prod / eu//db "), "PROD/EU/DB");
+        assert_eq!(normalize("//"), DEFAULT_FOLDER);
+        assert!(is_within("PROD/EU", "PROD"));
+        assert!(is_within("PROD", "PROD"));
+        assert!(!is_within("PRODUCTION", "PROD"));
+        assert_eq!(name("PROD/EU"), "EU");
+        assert_eq!(lineage("A/B/C").collect::<Vec<_>>(), ["A/B/C", "A/B", "A"]);
+    }
+
+    #[test]
+    fn nearest_folder_defaults_win() {
+        let mut settings = Settings::default();
+        settings.folders.insert(
+            "PROD".into(),
+            FolderDefaults {
+                theme: Some(ThemeChoice::Dracula),
+                username: Some("ops".into()),
+                ..Default::default()
+            },
+        );
+        settings.folders.insert(
+            "PROD/EU".into(),
+            FolderDefaults {
+                username: Some("eu-ops".into()),
+                ..Default::default()
+            },
+        );
+
+        let mut db = profile("db", "PROD/EU/DB");
+        db.inherit_theme = true;
+        let resolved = resolve(&db, &settings);
+        assert_eq!(resolved.username, "eu-ops");
+        assert_eq!(resolved.theme, ThemeChoice::Dracula);
+
+        // an explicit user name and a profile that keeps its theme
+        let web = Profile {
+            username: "deploy".into(),
+            ..profile("web", "PROD")
+        };
+        let resolved = resolve(&web, &settings);
+        assert_eq!(resolved.username, "deploy");
+        assert_eq!(resolved.theme, web.theme);
+    }
+
+    #[test]
+    fn renaming_moves_sub_folders_and_defaults() {
+        let mut profiles = vec![
+            profile("a", "PROD/EU"),
+            profile("b", "PROD/EU/DB"),
+            profile("c", "PRODUCTION"),
+        ];
+        let mut settings = Settings::default();
+        settings.folders.insert("PROD/EU/DB".into(), FolderDefaults::default());
+
+        assert_eq!(rename(&mut profiles, &mut settings, "PROD/EU", "live/europe"), 2);
+        assert_eq!(profiles[0].group, "LIVE/EUROPE");
+        assert_eq!(profiles[1].group, "LIVE/EUROPE/DB");
+        assert_eq!(profiles[2].group, "PRODUCTION");
+        assert!(settings.folders.contains_key("LIVE/EUROPE/DB"));
+    }
+
+    #[test]
+    fn tree_nests_folders() {
+        let profiles = vec![
+            profile("web", "PROD/EU"),
+            profile("db", "PROD/EU/DB"),
+            profile("us", "PROD/US"),
+            profile("dev", "DEV"),
+        ];
+        let root = tree(&profiles, |p| p.name != "us");
+
+        let paths: Vec<&str> = root.folders.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["DEV", "PROD"]);
+        let prod = &root.folders[1];
+        assert_eq!(prod.count(), 2);
+        assert_eq!(prod.folders.len(), 1, "PROD/US has no kept profile");
+        let eu = &prod.folders[0];
+        assert_eq!(eu.profiles, [0]);
+        assert_eq!(eu.folders[0].path, "PROD/EU/DB");
+        assert_eq!(eu.folders[0].profiles, [1]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Fuzzy matching of the profile search: the characters of the query must appear in order,
+//! matches at the start of a word and runs of consecutive characters rank first.
+
+use crate::models::Profile;
+
+const MATCH: i32 = 16;
+const CONSECUTIVE: i32 = 12;
+const WORD_START: i32 = 10;
+/// Per character skipped between two matches, at most MAX_GAP_PENALTY each time
+const GAP: i32 = 1;
+const MAX_GAP_PENALTY: i32 = 8;
+
+/// Score of a match and the positions (in chars) of the matched characters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+/// `pattern` found in `candidate`, ignoring case; the best placement wins
+pub fn find(pattern: &str, candidate: &str) -> Option<Match> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    if pattern.is_empty() || pattern.len() > chars.len() {
+        return None;
+    }
+    let bonus = |i: usize| {
+        if i == 0 || !chars[i - 1].is_alphanumeric() {
+            MATCH + WORD_START
+        } else {
+            MATCH
+        }
+    };
+
+    // best[j][i]: score of the best placement of pattern[..=j] with pattern[j] on chars[i],
+    // and where pattern[j - 1] was placed
+    let mut best: Vec<Vec<Option<(i32, usize)>>> = vec![vec![None; chars.len()]; pattern.len()];
+    for (i, &c) in lower.iter().enumerate() {
+        if c == pattern[0] {
+            // earlier is better
+            best[0][i] = Some((bonus(i) - (i as i32).min(MAX_GAP_PENALTY), 0));
+        }
+    }
+    for j in 1..pattern.len() {
+        for i in j..chars.len() {
+            if lower[i] != pattern[j] {
+                continue;
+            }
+            best[j][i] = (j - 1..i)
+                .filter_map(|k| best[j - 1][k].map(|(score, _)| (score, k)))
+                .map(|(score, k)| {
+                    let gap = (i - k - 1) as i32;
+                    let link = if gap == 0 { CONSECUTIVE } else { -(gap * GAP).min(MAX_GAP_PENALTY) };
+                    (score + bonus(i) + link, k)
+                })
+                .max_by_key(|&(score, k)| (score, std::cmp::Reverse(k)));
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (score, mut i) = (0..chars.len())
+        .filter_map(|i| best[last][i].map(|(score, _)| (score, i)))
+        .max_by_key(|&(score, i)| (score, std::cmp::Reverse(i)))?;
+    let mut positions = vec![0; pattern.len()];
+    for j in (0..=last).rev() {
+        positions[j] = i;
+        if let Some((_, previous)) = best[j][i] {
+            i = previous;
+        }
+    }
+    Some(Match { score, positions })
+}
+
+/// Where a query matched a profile, for ranking and highlighting
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileMatch {
+    pub score: i32,
+    pub name: Vec<usize>,
+    pub group: Vec<usize>,
+    pub username: Vec<usize>,
+    pub ip: Vec<usize>,
+}
+
+/// Each word of the query must match the name, folder, user, address or a tag of the profile
+pub fn match_profile(profile: &Profile, query: &str) -> Option<ProfileMatch> {
+    let mut result = ProfileMatch::default();
+    for word in query.split_whitespace() {
+        let fields = [
+            (Some(&mut result.name), profile.name.as_str()),
+            (Some(&mut result.group), profile.group.as_str()),
+            (Some(&mut result.username), profile.username.as_str()),
+            (Some(&mut result.ip), profile.ip.as_str()),
+        ];
+        let mut best: Option<(Option<&mut Vec<usize>>, Match)> = None;
+        for (highlight, field) in fields {
+            if let Some(found) = find(word, field)
+                && best.as_ref().is_none_or(|(_, b)| found.score > b.score)
+            {
+                best = Some((highlight, found));
+            }
+        }
+        // tags aren't shown in the table, they only rank
+        for tag in &profile.tags {
+            if let Some(found) = find(word, tag)
+                && best.as_ref().is_none_or(|(_, b)| found.score > b.score)
+            {
+                best = Some((None, found));
+            }
+        }
+
+        let (highlight, found) = best?;
+        result.score += found.score;
+        if let Some(highlight) = highlight {
+            highlight.extend(found.positions);
+        }
+    }
+    for positions in [&mut result.name, &mut result.group, &mut result.username, &mut result.ip] {
+        positions.sort_unstable();
+        positions.dedup();
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_in_order_ignoring_case() {
+        assert_eq!(find("pdb", "Prod-DB").unwrap().positions, [0, 5, 6]);
+        assert!(find("bdp", "Prod-DB").is_none());
+        assert!(find("", "anything").is_none());
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_first() {
+        let run = find("web", "web-01").unwrap().score;
+        let word_starts = find("web", "www-east-backup").unwrap().score;
+        let scattered = find("web", "sweetbread").unwrap().score;
+        assert!(run > word_starts, "{} > {}", run, word_starts);
+        assert!(word_starts > scattered, "{} > {}", word_starts, scattered);
+
+        // the best placement is kept, not the first one
+        assert_eq!(find("db", "d-x-db").unwrap().positions, [4, 5]);
+    }
+
+    #[test]
+    fn every_word_must_match_a_field() {
+        let profile = Profile {
+            name: "frontal".into(),
+            group: "PROD/EU".into(),
+            ip: "10.0.0.5".into(),
+            username: "deploy".into(),
+            tags: vec!["nginx".into()],
+            ..Profile::default()
+        };
+        let found = match_profile(&profile, "eu front").unwrap();
+        assert_eq!(found.name, [0, 1, 2, 3, 4]);
+        assert_eq!(found.group, [5, 6]);
+        assert!(match_profile(&profile, "ngx").is_some());
+        assert!(match_profile(&profile, "front mysql").is_none());
+    }
+}
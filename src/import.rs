@@ -0,0 +1,335 @@
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+use crate::models::Profile;
+
+pub mod bundle;
+pub mod openssh;
+pub mod putty;
+
+/// Tool the imported file comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportSource {
+    #[default]
+    Putty,
+    OpenSsh,
+    /// Profiles exported by RustTy (JSON or TOML)
+    Bundle,
+}
+
+impl ImportSource {
+    pub const ALL: [ImportSource; 3] = [ImportSource::Putty, ImportSource::OpenSsh, ImportSource::Bundle];
+
+    /// File suggested when this source is picked
+    pub fn default_path(&self) -> Option<PathBuf> {
+        match self {
+            ImportSource::Putty | ImportSource::Bundle => None,
+            ImportSource::OpenSsh => Some(openssh::default_config_path()),
+        }
+    }
+}
+
+impl std::fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportSource::Putty => write!(f, "PuTTY (.reg)"),
+            ImportSource::OpenSsh => write!(f, "OpenSSH (~/.ssh/config)"),
+            ImportSource::Bundle => write!(f, "Bundle RustTy (.json, .toml)"),
+        }
+    }
+}
+
+/// What to do with an imported profile that already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Resolution {
+    #[default]
+    Skip,
+    /// Replace the existing profile, keeping its id
+    Overwrite,
+    /// Add the imported profile under another name
+    KeepBoth,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 3] = [Resolution::Skip, Resolution::Overwrite, Resolution::KeepBoth];
+}
+
+impl std::fmt::Display for Resolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resolution::Skip => write!(f, "Ignorer"),
+            Resolution::Overwrite => write!(f, "Remplacer"),
+            Resolution::KeepBoth => write!(f, "Garder les deux"),
+        }
+    }
+}
+
+/// A profile read from another tool, shown in the preview before being added
+#[derive(Debug, Clone)]
+pub struct ImportCandidate {
+    pub profile: Profile,
+    pub selected: bool,
+    /// Settings that could not be carried over
+    pub warnings: Vec<String>,
+    /// Existing profile with the same name or the same destination
+    pub duplicate_of: Option<Uuid>,
+    pub resolution: Resolution,
+}
+
+impl ImportCandidate {
+    pub fn new(profile: Profile, warnings: Vec<String>, existing: &[Profile]) -> Self {
+        let duplicate_of = find_duplicate(&profile, existing);
+        Self::with_duplicate(profile, warnings, duplicate_of)
+    }
+
+    pub fn with_duplicate(profile: Profile, warnings: Vec<String>, duplicate_of: Option<Uuid>) -> Self {
+        Self {
+            duplicate_of,
+            profile,
+            selected: true,
+            warnings,
+            resolution: Resolution::default(),
+        }
+    }
+}
+
+/// Key, forwardings and proxy are kept in the profile, but the connection doesn't use them yet:
+/// the host is reached directly and with the password
+pub fn not_applied(profile: &Profile) -> Vec<String> {
+    let key = profile.identity_file.iter().map(|key| format!("Clé {}", key));
+    let forwards = profile.forwards.iter().map(|forward| format!("Redirection {}", forward));
+    let proxy = profile.proxy.iter().map(|proxy| format!("Proxy {}", proxy));
+    key.chain(forwards)
+        .chain(proxy)
+        .map(|setting| format!("Pas encore appliqué par RustTy : {}", setting))
+        .collect()
+}
+
+/// Same name (case insensitive), or same protocol, user, host (or serial device) and port
+pub fn find_duplicate(profile: &Profile, existing: &[Profile]) -> Option<Uuid> {
+    existing
+        .iter()
+        .find(|p| {
+            p.name.eq_ignore_ascii_case(&profile.name)
+                || (p.protocol == profile.protocol
+                    && p.host().eq_ignore_ascii_case(profile.host())
+                    && p.port == profile.port
+                    && p.username == profile.username)
+        })
+        .map(|p| p.id)
+}
+
+/// Read the file of another tool and compare what it holds with the existing profiles
+pub async fn analyse(
+    source: ImportSource,
+    path: PathBuf,
+    existing: Vec<Profile>,
+) -> Result<Vec<ImportCandidate>, String> {
+    let read_err = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let from_other_tool = |parsed: Vec<(Profile, Vec<String>)>| {
+        parsed
+            .into_iter()
+            .map(|(profile, mut warnings)| {
+                warnings.extend(not_applied(&profile));
+                ImportCandidate::new(profile, warnings, &existing)
+            })
+            .collect()
+    };
+
+    match source {
+        ImportSource::Putty => {
+            let data = tokio::fs::read(&path).await.map_err(read_err)?;
+            Ok(from_other_tool(putty::parse(&data)?))
+        }
+        // Include lines read more files
+        ImportSource::OpenSsh => {
+            let config = path.clone();
+            let parsed = tokio::task::spawn_blocking(move || openssh::parse_file(&config))
+                .await
+                .map_err(|e| e.to_string())??;
+            Ok(from_other_tool(parsed))
+        }
+        // matched by id first: the same bundle imported again updates its profiles
+        ImportSource::Bundle => {
+            let content = tokio::fs::read_to_string(&path).await.map_err(read_err)?;
+            Ok(bundle::parse(&content, bundle::BundleFormat::from_path(&path))?
+                .into_iter()
+                .map(|profile| {
+                    let duplicate_of = bundle::find_match(&profile, &existing);
+                    let warnings = not_applied(&profile);
+                    ImportCandidate::with_duplicate(profile, warnings, duplicate_of)
+                })
+                .collect())
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ajouté(s), {} remplacé(s), {} ignoré(s)",
+            self.added, self.replaced, self.skipped
+        )
+    }
+}
+
+/// Add the selected candidates to `profiles`, following the resolution chosen for each duplicate
+pub fn apply(profiles: &mut Vec<Profile>, candidates: &[ImportCandidate]) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+
+    for candidate in candidates.iter().filter(|c| c.selected) {
+        let mut profile = candidate.profile.clone();
+        let existing = candidate
+            .duplicate_of
+            .and_then(|id| profiles.iter().position(|p| p.id == id));
+
+        match (existing, candidate.resolution) {
+            (None, _) => {
+                // bundles keep their ids, so that importing them again finds the same profiles
+                if profiles.iter().any(|p| p.id == profile.id) {
+                    profile.id = Uuid::new_v4();
+                }
+                profiles.push(profile);
+                summary.added += 1;
+            }
+            (Some(_), Resolution::Skip) => summary.skipped += 1,
+            (Some(index), Resolution::Overwrite) => {
+                let replaced = &profiles[index];
+                profile.id = replaced.id;
+                // usage of this installation
+                profile.favourite = replaced.favourite;
+                profile.last_connected = replaced.last_connected;
+                profile.connection_count = replaced.connection_count;
+                profiles[index] = profile;
+                summary.replaced += 1;
+            }
+            (Some(_), Resolution::KeepBoth) => {
+                profile.id = Uuid::new_v4();
+                profile.name = unique_name(&profile.name, profiles);
+                profiles.push(profile);
+                summary.added += 1;
+            }
+        }
+    }
+
+    profiles.sort_by(|a, b| a.group.cmp(&b.group).then(a.name.cmp(&b.name)));
+    summary
+}
+
+/// "web" -> "web (2)", "web (3)", ... for a name already taken
+pub fn unique_name(name: &str, profiles: &[Profile]) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !profiles.iter().any(|p| p.name.eq_ignore_ascii_case(candidate)))
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, ip: &str) -> Profile {
+        Profile {
+            name: name.into(),
+            ip: ip.into(),
+            username: "root".into(),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn duplicates_follow_their_resolution() {
+        let mut profiles = vec![profile("web", "10.0.0.1"), profile("db", "10.0.0.2")];
+        let web_id = profiles[0].id;
+
+        let mut candidates: Vec<ImportCandidate> = [
+            profile("WEB", "10.9.9.9"),    // same name
+            profile("database", "10.0.0.2"), // same destination
+            profile("cache", "10.0.0.3"),
+            profile("web", "10.0.0.1"),
+        ]
+        .into_iter()
+        .map(|p| ImportCandidate::new(p, Vec::new(), &profiles))
+        .collect();
+
+        assert_eq!(candidates[0].duplicate_of, Some(web_id));
+        assert!(candidates[1].duplicate_of.is_some());
+        assert_eq!(candidates[2].duplicate_of, None);
+
+        candidates[0].resolution = Resolution::Overwrite;
+        candidates[1].resolution = Resolution::Skip;
+        candidates[3].resolution = Resolution::KeepBoth;
+
+        let summary = apply(&mut profiles, &candidates);
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 2,
+                replaced: 1,
+                skipped: 1
+            }
+        );
+
+        let replaced = profiles.iter().find(|p| p.id == web_id).unwrap();
+        assert_eq!(replaced.ip, "10.9.9.9");
+        assert!(profiles.iter().any(|p| p.name == "cache"));
+        assert!(profiles.iter().any(|p| p.name == "web (2)"));
+        assert_eq!(profiles.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn keys_forwards_and_proxies_are_reported_as_not_applied() {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/putty/sessions.reg");
+        let candidates = analyse(ImportSource::Putty, fixture, Vec::new()).await.unwrap();
+        let web = candidates.iter().find(|c| c.profile.name == "web prod").unwrap();
+        let not_applied: Vec<&String> =
+            web.warnings.iter().filter(|w| w.starts_with("Pas encore appliqué par RustTy")).collect();
+
+        // the key, the three forwardings and the proxy
+        assert_eq!(not_applied.len(), 5, "{:?}", web.warnings);
+        assert!(not_applied[0].contains(": Clé "));
+        assert!(not_applied.iter().any(|w| w.contains("Redirection D 1080")));
+        assert!(not_applied.iter().any(|w| w.contains("Proxy SOCKS5 proxy.corp:1080")));
+    }
+
+    #[tokio::test]
+    async fn proxy_jump_is_reported_as_not_applied() {
+        let dir = std::env::temp_dir().join(format!("rustty-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config");
+        std::fs::write(
+            &config,
+            "Host db\n    HostName 10.0.0.5\n    IdentityFile ~/.ssh/db\n    ProxyJump jump@bastion\n    LocalForward 5432 localhost:5432\n",
+        )
+        .unwrap();
+
+        let candidates = analyse(ImportSource::OpenSsh, config, Vec::new()).await.unwrap();
+        assert_eq!(
+            candidates[0].warnings,
+            [
+                "Pas encore appliqué par RustTy : Clé ~/.ssh/db",
+                "Pas encore appliqué par RustTy : Redirection L 5432 → localhost:5432",
+                "Pas encore appliqué par RustTy : Proxy rebond SSH jump@bastion:22",
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unselected_candidates_are_left_out() {
+        let mut profiles = Vec::new();
+        let mut candidate = ImportCandidate::new(profile("web", "10.0.0.1"), Vec::new(), &profiles);
+        candidate.selected = false;
+        assert_eq!(apply(&mut profiles, &[candidate]), ImportSummary::default());
+        assert!(profiles.is_empty());
+    }
+}
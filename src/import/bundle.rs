@@ -0,0 +1,182 @@
+//! Profile bundles: a set of profiles shared between users, in JSON or TOML.
+//! Passwords never leave the vault, usage stats and favourites stay with their user,
+//! and the fields this build doesn't know are left out since nothing tells whether they hold secrets.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Map;
+use uuid::Uuid;
+
+use crate::models::Profile;
+
+/// Version of the bundle layout
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleFormat {
+    Json,
+    Toml,
+}
+
+impl BundleFormat {
+    /// TOML for a .toml file, JSON otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => BundleFormat::Toml,
+            _ => BundleFormat::Json,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    rustty_bundle: u32,
+    #[serde(default)]
+    created: String,
+    #[serde(default)]
+    profiles: Vec<Profile>,
+}
+
+/// Serialize the profiles without anything that could be a secret
+pub fn to_string(profiles: &[&Profile], format: BundleFormat) -> Result<String, String> {
+    let bundle = Bundle {
+        rustty_bundle: BUNDLE_VERSION,
+        created: chrono::Local::now().to_rfc3339(),
+        profiles: profiles
+            .iter()
+            .map(|&p| Profile {
+                favourite: false,
+                last_connected: None,
+                connection_count: 0,
+                extra: Map::new(),
+                ..p.clone()
+            })
+            .collect(),
+    };
+    match format {
+        BundleFormat::Json => serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string()),
+        BundleFormat::Toml => toml::to_string_pretty(&bundle).map_err(|e| e.to_string()),
+    }
+}
+
+/// Profiles of a bundle, keeping their ids so a later import finds them again
+pub fn parse(content: &str, format: BundleFormat) -> Result<Vec<Profile>, String> {
+    let bundle: Bundle = match format {
+        BundleFormat::Json => serde_json::from_str(content).map_err(|e| format!("Bundle illisible : {}", e))?,
+        BundleFormat::Toml => toml::from_str(content).map_err(|e| format!("Bundle illisible : {}", e))?,
+    };
+    if bundle.rustty_bundle > BUNDLE_VERSION {
+        return Err(format!(
+            "Bundle de version {} : mettez RustTy à jour pour l'importer",
+            bundle.rustty_bundle
+        ));
+    }
+    Ok(bundle
+        .profiles
+        .into_iter()
+        .map(|p| Profile { extra: Map::new(), ..p })
+        .collect())
+}
+
+/// Same id, or else same name and same host
+pub fn find_match(profile: &Profile, existing: &[Profile]) -> Option<Uuid> {
+    existing
+        .iter()
+        .find(|p| p.id == profile.id)
+        .or_else(|| {
+            existing.iter().find(|p| {
+                p.name.eq_ignore_ascii_case(&profile.name) && p.ip.eq_ignore_ascii_case(&profile.ip)
+            })
+        })
+        .map(|p| p.id)
+}
+
+/// Write the bundle, the format follows the extension
+pub async fn save(path: PathBuf, profiles: Vec<Profile>) -> Result<PathBuf, String> {
+    let refs: Vec<&Profile> = profiles.iter().collect();
+    let content = to_string(&refs, BundleFormat::from_path(&path))?;
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ForwardKind, PortForward, ProxyKind, ProxySettings};
+
+    fn sample() -> Profile {
+        let mut profile = Profile {
+            name: "db eu".into(),
+            ip: "10.1.0.5".into(),
+            username: "admin".into(),
+            group: "PROD".into(),
+            identity_file: Some("~/.ssh/prod".into()),
+            forwards: vec![PortForward {
+                kind: ForwardKind::Local,
+                bind_address: None,
+                bind_port: 5432,
+                host: "localhost".into(),
+                host_port: 5432,
+            }],
+            proxy: Some(ProxySettings {
+                kind: ProxyKind::Ssh,
+                host: "bastion".into(),
+                port: 22,
+                username: String::new(),
+                command: String::new(),
+            }),
+            ..Profile::default()
+        };
+        // written by a newer version, unknown here: it might be a secret
+        profile.extra.insert("token".into(), "s3cret".into());
+        profile
+    }
+
+    #[test]
+    fn both_formats_round_trip_without_unknown_fields() {
+        let profile = sample();
+        for format in [BundleFormat::Json, BundleFormat::Toml] {
+            let content = to_string(&[&profile], format).unwrap();
+            assert!(!content.contains("s3cret"), "{}", content);
+
+            let read = parse(&content, format).unwrap();
+            assert_eq!(read.len(), 1);
+            assert_eq!(read[0].id, profile.id);
+            assert_eq!(read[0].name, profile.name);
+            assert_eq!(read[0].forwards, profile.forwards);
+            assert_eq!(read[0].proxy, profile.proxy);
+            assert_eq!(read[0].identity_file, profile.identity_file);
+            assert!(read[0].extra.is_empty());
+        }
+    }
+
+    #[test]
+    fn matches_by_id_then_name_and_host() {
+        let profile = sample();
+        let renamed = Profile {
+            name: "renamed".into(),
+            ..profile.clone()
+        };
+        let same_name_and_host = Profile {
+            id: Uuid::new_v4(),
+            ..profile.clone()
+        };
+        let other_host = Profile {
+            id: Uuid::new_v4(),
+            ip: "10.1.0.6".into(),
+            ..profile.clone()
+        };
+
+        assert_eq!(find_match(&renamed, std::slice::from_ref(&profile)), Some(profile.id));
+        assert_eq!(find_match(&same_name_and_host, std::slice::from_ref(&profile)), Some(profile.id));
+        assert_eq!(find_match(&other_host, std::slice::from_ref(&profile)), None);
+    }
+
+    #[test]
+    fn newer_bundles_are_refused() {
+        assert!(parse(r#"{"rustty_bundle": 99, "profiles": []}"#, BundleFormat::Json).is_err());
+    }
+}
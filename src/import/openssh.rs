@@ -0,0 +1,576 @@
+//! OpenSSH client configuration (`~/.ssh/config`), read into profiles and written back from them
+
+use std::path::{Path, PathBuf};
+
+use crate::models::{ForwardKind, PortForward, Profile, ProxyKind, ProxySettings};
+
+/// Group given to the imported profiles
+const GROUP: &str = "SSH";
+/// Include nesting allowed before giving up (ssh itself stops at 16)
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// ~/.ssh, where relative Include paths and the default config live
+pub fn ssh_dir() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|dirs| dirs.home_dir().join(".ssh"))
+        .unwrap_or_else(|| PathBuf::from(".ssh"))
+}
+
+/// ~/.ssh/config
+pub fn default_config_path() -> PathBuf {
+    ssh_dir().join("config")
+}
+
+/// File the exported profiles are written to, meant to be pulled in with `Include rustty.conf`
+pub fn default_export_path() -> PathBuf {
+    ssh_dir().join("rustty.conf")
+}
+
+/// Write the exported snippet, the previous version is kept as a backup
+pub async fn save(path: PathBuf, content: String) -> Result<PathBuf, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::storage::write_atomic(&path, content.as_bytes())
+            .map(|_| path.clone())
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Resolves an Include pattern into (name, content) of each matching file
+pub type IncludeReader<'a> = dyn FnMut(&str) -> Result<Vec<(String, String)>, String> + 'a;
+
+/// A `Host` block: its patterns and its directives in file order
+#[derive(Debug)]
+struct Block {
+    patterns: Vec<String>,
+    directives: Vec<(String, Vec<String>)>,
+    /// Line of the file, for the messages
+    origin: String,
+}
+
+/// Read a config file and its includes, one profile per concrete host alias
+pub fn parse_file(path: &Path) -> Result<Vec<(Profile, Vec<String>)>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let base = path.parent().map(Path::to_path_buf).unwrap_or_else(ssh_dir);
+    parse(&content, &mut |pattern| read_includes(&base, pattern))
+}
+
+// every file matching the pattern, sorted by name like ssh does
+fn read_includes(base: &Path, pattern: &str) -> Result<Vec<(String, String)>, String> {
+    let pattern = match pattern.strip_prefix("~/") {
+        Some(rest) => directories::BaseDirs::new()
+            .map(|dirs| dirs.home_dir().join(rest))
+            .unwrap_or_else(|| PathBuf::from(rest)),
+        None => base.join(pattern),
+    };
+    let file_pattern = pattern
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let paths: Vec<PathBuf> = if file_pattern.contains(['*', '?']) {
+        let dir = pattern.parent().unwrap_or(base);
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file() && wildcard_match(&file_pattern, &p.file_name().unwrap_or_default().to_string_lossy()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+        paths
+    } else if pattern.is_file() {
+        vec![pattern]
+    } else {
+        Vec::new()
+    };
+
+    paths
+        .into_iter()
+        .map(|p| {
+            std::fs::read_to_string(&p)
+                .map(|content| (p.display().to_string(), content))
+                .map_err(|e| format!("{}: {}", p.display(), e))
+        })
+        .collect()
+}
+
+/// Parse a config whose `Include` lines are resolved by `include`
+pub fn parse(
+    content: &str,
+    include: &mut IncludeReader,
+) -> Result<Vec<(Profile, Vec<String>)>, String> {
+    // directives before the first Host apply to every host
+    let mut blocks = vec![Block {
+        patterns: vec!["*".into()],
+        directives: Vec::new(),
+        origin: "début du fichier".into(),
+    }];
+    let mut warnings = Vec::new();
+    read_lines(content, "config", include, 0, &mut blocks, &mut warnings)?;
+
+    let mut aliases: Vec<&str> = Vec::new();
+    for block in &blocks {
+        for pattern in &block.patterns {
+            if is_concrete(pattern) && !aliases.contains(&pattern.as_str()) {
+                aliases.push(pattern);
+            }
+        }
+    }
+    if aliases.is_empty() {
+        return Err("Aucun hôte (Host sans caractère générique) dans cette configuration".into());
+    }
+
+    Ok(aliases
+        .into_iter()
+        .map(|alias| {
+            let (profile, mut unmapped) = resolve(alias, &blocks);
+            let mut all = warnings.clone();
+            all.append(&mut unmapped);
+            (profile, all)
+        })
+        .collect())
+}
+
+fn read_lines(
+    content: &str,
+    file: &str,
+    include: &mut IncludeReader,
+    depth: usize,
+    blocks: &mut Vec<Block>,
+    warnings: &mut Vec<String>,
+) -> Result<(), String> {
+    for (number, line) in content.lines().enumerate() {
+        let Some((keyword, args)) = split_line(line) else {
+            continue;
+        };
+        let origin = format!("{}:{}", file, number + 1);
+        match keyword.as_str() {
+            "host" => blocks.push(Block {
+                patterns: args,
+                directives: Vec::new(),
+                origin,
+            }),
+            // conditions on the user, the command... can't be evaluated here
+            "match" => {
+                warnings.push(format!("{} : bloc Match ignoré", origin));
+                blocks.push(Block {
+                    patterns: Vec::new(),
+                    directives: Vec::new(),
+                    origin,
+                });
+            }
+            "include" => {
+                if depth >= MAX_INCLUDE_DEPTH {
+                    return Err(format!("{} : trop d'Include imbriqués", origin));
+                }
+                for pattern in &args {
+                    for (name, included) in include(pattern)? {
+                        read_lines(&included, &name, include, depth + 1, blocks, warnings)?;
+                    }
+                }
+            }
+            _ => {
+                if let Some(block) = blocks.last_mut() {
+                    block.directives.push((keyword, args));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// "Keyword value", "Keyword=value", quoted arguments, # comments; the keyword is lowercased
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let keyword = line[..end].to_ascii_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+fn is_concrete(pattern: &str) -> bool {
+    !pattern.contains(['*', '?', '!'])
+}
+
+// a negated pattern that matches excludes the host from the whole block
+fn block_matches(block: &Block, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in &block.patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, alias) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, alias),
+        }
+    }
+    matched
+}
+
+// '*' any run of characters, '?' exactly one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// ssh keeps the first value of each directive, except the ones that can be repeated
+fn resolve(alias: &str, blocks: &[Block]) -> (Profile, Vec<String>) {
+    let mut profile = Profile {
+        name: alias.to_string(),
+        ip: alias.to_string(),
+        group: GROUP.into(),
+        ..Profile::default()
+    };
+    let mut warnings = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+
+    for block in blocks.iter().filter(|b| block_matches(b, alias)) {
+        for (keyword, args) in &block.directives {
+            let repeatable = matches!(
+                keyword.as_str(),
+                "identityfile" | "localforward" | "remoteforward" | "dynamicforward"
+            );
+            if !repeatable && seen.contains(&keyword.as_str()) {
+                continue;
+            }
+            seen.push(keyword);
+
+            let value = args.join(" ");
+            let mapped = match keyword.as_str() {
+                "hostname" => {
+                    profile.ip = value.replace("%h", alias);
+                    true
+                }
+                "user" => {
+                    profile.username = value;
+                    true
+                }
+                "port" => value.parse::<u16>().map(|port| profile.port = port.to_string()).is_ok(),
+                "identityfile" => {
+                    if profile.identity_file.is_none() {
+                        profile.identity_file = Some(value);
+                    } else {
+                        warnings.push(format!("IdentityFile supplémentaire ignoré : {}", value));
+                    }
+                    true
+                }
+                "proxyjump" => proxy_jump(&value, &mut profile, &mut warnings),
+                "proxycommand" if value != "none" => {
+                    profile.proxy = Some(ProxySettings {
+                        kind: ProxyKind::Command,
+                        host: String::new(),
+                        port: 0,
+                        username: String::new(),
+                        command: value,
+                    });
+                    true
+                }
+                "localforward" | "remoteforward" | "dynamicforward" => {
+                    match forward(keyword, args) {
+                        Some(f) => profile.forwards.push(f),
+                        None => warnings.push(format!("Redirection invalide : {} {}", keyword, value)),
+                    }
+                    true
+                }
+                _ => false,
+            };
+            if !mapped {
+                warnings.push(format!("Directive non reprise ({}) : {} {}", block.origin, keyword, args.join(" ")));
+            }
+        }
+    }
+    (profile, warnings)
+}
+
+// "[user@]host[:port]", several hops separated by commas
+fn proxy_jump(value: &str, profile: &mut Profile, warnings: &mut Vec<String>) -> bool {
+    if value == "none" {
+        return true;
+    }
+    let mut hops = value.split(',');
+    let Some(first) = hops.next() else {
+        return false;
+    };
+    if hops.next().is_some() {
+        warnings.push(format!("ProxyJump : seul le premier rebond est repris ({})", value));
+    }
+    let (username, host) = match first.rsplit_once('@') {
+        Some((user, host)) => (user.to_string(), host),
+        None => (String::new(), first),
+    };
+    let (host, port) = split_host_port(host);
+    profile.proxy = Some(ProxySettings {
+        kind: ProxyKind::Ssh,
+        host,
+        port: port.unwrap_or(22),
+        username,
+        command: String::new(),
+    });
+    true
+}
+
+// "host:port", "[::1]:port" or "host"
+fn split_host_port(s: &str) -> (String, Option<u16>) {
+    if let Some(rest) = s.strip_prefix('[')
+        && let Some((host, port)) = rest.split_once(']')
+    {
+        return (host.to_string(), port.strip_prefix(':').and_then(|p| p.parse().ok()));
+    }
+    match s.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => (host.to_string(), port.parse().ok()),
+        _ => (s.to_string(), None),
+    }
+}
+
+// LocalForward [bind:]port host:hostport, DynamicForward [bind:]port
+fn forward(keyword: &str, args: &[String]) -> Option<PortForward> {
+    let kind = match keyword {
+        "localforward" => ForwardKind::Local,
+        "remoteforward" => ForwardKind::Remote,
+        _ => ForwardKind::Dynamic,
+    };
+    let (bind_address, bind_port) = match split_host_port(args.first()?) {
+        (port, None) => (None, port.parse().ok()?),
+        (address, Some(port)) => (Some(address), port),
+    };
+    let (host, host_port) = match kind {
+        ForwardKind::Dynamic => (String::new(), 0),
+        _ => match split_host_port(args.get(1)?) {
+            (host, Some(port)) => (host, port),
+            _ => return None,
+        },
+    };
+    Some(PortForward {
+        kind,
+        bind_address,
+        bind_port,
+        host,
+        host_port,
+    })
+}
+
+/// Config snippet with one Host block per profile
+pub fn to_config(profiles: &[&Profile]) -> String {
+    let mut out = format!(
+        "# Généré par RustTy le {}\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    for profile in profiles {
+        out.push('\n');
+        out.push_str(&format!("Host {}\n", alias(&profile.name)));
+        out.push_str(&format!("    HostName {}\n", profile.ip));
+        if !profile.username.is_empty() {
+            out.push_str(&format!("    User {}\n", profile.username));
+        }
+        if profile.port != "22" && !profile.port.is_empty() {
+            out.push_str(&format!("    Port {}\n", profile.port));
+        }
+        if let Some(key) = &profile.identity_file {
+            out.push_str(&format!("    IdentityFile {}\n", quote(key)));
+        }
+        match &profile.proxy {
+            Some(proxy) if proxy.kind == ProxyKind::Ssh => {
+                let user = if proxy.username.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}@", proxy.username)
+                };
+                out.push_str(&format!("    ProxyJump {}{}:{}\n", user, proxy.host, proxy.port));
+            }
+            Some(proxy) if proxy.kind == ProxyKind::Command => {
+                out.push_str(&format!("    ProxyCommand {}\n", proxy.command));
+            }
+            Some(proxy) => out.push_str(&format!(
+                "    # proxy {:?} {}:{} non exprimable dans ssh_config\n",
+                proxy.kind, proxy.host, proxy.port
+            )),
+            None => {}
+        }
+        for forward in &profile.forwards {
+            let bind = match &forward.bind_address {
+                Some(address) => format!("{}:{}", address, forward.bind_port),
+                None => forward.bind_port.to_string(),
+            };
+            let line = match forward.kind {
+                ForwardKind::Local => format!("LocalForward {} {}:{}", bind, forward.host, forward.host_port),
+                ForwardKind::Remote => format!("RemoteForward {} {}:{}", bind, forward.host, forward.host_port),
+                ForwardKind::Dynamic => format!("DynamicForward {}", bind),
+            };
+            out.push_str(&format!("    {}\n", line));
+        }
+    }
+    out
+}
+
+// a Host pattern can't hold spaces nor wildcards
+fn alias(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_whitespace() || matches!(c, '*' | '?' | '!' | ',') { '-' } else { c })
+        .collect()
+}
+
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# global defaults
+User nobody
+
+Host web web-backup
+    HostName %h.example.com
+    Port 2222
+    IdentityFile ~/.ssh/web
+    IdentityFile ~/.ssh/other
+    LocalForward 8080 localhost:80
+    LocalForward 127.0.0.1:5432 db.internal:5432
+    ForwardAgent yes
+
+Host db
+    HostName=10.0.0.5
+    User admin
+    ProxyJump jump@bastion.example.com:2200
+
+Include conf.d/*
+
+Host web* !web-backup
+    Compression yes
+
+Host *
+    User fallback
+    ServerAliveInterval 30
+";
+
+    const INCLUDED: &str = "\
+Host cache
+    HostName 10.0.0.9
+    DynamicForward 1080
+";
+
+    fn parsed() -> Vec<(Profile, Vec<String>)> {
+        parse(CONFIG, &mut |pattern| {
+            assert_eq!(pattern, "conf.d/*");
+            Ok(vec![("conf.d/cache".into(), INCLUDED.into())])
+        })
+        .unwrap()
+    }
+
+    fn find<'a>(profiles: &'a [(Profile, Vec<String>)], name: &str) -> &'a (Profile, Vec<String>) {
+        profiles.iter().find(|(p, _)| p.name == name).unwrap()
+    }
+
+    #[test]
+    fn host_blocks_become_profiles() {
+        let profiles = parsed();
+        let names: Vec<&str> = profiles.iter().map(|(p, _)| p.name.as_str()).collect();
+        assert_eq!(names, ["web", "web-backup", "db", "cache"]);
+
+        let (web, warnings) = find(&profiles, "web");
+        assert_eq!(web.ip, "web.example.com");
+        assert_eq!(web.port, "2222");
+        // the first value wins: the global User comes before the Host * block
+        assert_eq!(web.username, "nobody");
+        assert_eq!(web.identity_file.as_deref(), Some("~/.ssh/web"));
+        assert_eq!(web.forwards.len(), 2);
+        assert_eq!(web.forwards[1].bind_address.as_deref(), Some("127.0.0.1"));
+        assert_eq!(web.forwards[1].host, "db.internal");
+        // unmapped directives are reported, with the negated pattern respected
+        assert!(warnings.iter().any(|w| w.contains("forwardagent yes")));
+        assert!(warnings.iter().any(|w| w.contains("serveraliveinterval 30")));
+        assert!(warnings.iter().any(|w| w.contains("~/.ssh/other")));
+        assert!(warnings.iter().any(|w| w.contains("compression")));
+        let (_, backup_warnings) = find(&profiles, "web-backup");
+        assert!(!backup_warnings.iter().any(|w| w.contains("compression")));
+
+        let (db, _) = find(&profiles, "db");
+        assert_eq!(db.ip, "10.0.0.5");
+        assert_eq!(db.username, "nobody");
+        let proxy = db.proxy.as_ref().unwrap();
+        assert_eq!(proxy.kind, ProxyKind::Ssh);
+        assert_eq!((proxy.username.as_str(), proxy.host.as_str(), proxy.port), ("jump", "bastion.example.com", 2200));
+
+        let (cache, _) = find(&profiles, "cache");
+        assert_eq!(cache.forwards[0].kind, ForwardKind::Dynamic);
+        assert_eq!(cache.forwards[0].bind_port, 1080);
+    }
+
+    #[test]
+    fn exported_profiles_read_back_the_same() {
+        let profiles = parsed();
+        let originals: Vec<&Profile> = profiles.iter().map(|(p, _)| p).collect();
+        let snippet = to_config(&originals);
+
+        let reread = parse(&snippet, &mut |_| Ok(Vec::new())).unwrap();
+        assert_eq!(reread.len(), originals.len());
+        for ((copy, warnings), original) in reread.iter().zip(&originals) {
+            assert!(warnings.is_empty(), "{:?}", warnings);
+            assert_eq!(copy.name, original.name);
+            assert_eq!(copy.ip, original.ip);
+            assert_eq!(copy.port, original.port);
+            assert_eq!(copy.username, original.username);
+            assert_eq!(copy.identity_file, original.identity_file);
+            assert_eq!(copy.forwards, original.forwards);
+            assert_eq!(copy.proxy, original.proxy);
+        }
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("*.example.com", "web.EXAMPLE.com"));
+        assert!(wildcard_match("db?", "db1"));
+        assert!(!wildcard_match("db?", "db12"));
+        assert!(wildcard_match("*", ""));
+        assert_eq!(alias("web prod"), "web-prod");
+    }
+}
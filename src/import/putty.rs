@@ -0,0 +1,419 @@
+//! Sessions exported from the registry by PuTTY users:
+//! `reg export "HKCU\Software\SimonTatham\PuTTY\Sessions" putty.reg`
+
+use std::collections::BTreeMap;
+
+use iced::Color;
+
+use crate::models::{
+    FlowControl, ForwardKind, Parity, PortForward, Profile, Protocol, ProxyKind, ProxySettings, SerialSettings,
+};
+use crate::ui::theme::ThemeChoice;
+
+const SESSIONS_KEY: &str = r"HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\";
+/// Group given to the imported profiles
+const GROUP: &str = "PUTTY";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RegValue {
+    Text(String),
+    Dword(u32),
+}
+
+/// One PuTTY session with its values
+#[derive(Debug, Default)]
+struct Session {
+    name: String,
+    values: BTreeMap<String, RegValue>,
+}
+
+impl Session {
+    fn text(&self, key: &str) -> Option<&str> {
+        match self.values.get(key) {
+            Some(RegValue::Text(value)) if !value.is_empty() => Some(value),
+            _ => None,
+        }
+    }
+
+    fn dword(&self, key: &str) -> Option<u32> {
+        match self.values.get(key) {
+            Some(RegValue::Dword(value)) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Read a .reg file (UTF-16 as written by regedit, or UTF-8) and turn each session into a profile,
+/// with the settings that could not be carried over
+pub fn parse(data: &[u8]) -> Result<Vec<(Profile, Vec<String>)>, String> {
+    let content = decode(data)?;
+    let sessions = sessions(&content);
+    if sessions.is_empty() {
+        return Err("Aucune session PuTTY trouvée dans ce fichier".into());
+    }
+    Ok(sessions.iter().map(to_profile).collect())
+}
+
+fn decode(data: &[u8]) -> Result<String, String> {
+    match data {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).map_err(|e| format!("Fichier .reg illisible : {}", e))
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8(rest.to_vec()).map_err(|e| e.to_string()),
+        _ => String::from_utf8(data.to_vec()).map_err(|e| format!("Fichier .reg illisible : {}", e)),
+    }
+}
+
+fn sessions(content: &str) -> Vec<Session> {
+    let mut sessions: Vec<Session> = Vec::new();
+    // false inside sections that are not PuTTY sessions
+    let mut in_session = false;
+
+    for line in content.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_session = false;
+            // "[-HKEY...]" deletes a key
+            if let Some(name) = section.strip_prefix(SESSIONS_KEY)
+                && !name.is_empty()
+                && !name.contains('\\')
+            {
+                sessions.push(Session {
+                    name: percent_decode(name),
+                    ..Session::default()
+                });
+                in_session = true;
+            }
+            continue;
+        }
+        if !in_session {
+            continue;
+        }
+        if let Some((key, value)) = value_line(line)
+            && let Some(session) = sessions.last_mut()
+        {
+            session.values.insert(key, value);
+        }
+    }
+    sessions
+}
+
+// "Key"="text" or "Key"=dword:00000016, other types (hex:...) are not used by the import
+fn value_line(line: &str) -> Option<(String, RegValue)> {
+    let (key, rest) = quoted(line.strip_prefix('"')?)?;
+    let rest = rest.strip_prefix('=')?;
+    let value = if let Some(text) = rest.strip_prefix('"') {
+        RegValue::Text(quoted(text)?.0)
+    } else {
+        RegValue::Dword(u32::from_str_radix(rest.strip_prefix("dword:")?, 16).ok()?)
+    };
+    Some((key, value))
+}
+
+// read up to the closing quote, with \\ and \" escapes; returns the text and what follows
+fn quoted(s: &str) -> Option<(String, &str)> {
+    let mut out = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => out.push(chars.next()?.1),
+            '"' => return Some((out, &s[i + 1..])),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+// PuTTY escapes the session names: "my%20server"
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn to_profile(session: &Session) -> (Profile, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut profile = Profile {
+        name: session.name.clone(),
+        group: GROUP.into(),
+        ..Profile::default()
+    };
+
+    // "user@host" is accepted in the host field
+    let host = session.text("HostName").unwrap_or_default();
+    match host.rsplit_once('@') {
+        Some((user, host)) => {
+            profile.username = user.to_string();
+            profile.ip = host.to_string();
+        }
+        None => profile.ip = host.to_string(),
+    }
+    if let Some(user) = session.text("UserName") {
+        profile.username = user.to_string();
+    }
+    if let Some(port) = session.dword("PortNumber") {
+        profile.port = port.to_string();
+    }
+    match session.text("Protocol").unwrap_or("ssh") {
+        "ssh" => {}
+        "telnet" => profile.protocol = Protocol::Telnet,
+        "raw" => profile.protocol = Protocol::Raw,
+        "serial" => {
+            profile.protocol = Protocol::Serial;
+            serial(session, &mut profile.serial, &mut warnings);
+        }
+        other => warnings.push(format!("Protocole {} non supporté", other)),
+    }
+    if profile.ip.is_empty() && profile.protocol != Protocol::Serial {
+        warnings.push("Aucun hôte".into());
+    }
+
+    profile.identity_file = session.text("PublicKeyFile").map(str::to_string);
+    if let Some(forwards) = session.text("PortForwardings") {
+        for spec in forwards.split(',').filter(|s| !s.is_empty()) {
+            match port_forward(spec) {
+                Some(forward) => profile.forwards.push(forward),
+                None => warnings.push(format!("Redirection ignorée : {}", spec)),
+            }
+        }
+    }
+    match proxy(session) {
+        Ok(proxy) => profile.proxy = proxy,
+        Err(warning) => warnings.push(warning),
+    }
+    if let (Some(fg), Some(bg)) = (
+        session.text("Colour0").and_then(rgb),
+        session.text("Colour2").and_then(rgb),
+    ) {
+        profile.theme = ThemeChoice::closest(bg, fg);
+    }
+
+    (profile, warnings)
+}
+
+// SerialLine is "COM3" or "/dev/ttyUSB0"; the missing keys keep the defaults of RustTy
+fn serial(session: &Session, settings: &mut SerialSettings, warnings: &mut Vec<String>) {
+    match session.text("SerialLine") {
+        Some(line) => settings.device = line.to_string(),
+        None => warnings.push("Aucun port série".into()),
+    }
+    if let Some(speed) = session.dword("SerialSpeed") {
+        settings.baud_rate = speed;
+    }
+    if let Some(bits) = session.dword("SerialDataBits") {
+        match u8::try_from(bits).ok().filter(|b| SerialSettings::DATA_BITS.contains(b)) {
+            Some(bits) => settings.data_bits = bits,
+            None => warnings.push(format!("{} bits de données non supportés", bits)),
+        }
+    }
+    // in half bits: 2 is one stop bit, 4 is two
+    match session.dword("SerialStopHalfbits") {
+        None | Some(2) => {}
+        Some(4) => settings.stop_bits = 2,
+        Some(_) => warnings.push("1,5 bit de stop non supporté, 1 utilisé".into()),
+    }
+    match session.dword("SerialParity") {
+        None | Some(0) => {}
+        Some(1) => settings.parity = Parity::Odd,
+        Some(2) => settings.parity = Parity::Even,
+        Some(_) => warnings.push("Parité mark/space non supportée, aucune utilisée".into()),
+    }
+    match session.dword("SerialFlowControl") {
+        None | Some(0) => {}
+        Some(1) => settings.flow_control = FlowControl::Software,
+        Some(2) => settings.flow_control = FlowControl::Hardware,
+        Some(_) => warnings.push("Contrôle de flux DSR/DTR non supporté, aucun utilisé".into()),
+    }
+}
+
+// "L8080=localhost:80", "4R127.0.0.1:2222=db:22", "D1080"
+fn port_forward(spec: &str) -> Option<PortForward> {
+    // the optional 4/6 prefix only restricts the address family
+    let spec = spec.trim_start_matches(['4', '6']);
+    let mut chars = spec.chars();
+    let kind = match chars.next()? {
+        'L' => ForwardKind::Local,
+        'R' => ForwardKind::Remote,
+        'D' => ForwardKind::Dynamic,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (bind, destination) = match rest.split_once('=') {
+        Some((bind, destination)) => (bind, Some(destination)),
+        None => (rest, None),
+    };
+    let (bind_address, bind_port) = match bind.rsplit_once(':') {
+        Some((address, port)) => (Some(address.to_string()), port.parse().ok()?),
+        None => (None, bind.parse().ok()?),
+    };
+
+    let (host, host_port) = match (kind, destination) {
+        (ForwardKind::Dynamic, _) => (String::new(), 0),
+        (_, Some(destination)) => {
+            let (host, port) = destination.rsplit_once(':')?;
+            (host.to_string(), port.parse().ok()?)
+        }
+        (_, None) => return None,
+    };
+    Some(PortForward {
+        kind,
+        bind_address,
+        bind_port,
+        host,
+        host_port,
+    })
+}
+
+fn proxy(session: &Session) -> Result<Option<ProxySettings>, String> {
+    let kind = match session.dword("ProxyMethod").unwrap_or(0) {
+        0 => return Ok(None),
+        1 => ProxyKind::Socks4,
+        2 => ProxyKind::Socks5,
+        3 => ProxyKind::Http,
+        4 => ProxyKind::Telnet,
+        5 => ProxyKind::Command,
+        6 => ProxyKind::Ssh,
+        other => return Err(format!("Type de proxy {} inconnu", other)),
+    };
+    Ok(Some(ProxySettings {
+        kind,
+        host: session.text("ProxyHost").unwrap_or_default().to_string(),
+        port: session
+            .dword("ProxyPort")
+            .and_then(|p| u16::try_from(p).ok())
+            .unwrap_or_default(),
+        username: session.text("ProxyUsername").unwrap_or_default().to_string(),
+        command: session.text("ProxyTelnetCommand").unwrap_or_default().to_string(),
+    }))
+}
+
+// "255,255,255"
+fn rgb(value: &str) -> Option<Color> {
+    let mut parts = value.split(',').map(|p| p.trim().parse::<u8>());
+    let color = Color::from_rgb8(parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?);
+    parts.next().is_none().then_some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &[u8] = include_bytes!("../../tests/fixtures/putty/sessions.reg");
+    const EXPORT_UTF16: &[u8] = include_bytes!("../../tests/fixtures/putty/sessions-utf16.reg");
+
+    fn find<'a>(profiles: &'a [(Profile, Vec<String>)], name: &str) -> &'a (Profile, Vec<String>) {
+        profiles.iter().find(|(p, _)| p.name == name).unwrap()
+    }
+
+    #[test]
+    fn sessions_are_mapped_onto_profiles() {
+        let profiles = parse(EXPORT).unwrap();
+        // "Default Settings" is a session like the others in PuTTY, the other keys are ignored
+        assert_eq!(profiles.len(), 7);
+
+        let (web, warnings) = find(&profiles, "web prod");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(web.ip, "web.example.com");
+        assert_eq!(web.port, "2222");
+        assert_eq!(web.username, "deploy");
+        assert_eq!(web.group, "PUTTY");
+        assert_eq!(web.identity_file.as_deref(), Some(r"C:\Users\me\.ssh\web.ppk"));
+        assert_eq!(
+            web.forwards,
+            vec![
+                PortForward {
+                    kind: ForwardKind::Local,
+                    bind_address: None,
+                    bind_port: 8080,
+                    host: "localhost".into(),
+                    host_port: 80,
+                },
+                PortForward {
+                    kind: ForwardKind::Remote,
+                    bind_address: Some("127.0.0.1".into()),
+                    bind_port: 2222,
+                    host: "db".into(),
+                    host_port: 22,
+                },
+                PortForward {
+                    kind: ForwardKind::Dynamic,
+                    bind_address: None,
+                    bind_port: 1080,
+                    host: String::new(),
+                    host_port: 0,
+                },
+            ]
+        );
+        let proxy = web.proxy.as_ref().unwrap();
+        assert_eq!(proxy.kind, ProxyKind::Socks5);
+        assert_eq!((proxy.host.as_str(), proxy.port), ("proxy.corp", 1080));
+        // white on black
+        assert_eq!(web.theme, ThemeChoice::closest(Color::BLACK, Color::WHITE));
+
+        let (db, _) = find(&profiles, "db/1");
+        assert_eq!((db.username.as_str(), db.ip.as_str()), ("admin", "10.0.0.5"));
+        assert!(db.proxy.is_none());
+
+        let (_, warnings) = find(&profiles, "bastion");
+        assert!(warnings.iter().any(|w| w.contains("rlogin")));
+    }
+
+    #[test]
+    fn telnet_raw_and_serial_sessions() {
+        let profiles = parse(EXPORT).unwrap();
+
+        let (router, warnings) = find(&profiles, "router");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(router.protocol, Protocol::Telnet);
+        assert_eq!((router.ip.as_str(), router.port.as_str()), ("192.168.0.1", "23"));
+
+        let (printer, warnings) = find(&profiles, "printer");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(printer.protocol, Protocol::Raw);
+        assert_eq!(printer.port, "9100");
+
+        let (console, warnings) = find(&profiles, "switch console");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(console.protocol, Protocol::Serial);
+        assert_eq!(
+            console.serial,
+            SerialSettings {
+                device: "COM3".into(),
+                baud_rate: 9600,
+                data_bits: 7,
+                parity: Parity::Even,
+                stop_bits: 2,
+                flow_control: FlowControl::Hardware,
+                ..SerialSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn utf16_exports_are_decoded() {
+        let profiles = parse(EXPORT_UTF16).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].0.name, "café");
+        assert_eq!(profiles[0].0.ip, "192.168.1.10");
+    }
+
+    #[test]
+    fn files_without_sessions_are_rejected() {
+        assert!(parse(b"Windows Registry Editor Version 5.00\r\n\r\n[HKEY_CURRENT_USER\\Software\\Other]\r\n").is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Local sessions: the user's login shell in a pseudo-terminal, shown like a remote one.
+
+use crate::messages::{Message, SshMessage};
+use crate::session::{SessionChannel, SessionEnd};
+use iced::{
+    Task,
+    futures::{SinkExt, channel::mpsc},
+};
+
+pub use pty::LocalPty;
+
+pub struct LocalService;
+
+impl LocalService {
+    /// Start the login shell for a terminal window: its channel, then its output,
+    /// then its exit status once the process is reaped
+    pub fn open(window_id: iced::window::Id, rows: u16, cols: u16) -> Task<Message> {
+        Task::stream(iced::stream::channel(100, move |mut output| async move {
+            let (pty, child) = match pty::spawn(pty::login_shell(), rows, cols) {
+                Ok(spawned) => spawned,
+                Err(e) => {
+                    let error = format!("Impossible de lancer le shell local : {}", e);
+                    let _ = output
+                        .send(Message::Ssh(SshMessage::SessionEnded(window_id, SessionEnd::lost(error))))
+                        .await;
+                    return;
+                }
+            };
+            tracing::info!("Shell local lancé (pid {}) pour {:?}", pty.pid(), window_id);
+            let _ = output
+                .send(Message::Ssh(SshMessage::SetChannel(window_id, SessionChannel::Local(pty.clone()))))
+                .await;
+            let status = match forward_output(&pty, child, window_id, &mut output).await {
+                Some(code) => format!("Processus terminé : code {}", code),
+                None => "Processus terminé".into(),
+            };
+            let _ = output
+                .send(Message::Ssh(SshMessage::SessionEnded(window_id, SessionEnd::expected(status))))
+                .await;
+        }))
+    }
+}
+
+/// Output of the shell until its side of the terminal is closed, then its exit code
+async fn forward_output(
+    pty: &LocalPty,
+    child: pty::Child,
+    window_id: iced::window::Id,
+    output: &mut mpsc::Sender<Message>,
+) -> Option<i32> {
+    let mut buf = vec![0; 8192];
+    loop {
+        match pty.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let _ = output
+                    .send(Message::Ssh(SshMessage::DataReceived(window_id, buf[..n].to_vec())))
+                    .await;
+            }
+        }
+    }
+    pty::wait(child).await
+}
+
+#[cfg(unix)]
+mod pty {
+    use std::io;
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Stdio;
+    use tokio::process::Command;
+
+    use crate::tty::{AsyncTty, set_flags};
+
+    pub use tokio::process::Child;
+
+    /// Master side of a pseudo-terminal, shared by the reader and the window
+    #[derive(Clone)]
+    pub struct LocalPty {
+        master: AsyncTty,
+        pid: u32,
+    }
+
+    /// $SHELL (or /bin/sh) started as a login shell in the home folder
+    pub fn login_shell() -> Command {
+        let shell = std::env::var("SHELL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "/bin/sh".into());
+        let name = std::path::Path::new(&shell)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "sh".into());
+
+        let mut command = Command::new(&shell);
+        // a leading '-' in argv[0] is how a shell knows it is a login shell
+        command.arg0(format!("-{}", name));
+        if let Some(dirs) = directories::BaseDirs::new() {
+            command.current_dir(dirs.home_dir());
+        }
+        command
+    }
+
+    /// Run `command` on the slave side of a new pseudo-terminal of `rows` x `cols`
+    pub fn spawn(mut command: Command, rows: u16, cols: u16) -> io::Result<(LocalPty, Child)> {
+        let mut size = winsize(rows, cols);
+        let (mut master, mut slave) = (0, 0);
+        // SAFETY: the out pointers are valid, name and termios are optional
+        let res = unsafe {
+            // winsize is *const on Linux, *mut on the BSDs
+            let size = std::ptr::addr_of_mut!(size);
+            libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null_mut(), size)
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: openpty just returned these descriptors, nothing else owns them
+        let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+        // the shell must not inherit the master, or it would never see the terminal close
+        set_flags(&master, libc::F_SETFD, libc::FD_CLOEXEC)?;
+        set_flags(&master, libc::F_SETFL, libc::O_NONBLOCK)?;
+
+        command
+            .env("TERM", "xterm-256color")
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            command.pre_exec(|| {
+                // new session, with the terminal on stdin as its controlling terminal
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn()?;
+        // the copies of the slave held by the command are closed here, only the child keeps it open
+        drop(command);
+
+        let pid = child.id().unwrap_or_default();
+        let pty = LocalPty {
+            master: AsyncTty::new(master)?,
+            pid,
+        };
+        Ok((pty, child))
+    }
+
+    /// Reap the process; the exit code, or 128 + signal like a shell reports it
+    pub async fn wait(mut child: Child) -> Option<i32> {
+        let status = child.wait().await.ok()?;
+        status.code().or_else(|| status.signal().map(|signal| 128 + signal))
+    }
+
+    impl LocalPty {
+        pub fn pid(&self) -> u32 {
+            self.pid
+        }
+
+        pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+            self.master.read(buf).await
+        }
+
+        pub async fn write(&self, data: &[u8]) -> io::Result<()> {
+            self.master.write(data).await
+        }
+
+        /// New size of the terminal, the shell gets SIGWINCH
+        pub fn resize(&self, rows: u16, cols: u16) -> io::Result<()> {
+            let size = winsize(rows, cols);
+            // SAFETY: TIOCSWINSZ reads a winsize
+            if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &size) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        /// Hang up, as when a terminal is closed: SIGHUP to the shell and its jobs
+        pub fn close(&self) {
+            if let Ok(pid) = libc::pid_t::try_from(self.pid)
+                && pid > 0
+            {
+                // SAFETY: the shell leads its own process group since setsid
+                unsafe { libc::kill(-pid, libc::SIGHUP) };
+            }
+        }
+    }
+
+    fn winsize(rows: u16, cols: u16) -> libc::winsize {
+        libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod pty {
+    use std::io;
+
+    pub struct Command;
+    pub struct Child;
+
+    #[derive(Clone)]
+    pub struct LocalPty;
+
+    pub fn login_shell() -> Command {
+        Command
+    }
+
+    pub fn spawn(_command: Command, _rows: u16, _cols: u16) -> io::Result<(LocalPty, Child)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "pas de pseudo-terminal sur ce système",
+        ))
+    }
+
+    pub async fn wait(_child: Child) -> Option<i32> {
+        None
+    }
+
+    impl LocalPty {
+        pub fn pid(&self) -> u32 {
+            0
+        }
+
+        pub async fn read(&self, _buf: &mut [u8]) -> io::Result<usize> {
+            Ok(0)
+        }
+
+        pub async fn write(&self, _data: &[u8]) -> io::Result<()> {
+            Ok(())
+        }
+
+        pub fn resize(&self, _rows: u16, _cols: u16) -> io::Result<()> {
+            Ok(())
+        }
+
+        pub fn close(&self) {}
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::pty;
+
+    async fn read_all(pty: &pty::LocalPty) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut buf = [0; 1024];
+        loop {
+            match pty.read(&mut buf).await.unwrap() {
+                0 => return out,
+                n => out.extend_from_slice(&buf[..n]),
+            }
+        }
+    }
+
+    fn sh(script: &str) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("/bin/sh");
+        command.arg("-c").arg(script);
+        command
+    }
+
+    #[tokio::test]
+    async fn output_reaches_the_terminal_and_the_child_is_reaped() {
+        let (pty, child) = pty::spawn(sh("printf 'hello\\n'; tty -s && echo tty; exit 3"), 24, 80).unwrap();
+        let output = read_all(&pty).await;
+        assert_eq!(pty::wait(child).await, Some(3));
+
+        let mut parser = vt100::Parser::new(24, 80, 0);
+        parser.process(&output);
+        let screen = parser.screen().contents();
+        // the terminal translated \n into \r\n (ONLCR), as for a remote shell
+        assert!(screen.starts_with("hello\ntty"), "{:?}", screen);
+    }
+
+    #[tokio::test]
+    async fn resize_and_input_reach_the_shell() {
+        let (pty, child) = pty::spawn(sh("read _line; stty size"), 24, 80).unwrap();
+        pty.resize(40, 132).unwrap();
+        pty.write(b"go\n").await.unwrap();
+        let output = String::from_utf8_lossy(&read_all(&pty).await).into_owned();
+        assert!(output.contains("40 132"), "{:?}", output);
+        assert_eq!(pty::wait(child).await, Some(0));
+    }
+
+    #[tokio::test]
+    async fn closing_hangs_up_the_shell() {
+        let (pty, child) = pty::spawn(sh("sleep 30"), 24, 80).unwrap();
+        pty.close();
+        assert_eq!(pty::wait(child).await, Some(128 + libc::SIGHUP));
+    }
+}
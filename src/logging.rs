@@ -0,0 +1,73 @@
+//! Where the app's traces go: the console, a daily log file in the data folder and the event log window.
+//! The `log` records of russh come through as well.
+
+use std::sync::OnceLock;
+
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{EnvFilter, Registry, fmt, layer::SubscriberExt, reload};
+
+use crate::event_log::EventLogLayer;
+use crate::models::LogLevel;
+
+/// Overrides the verbosity of the settings at startup, with the `EnvFilter` syntax ("rustty=trace,russh=debug")
+pub const ENV_VAR: &str = "RUSTTY_LOG";
+/// Days of log files kept
+const KEPT_FILES: usize = 7;
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Folder of the log files (rustty.YYYY-MM-DD.log)
+pub fn log_dir() -> std::path::PathBuf {
+    crate::storage::data_dir().join("logs")
+}
+
+/// Install the subscriber, once, before anything is traced
+pub fn init(level: LogLevel) {
+    let filter =
+        EnvFilter::try_from_env(ENV_VAR).unwrap_or_else(|_| EnvFilter::new(level.directives()));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    // the appender only lists the old files of an existing folder
+    let _ = std::fs::create_dir_all(log_dir());
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("rustty")
+        .filename_suffix("log")
+        .max_log_files(KEPT_FILES)
+        .build(log_dir());
+    // without a writable data folder the console and the window still get everything
+    let (file, file_error) = match file {
+        Ok(file) => (Some(fmt::layer().with_ansi(false).with_writer(file)), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file)
+        .with(EventLogLayer::global());
+    if tracing::subscriber::set_global_default(subscriber).is_ok() {
+        let _ = FILTER.set(handle);
+    }
+    // every level of russh: the filter above decides, even once the verbosity is raised
+    let _ = tracing_log::LogTracer::init();
+    if let Some(e) = file_error {
+        tracing::warn!(dir = %log_dir().display(), "Fichier de journal impossible à créer : {}", e);
+    }
+}
+
+/// Verbosity of the settings once they are loaded, unless the environment variable chose one
+pub fn apply_settings(level: LogLevel) {
+    if std::env::var_os(ENV_VAR).is_none() {
+        set_level(level);
+    }
+}
+
+/// Verbosity chosen in the app; it replaces the one of the environment variable
+pub fn set_level(level: LogLevel) {
+    let Some(handle) = FILTER.get() else { return };
+    match handle.reload(EnvFilter::new(level.directives())) {
+        Ok(()) => tracing::debug!(?level, "Verbosité du journal"),
+        Err(e) => tracing::warn!("Verbosité inchangée : {}", e),
+    }
+}
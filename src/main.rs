@@ -1,23 +1,77 @@
+pub mod cli;
 pub mod messages;
 pub mod ssh;
 pub mod ui;
 pub mod models;
+pub mod recording;
+pub mod session_log;
+pub mod sftp;
+pub mod exec;
+pub mod destination;
+pub mod folders;
+pub mod fuzzy;
+pub mod import;
+pub mod storage;
+pub mod vault;
+#[cfg(unix)]
+pub mod tty;
+pub mod local;
+pub mod session;
+pub mod net;
+pub mod algorithms;
+pub mod connect_error;
+pub mod connect_progress;
+pub mod event_log;
+pub mod logging;
+pub mod telnet;
+pub mod serial;
 
 use iced::{Task, widget::text_input, window};
 use ui::MyApp;
 
-use crate::{messages::Message, ui::constants::*};
+use crate::{messages::{EventLogMessage, Message, PlaybackMessage, SftpMessage, SshMessage, VaultMessage}, ui::constants::*};
 
 pub fn main() -> iced::Result {
+    // the verbosity of the settings applies once they are loaded
+    logging::init(models::LogLevel::default());
+
+    // sessions asked for on the command line open without the dashboard
+    let launch = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Dashboard) => None,
+        Ok(cli::Command::Launch(launch)) => Some(launch),
+        Ok(cli::Command::ListProfiles) => {
+            cli::list_profiles();
+            return Ok(());
+        }
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("rustty: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let sessions = match launch.as_ref().map(cli::prepare).transpose() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            eprintln!("rustty: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // idec daemon to manage multiple windows and global events
     iced::daemon("RustTy", MyApp::update, MyApp::view)
         //By writing |_|,
         //you were telling Rust: “Receive this argument, but I don't care about it, I'm not going to call it inside my code.”
         //So if we want to use it, then |app|
-        .subscription(|_| {
+        .subscription(|app| {
             let window_events = window::events().map(|(id, event)| match event {
                 window::Event::Opened { .. } => Message::WindowOpened(id),
                 window::Event::CloseRequested | window::Event::Closed => Message::WindowClosed(id),
+                window::Event::Resized(size) => Message::WindowResized(id, size),
+                window::Event::FileDropped(path) => Message::Sftp(SftpMessage::FileDropped(id, path)),
+                window::Event::Focused => Message::Ssh(SshMessage::WindowFocused(id)),
                 _ => Message::DoNothing,
             });
 
@@ -31,9 +85,39 @@ pub fn main() -> iced::Result {
                 }
             });
 
-            iced::Subscription::batch(vec![window_events, events])
+            // the playback clock only ticks while a recording is playing
+            let playback_clock = if app.players.values().any(|p| !p.paused) {
+                iced::time::every(std::time::Duration::from_millis(30))
+                    .map(|now| Message::Playback(PlaybackMessage::Tick(now)))
+            } else {
+                iced::Subscription::none()
+            };
+
+            // checks from time to time whether the unlocked vault has been idle for too long
+            let vault_clock = if app.vault.is_some() && app.store.settings.vault_auto_lock_minutes > 0 {
+                iced::time::every(std::time::Duration::from_secs(15))
+                    .map(|now| Message::Vault(VaultMessage::Tick(now)))
+            } else {
+                iced::Subscription::none()
+            };
+
+            // the event log window follows the events recorded while it is open
+            let event_log_clock = if app.event_log_window.is_some() {
+                iced::time::every(std::time::Duration::from_millis(500))
+                    .map(|_| Message::EventLog(EventLogMessage::Tick))
+            } else {
+                iced::Subscription::none()
+            };
+
+            iced::Subscription::batch(vec![window_events, events, playback_clock, vault_clock, event_log_clock])
         })
-        .run_with(|| {
+        .run_with(move || {
+            if let Some((sessions, password)) = sessions {
+                let mut app = MyApp::new(None);
+                let task = app.launch(sessions, password);
+                return (app, task);
+            }
+
             // Init the first window and get its ID and the task to open it
             let (id, task) = window::open(window::Settings {
                 size: iced::Size::new(950.0, 900.0),
@@ -41,7 +125,7 @@ pub fn main() -> iced::Result {
             });
 
             (
-                MyApp::new(id),
+                MyApp::new(Some(id)),
                 Task::batch(vec![
                     task.discard(),
                     // Focus the TextInput PROFILE_NAME in the new window to allow immediate typing
@@ -1,7 +1,6 @@
-use std::sync::Arc;
+use std::{path::PathBuf, time::Instant};
 use iced::{window, Event};
-use crate::{models::EditSection, ssh::{MyHandler, SshChannel, SshHandle}, ui::theme::ThemeChoice};
-use tokio::sync::Mutex;
+use crate::{vault::Vault, connect_progress, event_log::Level, import::{ImportCandidate, ImportSource, Resolution}, exec::{ExecResult, ExecTarget, ExportFormat}, models::{AlgorithmKind, LogLevel, AlgorithmPreset, EditSection, FlowControl, LogMode, Parity, Profile, ProfileView, Protocol, SortColumn}, recording::Recording, sftp::{RemoteEntry, SftpSessionArc, Uploader}, connect_error::ConnectError, ssh::SshConnection, ui::theme::ThemeChoice};
 
 #[derive(Clone, Debug)] 
 pub enum Message {
@@ -10,6 +9,7 @@ pub enum Message {
     //KeyboardEvent(iced::keyboard::Event),
     WindowOpened(window::Id),
     WindowClosed(window::Id),
+    WindowResized(window::Id, iced::Size),
     QuitRequested,
     DoNothing,
 
@@ -17,7 +17,15 @@ pub enum Message {
     Login(LoginMessage),     // Everything related to input fields
     Ssh(SshMessage),         // Everything related to the network/terminal
     Profile(ProfileMessage), // Everything related to the profiles database
+    Folder(FolderMessage),   // Folder tree of the profiles and folder-level actions
     Config(ConfigMessage),   // Themes, sections, settings
+    Playback(PlaybackMessage), // Recorded sessions (asciicast) and their playback windows
+    Sftp(SftpMessage),       // File browser windows opened on a session
+    Exec(ExecMessage),       // One-off commands run on a profile or a group
+    Vault(VaultMessage),     // Encrypted store of the passwords and key passphrases
+    Import(ImportMessage),   // Profiles read from other tools, previewed before being added
+    QuickConnect(QuickConnectMessage), // Connection typed as user@host:port, without a profile
+    EventLog(EventLogMessage), // Window listing what the app did, connections first
 }
 
 #[derive(Clone, Debug)]
@@ -31,14 +39,50 @@ pub enum LoginMessage {
 
 #[derive(Clone)]
 pub enum SshMessage {
-    Connected(Result<(crate::ssh::SshHandle, Arc<Mutex<Option<iced::window::Id>>>), String>),
+    /// (session, window slot of the handler, profile the session was opened for)
+    Connected(Result<(SshConnection, Box<Profile>), ConnectError>),
     SendData(Vec<u8>),   
-    TerminalWindowOpened(window::Id, SshHandle, Arc<Mutex<Option<window::Id>>>),
-    SetChannel(iced::window::Id, crate::ssh::SshChannelArc), 
+    TerminalWindowOpened(window::Id, SshConnection, Box<Profile>),
+    SetChannel(iced::window::Id, crate::session::SessionChannel),
+    /// The terminal window is open but the server refused its session channel or PTY
+    ShellFailed(window::Id, ConnectError),
+    /// Close the connection error panel of the dashboard
+    DismissConnectError,
+    /// Step of a connection being established
+    Progress(uuid::Uuid, connect_progress::Update),
+    /// Forget a failed connection shown on the dashboard
+    DismissProgress(uuid::Uuid),
+    /// Local shell, Telnet or raw profile: its terminal window opens before it connects
+    OpenSession(Box<Profile>),
+    SessionWindowOpened(window::Id, Box<Profile>),
+    /// Process reaped, connection closed or lost, with what to show about it
+    SessionEnded(window::Id, crate::session::SessionEnd),
+    /// Open the session of a disconnected window again, in place
+    Reconnect(window::Id),
+    /// New SSH connection for a window whose session was lost
+    Reconnected(window::Id, Result<SshConnection, ConnectError>),
     DataReceived(iced::window::Id, Vec<u8>),
+    /// Round trip measured on the connection of an SSH window
+    Latency(window::Id, std::time::Duration),
+    LogFinished(iced::window::Id, Result<std::path::PathBuf, String>),
+    ToggleRecording(iced::window::Id),
+    RecordingFinished(iced::window::Id, Result<std::path::PathBuf, String>),
     WindowFocused(iced::window::Id),
 }
 
+#[derive(Clone, Debug)]
+pub enum EventLogMessage {
+    Open,
+    /// Lowest level shown
+    LevelChanged(Level),
+    /// What gets traced at all (file, console and window), saved in the settings
+    VerbosityChanged(LogLevel),
+    Copy,
+    Clear,
+    /// Redraw the open window with the events recorded since
+    Tick,
+}
+
 #[derive(Clone, Debug)]
 pub enum ProfileMessage {
     Selected(uuid::Uuid),
@@ -49,12 +93,182 @@ pub enum ProfileMessage {
     InputGroup(String),
     SearchChanged(String),
     TerminalCountChanged(usize),
+    ProtocolChanged(Protocol),
+    InputSerialDevice(String),
+    SerialBaudRateChanged(u32),
+    SerialDataBitsChanged(u8),
+    SerialParityChanged(Parity),
+    SerialStopBitsChanged(u8),
+    SerialFlowControlChanged(FlowControl),
+    LogModeChanged(LogMode),
+    InputLogFile(String),
+    LogAppendToggled(bool),
+    /// Network page: numbers typed in seconds, empty for 0 (off)
+    InputKeepaliveInterval(String),
+    InputKeepaliveMax(String),
+    InputInactivityTimeout(String),
+    InputConnectTimeout(String),
+    TcpNodelayToggled(bool),
+    TcpKeepaliveToggled(bool),
+    /// Advanced page: lists of SSH algorithms
+    AlgorithmPresetChosen(AlgorithmPreset),
+    /// Allow the algorithm, or stop allowing it
+    AlgorithmToggled(AlgorithmKind, String),
+    /// Move the algorithm at this index one place up
+    AlgorithmRaised(AlgorithmKind, usize),
+    ReconnectAutoToggled(bool),
+    ReconnectAttemptsChanged(u32),
+    /// Line of the startup commands edited
+    StartupCommandChanged(usize, String),
+    StartupCommandAdded,
+    StartupCommandRemoved(usize),
+    /// Follow the default theme of the folder
+    InheritThemeToggled(bool),
+    /// Comma-separated tags of the edited profile
+    InputTags(String),
+    /// Star of a profile row
+    FavouriteToggled(uuid::Uuid),
+    ViewChanged(ProfileView),
+    /// Tag chip above the list: show only the profiles that have it
+    TagFilterToggled(String),
+    /// Column header clicked: ascending, descending, then back to the folder tree
+    SortBy(SortColumn),
+    /// Enter in the list: connect the selected profile, or the best result of the search
+    ConnectSelected,
+}
+
+#[derive(Clone, Debug)]
+pub enum FolderMessage {
+    /// Select a folder and expand or collapse it
+    Toggle(String),
+    /// Press on a profile row: select it and start dragging it
+    Grab(uuid::Uuid),
+    Hover(String),
+    Unhover(String),
+    /// Mouse released over a folder
+    DropOn(String),
+    CancelDrag,
+    InputRename(String),
+    Rename,
+    /// The first press asks for confirmation
+    Delete,
+    CancelDelete,
+    ConnectAll,
+    DefaultTheme(Option<ThemeChoice>),
+    InputDefaultUser(String),
+    ApplyDefaultUser,
+}
+
+#[derive(Clone, Debug)]
+pub enum QuickConnectMessage {
+    Input(String),
+    InputPass(String),
+    Connect,
+    /// Keep the last quick connection as a profile
+    SaveAsProfile,
+    Dismiss,
+}
+
+#[derive(Clone, Debug)]
+pub enum VaultMessage {
+    InputMaster(String),
+    InputConfirm(String),
+    Create,
+    Unlock,
+    Opened(Result<Vault, String>),
+    Lock,
+    InputPassphrase(String),
+    /// Keep the password typed in the form (and the passphrase) for the selected profile
+    StoreSecrets,
+    ForgetSecrets,
+    AutoLockChanged(u32),
+    Tick(Instant),
+}
+
+#[derive(Clone, Debug)]
+pub enum ImportMessage {
+    SourceChanged(ImportSource),
+    InputPath(String),
+    Analyse,
+    Analysed(Result<Vec<ImportCandidate>, String>),
+    /// (row in the preview, selected)
+    Toggle(usize, bool),
+    ToggleAll(bool),
+    ResolutionChanged(usize, Resolution),
+    Apply,
+    /// Profile included in the exports (ssh_config, bundle)
+    ExportToggled(uuid::Uuid, bool),
+    ExportGroup(String, bool),
+    ExportAll(bool),
+    CopySshConfig,
+    SaveSshConfig,
+    InputBundlePath(String),
+    SaveBundle,
+    ExportSaved(Result<PathBuf, String>),
+}
+
+#[derive(Clone, Debug)]
+pub enum ExecMessage {
+    InputCommand(String),
+    TargetChanged(ExecTarget),
+    Run,
+    /// Stop the commands still running, their output so far is kept
+    Cancel,
+    /// (run number, row in the results table, outcome)
+    Finished(u64, usize, ExecResult),
+    Export(ExportFormat),
+    Exported(Result<PathBuf, String>),
+}
+
+#[derive(Clone, Debug)]
+pub enum PlaybackMessage {
+    RefreshList,
+    ListLoaded(Vec<PathBuf>),
+    Open(PathBuf),
+    Loaded(Result<Recording, String>),
+    TogglePause(window::Id),
+    Seek(window::Id, f64),
+    SpeedChanged(window::Id, f64),
+    Tick(Instant),
+}
+
+// every variant targets an SFTP window, except Open and the drag-and-drop ones which come from a terminal window
+#[derive(Clone)]
+pub enum SftpMessage {
+    Open(window::Id),
+    Ready(window::Id, Result<SftpSessionArc, String>),
+    Navigate(window::Id, String),
+    Listed(window::Id, Result<(String, Vec<RemoteEntry>), String>),
+    EntryClicked(window::Id, String),
+    InputRename(window::Id, String),
+    InputChmod(window::Id, String),
+    InputMkdir(window::Id, String),
+    InputUpload(window::Id, String),
+    Rename(window::Id),
+    Chmod(window::Id),
+    Delete(window::Id),
+    Mkdir(window::Id),
+    Upload(window::Id),
+    Download(window::Id),
+    OperationDone(window::Id, Result<(), String>),
+    /// (window, transfer id, bytes done, bytes total)
+    TransferProgress(window::Id, u64, u64, u64),
+    TransferFinished(window::Id, u64, Result<(), String>),
+    CancelTransfer(window::Id, u64),
+    // files dropped on a terminal window
+    FileDropped(window::Id, PathBuf),
+    DropReady(window::Id, Result<Uploader, String>),
+    InputDropDir(window::Id, String),
+    ConfirmDropDir(window::Id),
+    CancelDropDir(window::Id),
+    DismissDrops(window::Id),
 }
 
 #[derive(Clone, Debug)]
 pub enum ConfigMessage {
     SectionChanged(EditSection),
     ThemeChanged(ThemeChoice),
+    DismissStoreError,
 }
 
 // necessary for debugging SshMessage::Connected without printing the entire SSH handle
@@ -67,4 +281,51 @@ impl std::fmt::Debug for SshMessage {
             _ => f.write_str("OtherSshMessage"), 
         }
     }
-}
\ No newline at end of file
+}
+
+impl SftpMessage {
+    /// Window the message is about (the terminal window for Open and the drops, the SFTP window otherwise)
+    pub fn window_id(&self) -> window::Id {
+        match self {
+            SftpMessage::Open(id)
+            | SftpMessage::Ready(id, _)
+            | SftpMessage::Navigate(id, _)
+            | SftpMessage::Listed(id, _)
+            | SftpMessage::EntryClicked(id, _)
+            | SftpMessage::InputRename(id, _)
+            | SftpMessage::InputChmod(id, _)
+            | SftpMessage::InputMkdir(id, _)
+            | SftpMessage::InputUpload(id, _)
+            | SftpMessage::Rename(id)
+            | SftpMessage::Chmod(id)
+            | SftpMessage::Delete(id)
+            | SftpMessage::Mkdir(id)
+            | SftpMessage::Upload(id)
+            | SftpMessage::Download(id)
+            | SftpMessage::OperationDone(id, _)
+            | SftpMessage::TransferProgress(id, ..)
+            | SftpMessage::TransferFinished(id, ..)
+            | SftpMessage::CancelTransfer(id, _)
+            | SftpMessage::FileDropped(id, _)
+            | SftpMessage::DropReady(id, _)
+            | SftpMessage::InputDropDir(id, _)
+            | SftpMessage::ConfirmDropDir(id)
+            | SftpMessage::CancelDropDir(id)
+            | SftpMessage::DismissDrops(id) => *id,
+        }
+    }
+}
+
+// the SFTP session has no Debug implementation
+impl std::fmt::Debug for SftpMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SftpMessage::Ready(id, Ok(_)) => write!(f, "Ready({:?}, Ok(SFTP_SESSION))", id),
+            SftpMessage::Ready(id, Err(e)) => write!(f, "Ready({:?}, Err({}))", id, e),
+            SftpMessage::Listed(id, Ok((path, entries))) => {
+                write!(f, "Listed({:?}, {} : {} entrées)", id, path, entries.len())
+            }
+            _ => f.write_str("OtherSftpMessage"),
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
 use crate::ui::theme::ThemeChoice;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Profile {
     pub id: Uuid,
     pub name: String,
@@ -13,7 +15,50 @@ pub struct Profile {
     pub username: String,
     pub group: String,
     pub theme: ThemeChoice,
-    pub terminal_count: usize
+    pub terminal_count: usize,
+    /// What the session connects to, SSH for the profiles saved before the other types existed
+    #[serde(default)]
+    pub protocol: Protocol,
+    /// Line settings of the Serial sessions
+    #[serde(default)]
+    pub serial: SerialSettings,
+    // missing in profiles saved before session logging existed
+    #[serde(default)]
+    pub logging: LogSettings,
+    #[serde(default)]
+    pub reconnect: ReconnectSettings,
+    /// Keepalives, timeouts and socket options
+    #[serde(default)]
+    pub network: NetworkSettings,
+    /// SSH algorithms offered, in order of preference
+    #[serde(default)]
+    pub algorithms: AlgorithmSettings,
+    /// Typed into the session once it is open, and again after each reconnection
+    #[serde(default)]
+    pub startup_commands: Vec<String>,
+    /// Private key file used for public key authentication
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    #[serde(default)]
+    pub forwards: Vec<PortForward>,
+    #[serde(default)]
+    pub proxy: Option<ProxySettings>,
+    /// Take the default theme of the folder instead of `theme`
+    #[serde(default)]
+    pub inherit_theme: bool,
+    /// Free-form labels, lowercase
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favourite: bool,
+    /// Unix time of the last successful connection
+    #[serde(default)]
+    pub last_connected: Option<i64>,
+    #[serde(default)]
+    pub connection_count: u32,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl std::fmt::Display for Profile {
@@ -22,10 +67,8 @@ impl std::fmt::Display for Profile {
     }
 }
 
-impl Profile {
-    const FILE_PATH: &'static str = "profiles.json";
-
-    pub fn default() -> Self {
+impl Default for Profile {
+    fn default() -> Self {
         Self {
             id: uuid::Uuid::new_v4(),
             name: "Nouveau Profil".into(),
@@ -35,30 +78,569 @@ impl Profile {
             username: "".into(),
             theme: crate::ui::theme::ThemeChoice::Slate, 
             terminal_count: 1,
+            protocol: Protocol::Ssh,
+            serial: SerialSettings::default(),
+            logging: LogSettings::default(),
+            reconnect: ReconnectSettings::default(),
+            network: NetworkSettings::default(),
+            algorithms: AlgorithmSettings::default(),
+            startup_commands: Vec::new(),
+            identity_file: None,
+            forwards: Vec::new(),
+            proxy: None,
+            inherit_theme: false,
+            tags: Vec::new(),
+            favourite: false,
+            last_connected: None,
+            connection_count: 0,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl Profile {
+    /// "Web, prod ,web" -> ["web", "prod"]
+    pub fn parse_tags(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in input.split(',').map(|t| t.trim().to_lowercase()) {
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Count a successful connection made at `now` (unix time)
+    pub fn record_connection(&mut self, now: i64) {
+        self.last_connected = Some(now);
+        self.connection_count = self.connection_count.saturating_add(1);
+    }
+
+    /// Address shown in the list: "host:port", or what replaces it for the sessions without one
+    pub fn endpoint(&self) -> String {
+        match self.protocol {
+            Protocol::Ssh | Protocol::Telnet | Protocol::Raw => format!("{}:{}", self.ip, self.port),
+            Protocol::Local => "shell local".into(),
+            Protocol::Serial => format!("{} ({} bauds)", self.serial.device, self.serial.baud_rate),
         }
     }
 
-    /// Load all profiles from the JSON file, returning an empty vector if the file doesn't exist or is invalid
-    pub fn load_all() -> Vec<Self> {
-        if !Path::new(Self::FILE_PATH).exists() {
-            return Vec::new();
+    /// What the session is opened on: the address, the serial device, or "local"
+    pub fn host(&self) -> &str {
+        match self.protocol {
+            Protocol::Ssh | Protocol::Telnet | Protocol::Raw => &self.ip,
+            Protocol::Serial => &self.serial.device,
+            Protocol::Local => "local",
+        }
+    }
+}
+
+/// Kind of session a profile opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Protocol {
+    #[default]
+    Ssh,
+    Telnet,
+    /// Plain TCP connection, bytes passed through as they are
+    Raw,
+    /// Console on a tty device (USB adapter, serial port)
+    Serial,
+    /// The user's login shell in a local pseudo-terminal
+    Local,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 5] = [
+        Protocol::Ssh,
+        Protocol::Telnet,
+        Protocol::Raw,
+        Protocol::Serial,
+        Protocol::Local,
+    ];
+
+    /// Needs an address and a port
+    pub fn is_remote(self) -> bool {
+        matches!(self, Protocol::Ssh | Protocol::Telnet | Protocol::Raw)
+    }
+
+    /// Logs in with the user name and password of the profile (Telnet asks in the terminal)
+    pub fn needs_login(self) -> bool {
+        self == Protocol::Ssh
+    }
+
+    /// Port filled in when the type is chosen
+    pub fn default_port(self) -> Option<&'static str> {
+        match self {
+            Protocol::Ssh => Some("22"),
+            Protocol::Telnet => Some("23"),
+            Protocol::Raw | Protocol::Serial | Protocol::Local => None,
         }
+    }
+}
 
-        match std::fs::read_to_string(Self::FILE_PATH) {
-            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
-                eprintln!("Erreur JSON: {}", e);
-                Vec::new()
-            }),
-            Err(_) => Vec::new(),
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Protocol::Ssh => write!(f, "SSH"),
+            Protocol::Telnet => write!(f, "Telnet"),
+            Protocol::Raw => write!(f, "TCP brut"),
+            Protocol::Serial => write!(f, "Série"),
+            Protocol::Local => write!(f, "Shell local"),
         }
     }
+}
+
+/// Profiles of the dashboard list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileView {
+    #[default]
+    All,
+    Favourites,
+    /// Last connected first
+    Recent,
+}
+
+impl ProfileView {
+    pub const ALL: [ProfileView; 3] = [ProfileView::All, ProfileView::Favourites, ProfileView::Recent];
+    /// Profiles shown by the Recent view
+    pub const RECENT_LIMIT: usize = 10;
+}
 
-    /// SAve all profiles to the JSON file, overwriting existing content. Logs an error if writing fails.
-    pub fn save_all(profiles: &[Self]) {
-        if let Ok(json) = serde_json::to_string_pretty(profiles) {
-            if let Err(e) = std::fs::write(Self::FILE_PATH, json) {
-                eprintln!("Erreur d'écriture: {}", e);
+impl std::fmt::Display for ProfileView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileView::All => write!(f, "Tous"),
+            ProfileView::Favourites => write!(f, "★ Favoris"),
+            ProfileView::Recent => write!(f, "🕘 Récents"),
+        }
+    }
+}
+
+/// Column of the profile table the list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Group,
+    Name,
+    Username,
+    Ip,
+}
+
+impl SortColumn {
+    pub fn compare(self, a: &Profile, b: &Profile) -> std::cmp::Ordering {
+        let text = |p: &Profile| {
+            match self {
+                SortColumn::Group => &p.group,
+                SortColumn::Name => &p.name,
+                SortColumn::Username => &p.username,
+                SortColumn::Ip => &p.ip,
             }
+            .to_lowercase()
+        };
+        // 10.0.0.9 before 10.0.0.10, host names after the addresses
+        let address = |p: &Profile| p.ip.parse::<std::net::IpAddr>().ok();
+        match (self, address(a), address(b)) {
+            (SortColumn::Ip, Some(x), Some(y)) => x.cmp(&y),
+            (SortColumn::Ip, Some(_), None) => std::cmp::Ordering::Less,
+            (SortColumn::Ip, None, Some(_)) => std::cmp::Ordering::Greater,
+            _ => text(a).cmp(&text(b)),
+        }
+    }
+}
+
+/// Indices of the profiles connected at least once, last connected first
+pub fn recent(profiles: &[Profile], limit: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..profiles.len())
+        .filter(|&i| profiles[i].last_connected.is_some())
+        .collect();
+    indices.sort_by_key(|&i| std::cmp::Reverse(profiles[i].last_connected));
+    indices.truncate(limit);
+    indices
+}
+
+/// Version of the profiles.json layout written by this build
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Application-wide settings, stored next to the profiles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Minutes without using the vault before it locks itself, 0 = never
+    #[serde(default = "Settings::default_auto_lock")]
+    pub vault_auto_lock_minutes: u32,
+    /// Defaults of the folders that have some, by path ("PROD/EU")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub folders: BTreeMap<String, FolderDefaults>,
+    /// Verbosity of the log file and of the event log window
+    #[serde(default)]
+    pub log_level: LogLevel,
+    // fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Settings {
+    pub const AUTO_LOCK_CHOICES: [u32; 6] = [1, 5, 10, 30, 60, 0];
+
+    fn default_auto_lock() -> u32 {
+        10
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            vault_auto_lock_minutes: Self::default_auto_lock(),
+            folders: BTreeMap::new(),
+            log_level: LogLevel::default(),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// How much the app writes about what it does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    /// Also the exchanges of the SSH library
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    /// Filter directives: the app at this level, the SSH crates only from Debug on, the rest (GUI) warnings only
+    pub fn directives(self) -> String {
+        let (app, ssh) = match self {
+            LogLevel::Error => ("error", "error"),
+            LogLevel::Warn => ("warn", "warn"),
+            LogLevel::Info => ("info", "warn"),
+            LogLevel::Debug => ("debug", "debug"),
+            LogLevel::Trace => ("trace", "trace"),
+        };
+        format!("warn,rustty={app},russh={ssh},russh_keys={ssh},russh_sftp={ssh}")
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LogLevel::Error => "Erreurs",
+            LogLevel::Warn => "Avertissements",
+            LogLevel::Info => "Normal",
+            LogLevel::Debug => "Débogage (SSH inclus)",
+            LogLevel::Trace => "Trace (très détaillé)",
+        })
+    }
+}
+
+/// Values inherited by the profiles of a folder and of its sub-folders
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeChoice>,
+    /// Used by the profiles without a user name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl FolderDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.theme.is_none() && self.username.is_none() && self.extra.is_empty()
+    }
+}
+
+/// Layout of profiles.json
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    profiles: Vec<Profile>,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// Everything in profiles.json besides the profiles, kept to write the file back
+#[derive(Debug, Default)]
+pub struct ProfileStore {
+    pub settings: Settings,
+    /// Unknown top-level fields
+    extra: Map<String, Value>,
+    /// The file comes from a newer version: saving would lose what this build doesn't know
+    pub read_only: bool,
+}
+
+impl ProfileStore {
+    const FILE_NAME: &'static str = "profiles.json";
+    /// Where versions before the config folder kept the profiles (relative to the working directory)
+    const LEGACY_FILE_PATH: &'static str = "profiles.json";
+
+    /// profiles.json in the per-user config folder
+    pub fn file_path() -> PathBuf {
+        crate::storage::config_dir().join(Self::FILE_NAME)
+    }
+
+    /// Load the profiles, migrating older layouts.
+    /// An unreadable file is moved aside (never overwritten) and reported in the returned error.
+    pub fn load() -> (Self, Vec<Profile>, Option<String>) {
+        let path = Self::file_path();
+        match crate::storage::migrate_legacy(Path::new(Self::LEGACY_FILE_PATH), &path) {
+            Ok(true) => tracing::info!(path = %path.display(), "profiles.json migré"),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Erreur de migration de profiles.json: {}", e),
+        }
+        Self::load_from(&path)
+    }
+
+    fn load_from(path: &Path) -> (Self, Vec<Profile>, Option<String>) {
+        if !path.exists() {
+            return (Self::default(), Vec::new(), None);
+        }
+
+        let data = match std::fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) => {
+                // maybe temporary (permissions, locked file): keep it and don't write over it
+                let store = Self {
+                    read_only: true,
+                    ..Self::default()
+                };
+                return (store, Vec::new(), Some(format!("Lecture de {} impossible : {}", path.display(), e)));
+            }
+        };
+
+        match Self::parse(&data) {
+            Ok((store, profiles)) if store.read_only => {
+                let error = format!(
+                    "{} a été écrit par une version plus récente de RustTy : les modifications ne seront pas enregistrées.",
+                    path.display()
+                );
+                (store, profiles, Some(error))
+            }
+            Ok((store, profiles)) => (store, profiles, None),
+            Err(e) => {
+                tracing::error!("Erreur JSON: {}", e);
+                // a newer layout this version can't decode is not a corrupt file: left untouched
+                if let Some(version) = newer_version(&data) {
+                    let store = Self {
+                        read_only: true,
+                        ..Self::default()
+                    };
+                    let error = format!(
+                        "{} a été écrit par une version plus récente de RustTy (format {}) et ne peut pas être lu : il n'est pas modifié.",
+                        path.display(),
+                        version
+                    );
+                    return (store, Vec::new(), Some(error));
+                }
+                let error = match quarantine(path) {
+                    Ok(moved) => format!(
+                        "Fichier de profils illisible ({}). Il a été mis de côté dans {}.",
+                        e,
+                        moved.display()
+                    ),
+                    Err(io) => {
+                        return (
+                            Self {
+                                read_only: true,
+                                ..Self::default()
+                            },
+                            Vec::new(),
+                            Some(format!("Fichier de profils illisible ({}), et impossible à déplacer : {}", e, io)),
+                        );
+                    }
+                };
+                (Self::default(), Vec::new(), Some(error))
+            }
+        }
+    }
+
+    /// Decode the content of profiles.json, whatever its version
+    pub fn parse(data: &str) -> Result<(Self, Vec<Profile>), String> {
+        let value: Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+        let version = schema_version(&value)?;
+        let value = if version > SCHEMA_VERSION {
+            value
+        } else {
+            migrate(value, version)?
+        };
+
+        let envelope: Envelope = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        let store = Self {
+            settings: envelope.settings,
+            extra: envelope.extra,
+            read_only: version > SCHEMA_VERSION,
+        };
+        Ok((store, envelope.profiles))
+    }
+
+    pub fn to_json(&self, profiles: &[Profile]) -> Result<String, String> {
+        let envelope = Envelope {
+            version: SCHEMA_VERSION,
+            profiles: profiles.to_vec(),
+            settings: self.settings.clone(),
+            extra: self.extra.clone(),
+        };
+        serde_json::to_string_pretty(&envelope).map_err(|e| e.to_string())
+    }
+
+    /// Write all profiles, the previous versions of the file are kept as backups
+    pub fn save(&self, profiles: &[Profile]) -> Result<(), String> {
+        if self.read_only {
+            return Err("Fichier de profils en lecture seule".into());
+        }
+        let json = self.to_json(profiles)?;
+        crate::storage::write_atomic(&Self::file_path(), json.as_bytes())
+            .map_err(|e| format!("Erreur d'écriture: {}", e))
+    }
+}
+
+// version 1 was a bare array of profiles, without envelope
+fn schema_version(value: &Value) -> Result<u32, String> {
+    match value {
+        Value::Array(_) => Ok(1),
+        Value::Object(map) => match map.get("version").and_then(Value::as_u64) {
+            None => Err("champ \"version\" manquant".into()),
+            Some(v) => u32::try_from(v)
+                .ok()
+                .filter(|v| *v >= 1)
+                .ok_or_else(|| format!("version {} invalide", v)),
+        },
+        _ => Err("format inconnu".into()),
+    }
+}
+
+// version of a file written by a newer RustTy, even when its content can't be decoded
+fn newer_version(data: &str) -> Option<u32> {
+    let value: Value = serde_json::from_str(data).ok()?;
+    schema_version(&value).ok().filter(|v| *v > SCHEMA_VERSION)
+}
+
+/// Upgrade a file of version `from` to SCHEMA_VERSION, one version at a time
+fn migrate(mut value: Value, from: u32) -> Result<Value, String> {
+    // MIGRATIONS[n] upgrades version n + 1 to version n + 2
+    const MIGRATIONS: [fn(Value) -> Result<Value, String>; 1] = [v1_to_v2];
+
+    for version in from..SCHEMA_VERSION {
+        let step = version
+            .checked_sub(1)
+            .and_then(|index| MIGRATIONS.get(index as usize))
+            .ok_or_else(|| format!("version {} inconnue", version))?;
+        value = step(value)?;
+    }
+    Ok(value)
+}
+
+fn v1_to_v2(value: Value) -> Result<Value, String> {
+    Ok(serde_json::json!({
+        "version": 2,
+        "profiles": value,
+        "settings": {},
+    }))
+}
+
+// profiles.json -> profiles.json.corrupt-<date>-<time>
+fn quarantine(path: &Path) -> std::io::Result<PathBuf> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".corrupt-{}", stamp));
+    let moved = PathBuf::from(name);
+    std::fs::rename(path, &moved)?;
+    Ok(moved)
+}
+
+/// Direction of a port forwarding (ssh -L, -R, -D)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForwardKind {
+    Local,
+    Remote,
+    /// SOCKS proxy on a local port
+    Dynamic,
+}
+
+/// One port forwarding of a profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortForward {
+    pub kind: ForwardKind,
+    /// Listening address, None for the loopback interface
+    #[serde(default)]
+    pub bind_address: Option<String>,
+    pub bind_port: u16,
+    /// Destination, empty for a dynamic forwarding
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub host_port: u16,
+}
+
+impl std::fmt::Display for PortForward {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bind = match &self.bind_address {
+            Some(address) => format!("{}:{}", address, self.bind_port),
+            None => self.bind_port.to_string(),
+        };
+        match self.kind {
+            ForwardKind::Local => write!(f, "L {} → {}:{}", bind, self.host, self.host_port),
+            ForwardKind::Remote => write!(f, "R {} → {}:{}", bind, self.host, self.host_port),
+            ForwardKind::Dynamic => write!(f, "D {} (SOCKS)", bind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProxyKind {
+    Socks4,
+    Socks5,
+    Http,
+    Telnet,
+    /// Local command whose stdin/stdout carry the connection
+    Command,
+    /// Jump host (ssh -J)
+    Ssh,
+}
+
+/// Proxy the connection goes through
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    pub kind: ProxyKind,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub port: u16,
+    #[serde(default)]
+    pub username: String,
+    /// Only for ProxyKind::Command
+    #[serde(default)]
+    pub command: String,
+}
+
+impl std::fmt::Display for ProxySettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let user = if self.username.is_empty() {
+            String::new()
+        } else {
+            format!("{}@", self.username)
+        };
+        match self.kind {
+            ProxyKind::Command => write!(f, "commande {}", self.command),
+            ProxyKind::Ssh => write!(f, "rebond SSH {}{}:{}", user, self.host, self.port),
+            ProxyKind::Socks4 => write!(f, "SOCKS4 {}{}:{}", user, self.host, self.port),
+            ProxyKind::Socks5 => write!(f, "SOCKS5 {}{}:{}", user, self.host, self.port),
+            ProxyKind::Http => write!(f, "HTTP {}{}:{}", user, self.host, self.port),
+            ProxyKind::Telnet => write!(f, "Telnet {}{}:{}", user, self.host, self.port),
         }
     }
 }
@@ -69,5 +651,584 @@ pub enum EditSection {
     Auth,
     Network,
     Advanced,
+    Logging,
+    Recordings,
+    Exec,
+    Vault,
+    Import,
     Themes,
-}
\ No newline at end of file
+}
+
+/// What a session writes to its log file (same choices as PuTTY)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LogMode {
+    #[default]
+    Off,
+    /// Only the printable output: escape sequences and control characters are stripped
+    Printable,
+    /// Every byte received from the server, untouched
+    Raw,
+}
+
+impl LogMode {
+    pub const ALL: [LogMode; 3] = [LogMode::Off, LogMode::Printable, LogMode::Raw];
+}
+
+impl std::fmt::Display for LogMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogMode::Off => write!(f, "Désactivé"),
+            LogMode::Printable => write!(f, "Texte imprimable"),
+            LogMode::Raw => write!(f, "Flux brut (tous les octets)"),
+        }
+    }
+}
+
+/// Per-profile session logging settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    pub mode: LogMode,
+    /// File name template, placeholders: &H host, &P port, &Y year, &M month, &D day, &T time, && a literal '&'
+    pub file_template: String,
+    /// Append to an existing file instead of overwriting it
+    pub append: bool,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            mode: LogMode::Off,
+            file_template: "rustty-&H-&Y&M&D-&T.log".into(),
+            append: true,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// Keepalives and timeouts of a connection, in seconds (0 turns one off)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// Keepalive sent after this long without anything from the server (SSH)
+    pub keepalive_interval: u64,
+    /// Keepalives left unanswered before the connection is dropped (SSH)
+    pub keepalive_max: u32,
+    /// Close the connection after this long without any traffic (SSH)
+    pub inactivity_timeout: u64,
+    pub connect_timeout: u64,
+    /// Send keystrokes right away instead of batching them (Nagle off)
+    pub tcp_nodelay: bool,
+    /// Let the system probe idle connections (SO_KEEPALIVE)
+    pub tcp_keepalive: bool,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl NetworkSettings {
+    pub fn keepalive_interval(&self) -> Option<std::time::Duration> {
+        seconds(self.keepalive_interval)
+    }
+
+    pub fn inactivity_timeout(&self) -> Option<std::time::Duration> {
+        seconds(self.inactivity_timeout)
+    }
+
+    pub fn connect_timeout(&self) -> Option<std::time::Duration> {
+        seconds(self.connect_timeout)
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            // well below the idle cut-off of most firewalls and VPNs
+            keepalive_interval: 30,
+            keepalive_max: 3,
+            inactivity_timeout: 0,
+            connect_timeout: 10,
+            tcp_nodelay: true,
+            tcp_keepalive: false,
+            extra: Map::new(),
+        }
+    }
+}
+
+fn seconds(value: u64) -> Option<std::time::Duration> {
+    (value > 0).then(|| std::time::Duration::from_secs(value))
+}
+
+/// One negotiated family of SSH algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgorithmKind {
+    Kex,
+    HostKey,
+    Cipher,
+    Mac,
+    Compression,
+}
+
+impl AlgorithmKind {
+    pub const ALL: [AlgorithmKind; 5] = [
+        AlgorithmKind::Kex,
+        AlgorithmKind::HostKey,
+        AlgorithmKind::Cipher,
+        AlgorithmKind::Mac,
+        AlgorithmKind::Compression,
+    ];
+}
+
+impl std::fmt::Display for AlgorithmKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlgorithmKind::Kex => write!(f, "Échange de clés"),
+            AlgorithmKind::HostKey => write!(f, "Clé d'hôte"),
+            AlgorithmKind::Cipher => write!(f, "Chiffrement"),
+            AlgorithmKind::Mac => write!(f, "Intégrité (MAC)"),
+            AlgorithmKind::Compression => write!(f, "Compression"),
+        }
+    }
+}
+
+/// Algorithms allowed for each family, the first ones preferred (names as in the SSH protocol)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlgorithmSettings {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher: Vec<String>,
+    pub mac: Vec<String>,
+    pub compression: Vec<String>,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl AlgorithmSettings {
+    pub fn list(&self, kind: AlgorithmKind) -> &Vec<String> {
+        match kind {
+            AlgorithmKind::Kex => &self.kex,
+            AlgorithmKind::HostKey => &self.host_key,
+            AlgorithmKind::Cipher => &self.cipher,
+            AlgorithmKind::Mac => &self.mac,
+            AlgorithmKind::Compression => &self.compression,
+        }
+    }
+
+    pub fn list_mut(&mut self, kind: AlgorithmKind) -> &mut Vec<String> {
+        match kind {
+            AlgorithmKind::Kex => &mut self.kex,
+            AlgorithmKind::HostKey => &mut self.host_key,
+            AlgorithmKind::Cipher => &mut self.cipher,
+            AlgorithmKind::Mac => &mut self.mac,
+            AlgorithmKind::Compression => &mut self.compression,
+        }
+    }
+
+    /// Allow the algorithm at the end of its list, or take it out (a family keeps at least one)
+    pub fn toggle(&mut self, kind: AlgorithmKind, name: &str) {
+        let list = self.list_mut(kind);
+        match list.iter().position(|n| n == name) {
+            Some(_) if list.len() == 1 => {}
+            Some(index) => {
+                list.remove(index);
+            }
+            None => list.push(name.to_string()),
+        }
+    }
+
+    /// Prefer the algorithm at `index` over the one before it
+    pub fn raise(&mut self, kind: AlgorithmKind, index: usize) {
+        let list = self.list_mut(kind);
+        if index > 0 && index < list.len() {
+            list.swap(index - 1, index);
+        }
+    }
+
+    fn from_lists(kex: &[&str], host_key: &[&str], cipher: &[&str], mac: &[&str], compression: &[&str]) -> Self {
+        let owned = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        Self {
+            kex: owned(kex),
+            host_key: owned(host_key),
+            cipher: owned(cipher),
+            mac: owned(mac),
+            compression: owned(compression),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// The choices of the SSH library
+impl Default for AlgorithmSettings {
+    fn default() -> Self {
+        Self::from_lists(
+            &[
+                "curve25519-sha256",
+                "curve25519-sha256@libssh.org",
+                "diffie-hellman-group16-sha512",
+                "diffie-hellman-group14-sha256",
+            ],
+            &["ssh-ed25519", "ecdsa-sha2-nistp256", "ecdsa-sha2-nistp521", "rsa-sha2-256", "rsa-sha2-512"],
+            &[
+                "chacha20-poly1305@openssh.com",
+                "aes256-gcm@openssh.com",
+                "aes256-ctr",
+                "aes192-ctr",
+                "aes128-ctr",
+            ],
+            &[
+                "hmac-sha2-512-etm@openssh.com",
+                "hmac-sha2-256-etm@openssh.com",
+                "hmac-sha2-512",
+                "hmac-sha2-256",
+                "hmac-sha1-etm@openssh.com",
+                "hmac-sha1",
+            ],
+            &["none", "zlib", "zlib@openssh.com"],
+        )
+    }
+}
+
+/// Ready-made algorithm lists of the Advanced page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgorithmPreset {
+    Default,
+    /// Modern algorithms only
+    Hardened,
+    /// Also the old ones still found on network equipment (SHA-1, CBC, ssh-rsa)
+    Legacy,
+}
+
+impl AlgorithmPreset {
+    pub const ALL: [AlgorithmPreset; 3] = [AlgorithmPreset::Default, AlgorithmPreset::Hardened, AlgorithmPreset::Legacy];
+
+    pub fn settings(self) -> AlgorithmSettings {
+        match self {
+            AlgorithmPreset::Default => AlgorithmSettings::default(),
+            AlgorithmPreset::Hardened => AlgorithmSettings::from_lists(
+                &["curve25519-sha256", "curve25519-sha256@libssh.org", "diffie-hellman-group16-sha512"],
+                &["ssh-ed25519", "rsa-sha2-512", "rsa-sha2-256"],
+                &["chacha20-poly1305@openssh.com", "aes256-gcm@openssh.com", "aes256-ctr"],
+                &["hmac-sha2-512-etm@openssh.com", "hmac-sha2-256-etm@openssh.com"],
+                &["none"],
+            ),
+            AlgorithmPreset::Legacy => {
+                let mut settings = AlgorithmSettings::default();
+                settings.kex.extend(
+                    [
+                        "ecdh-sha2-nistp256",
+                        "ecdh-sha2-nistp384",
+                        "ecdh-sha2-nistp521",
+                        "diffie-hellman-group14-sha1",
+                        "diffie-hellman-group1-sha1",
+                    ]
+                    .map(String::from),
+                );
+                settings.host_key.extend(["ecdsa-sha2-nistp384", "ssh-rsa"].map(String::from));
+                settings
+                    .cipher
+                    .extend(["aes256-cbc", "aes192-cbc", "aes128-cbc", "3des-cbc"].map(String::from));
+                settings
+            }
+        }
+    }
+
+    /// The preset the lists are equal to, if any
+    pub fn matching(settings: &AlgorithmSettings) -> Option<AlgorithmPreset> {
+        Self::ALL.into_iter().find(|preset| preset.apply_to(settings) == *settings)
+    }
+
+    /// The lists of the preset, with the other fields of `settings`
+    pub fn apply_to(self, settings: &AlgorithmSettings) -> AlgorithmSettings {
+        AlgorithmSettings {
+            extra: settings.extra.clone(),
+            ..self.settings()
+        }
+    }
+}
+
+impl std::fmt::Display for AlgorithmPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlgorithmPreset::Default => write!(f, "Par défaut"),
+            AlgorithmPreset::Hardened => write!(f, "Renforcé"),
+            AlgorithmPreset::Legacy => write!(f, "Équipement ancien"),
+        }
+    }
+}
+
+/// What to do when a session is lost
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectSettings {
+    /// Reconnect on its own, without waiting for R
+    pub auto: bool,
+    /// Automatic attempts before giving up
+    pub max_attempts: u32,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ReconnectSettings {
+    pub const ATTEMPTS: [u32; 5] = [1, 3, 5, 10, 20];
+    const MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+
+    /// Wait before the attempt `attempt` (from 0): 1 s, doubled each time, at most a minute
+    pub fn delay(attempt: u32) -> std::time::Duration {
+        std::time::Duration::from_secs(1u64 << attempt.min(6)).min(Self::MAX_DELAY)
+    }
+}
+
+impl Default for ReconnectSettings {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            max_attempts: 5,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// Line settings of a serial console (8N1 at 115200 bauds by default)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialSettings {
+    /// Device path, e.g. /dev/ttyUSB0
+    pub device: String,
+    pub baud_rate: u32,
+    /// 5 to 8
+    pub data_bits: u8,
+    pub parity: Parity,
+    /// 1 or 2
+    pub stop_bits: u8,
+    pub flow_control: FlowControl,
+    /// Fields written by other versions, kept as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl SerialSettings {
+    pub const BAUD_RATES: [u32; 11] = [
+        1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600,
+    ];
+    pub const DATA_BITS: [u8; 4] = [5, 6, 7, 8];
+    pub const STOP_BITS: [u8; 2] = [1, 2];
+}
+
+impl Default for SerialSettings {
+    fn default() -> Self {
+        Self {
+            device: "/dev/ttyUSB0".into(),
+            baud_rate: 115200,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+            flow_control: FlowControl::None,
+            extra: Map::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+impl Parity {
+    pub const ALL: [Parity; 3] = [Parity::None, Parity::Odd, Parity::Even];
+}
+
+impl std::fmt::Display for Parity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parity::None => write!(f, "Aucune"),
+            Parity::Odd => write!(f, "Impaire"),
+            Parity::Even => write!(f, "Paire"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// RTS/CTS
+    Hardware,
+    /// XON/XOFF
+    Software,
+}
+
+impl FlowControl {
+    pub const ALL: [FlowControl; 3] = [FlowControl::None, FlowControl::Hardware, FlowControl::Software];
+}
+
+impl std::fmt::Display for FlowControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlowControl::None => write!(f, "Aucun"),
+            FlowControl::Hardware => write!(f, "Matériel (RTS/CTS)"),
+            FlowControl::Software => write!(f, "Logiciel (XON/XOFF)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"[{
+        "id": "6f1c9b52-3f0e-4a53-9a43-0c1f6a0f2d11",
+        "name": "web",
+        "ip": "10.0.0.1",
+        "port": "22",
+        "username": "admin",
+        "group": "PROD",
+        "theme": "Slate",
+        "terminal_count": 1
+    }]"#;
+
+    #[test]
+    fn bare_array_is_migrated() {
+        let (store, profiles) = ProfileStore::parse(V1).unwrap();
+        assert!(!store.read_only);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "web");
+        assert_eq!(profiles[0].logging, LogSettings::default());
+
+        let saved: Value = serde_json::from_str(&store.to_json(&profiles).unwrap()).unwrap();
+        assert_eq!(saved["version"], SCHEMA_VERSION);
+        assert_eq!(saved["profiles"][0]["ip"], "10.0.0.1");
+    }
+
+    #[test]
+    fn unknown_fields_survive_a_save() {
+        let data = r#"{
+            "version": 2,
+            "profiles": [{
+                "id": "6f1c9b52-3f0e-4a53-9a43-0c1f6a0f2d11",
+                "name": "web", "ip": "10.0.0.1", "port": "22", "username": "admin",
+                "group": "PROD", "theme": "Slate", "terminal_count": 1,
+                "future_option": {"enabled": true}
+            }],
+            "settings": {"future_setting": 3},
+            "future_section": [1, 2]
+        }"#;
+        let (store, profiles) = ProfileStore::parse(data).unwrap();
+        let saved: Value = serde_json::from_str(&store.to_json(&profiles).unwrap()).unwrap();
+        assert_eq!(saved["profiles"][0]["future_option"]["enabled"], true);
+        assert_eq!(saved["settings"]["future_setting"], 3);
+        assert_eq!(saved["future_section"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn unknown_nested_fields_survive_a_save() {
+        let data = r#"{
+            "version": 2,
+            "profiles": [{
+                "id": "6f1c9b52-3f0e-4a53-9a43-0c1f6a0f2d11",
+                "name": "web", "ip": "10.0.0.1", "port": "22", "username": "admin",
+                "group": "PROD", "theme": "Slate", "terminal_count": 1,
+                "logging": {"mode": "Raw", "future": 1},
+                "network": {"keepalive_interval": 60, "future": 2},
+                "algorithms": {"kex": ["curve25519-sha256"], "future": 3},
+                "reconnect": {"auto": true, "future": 4},
+                "serial": {"device": "COM3", "future": 5}
+            }],
+            "settings": {"folders": {"PROD": {"username": "ops", "future": 6}}}
+        }"#;
+        let (store, mut profiles) = ProfileStore::parse(data).unwrap();
+        // a preset replaces the lists only
+        profiles[0].algorithms = AlgorithmPreset::Hardened.apply_to(&profiles[0].algorithms);
+        assert_eq!(AlgorithmPreset::matching(&profiles[0].algorithms), Some(AlgorithmPreset::Hardened));
+
+        let saved: Value = serde_json::from_str(&store.to_json(&profiles).unwrap()).unwrap();
+        let profile = &saved["profiles"][0];
+        for (section, value) in [("logging", 1), ("network", 2), ("algorithms", 3), ("reconnect", 4), ("serial", 5)] {
+            assert_eq!(profile[section]["future"], value, "{}", section);
+        }
+        assert_eq!(profile["network"]["keepalive_interval"], 60);
+        assert_eq!(saved["settings"]["folders"]["PROD"]["future"], 6);
+    }
+
+    #[test]
+    fn newer_file_is_read_only_and_garbage_is_an_error() {
+        let newer = format!(r#"{{"version": {}, "profiles": []}}"#, SCHEMA_VERSION + 1);
+        let (store, _) = ProfileStore::parse(&newer).unwrap();
+        assert!(store.read_only);
+        assert!(store.save(&[]).is_err());
+
+        assert!(ProfileStore::parse("{ not json").is_err());
+        assert!(ProfileStore::parse(r#"{"profiles": []}"#).is_err());
+        assert!(ProfileStore::parse(r#"[{"name": 3}]"#).is_err());
+    }
+
+    #[test]
+    fn version_zero_is_an_error() {
+        let err = ProfileStore::parse(r#"{"version": 0, "profiles": []}"#).unwrap_err();
+        assert_eq!(err, "version 0 invalide");
+        assert!(ProfileStore::parse(r#"{"version": 4294967296, "profiles": []}"#).is_err());
+    }
+
+    #[test]
+    fn unreadable_files_are_quarantined_unless_newer() {
+        let dir = std::env::temp_dir().join(format!("rustty-models-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // newer layout this version can't decode: refused, left in place, never saved over
+        let newer = dir.join("newer.json");
+        let data = format!(r#"{{"version": {}, "profiles": {{"by_id": {{}}}}}}"#, SCHEMA_VERSION + 1);
+        std::fs::write(&newer, &data).unwrap();
+        let (store, profiles, error) = ProfileStore::load_from(&newer);
+        assert!(store.read_only && profiles.is_empty());
+        assert!(error.unwrap().contains("version plus récente"));
+        assert_eq!(std::fs::read_to_string(&newer).unwrap(), data);
+
+        let corrupt = dir.join("corrupt.json");
+        std::fs::write(&corrupt, "{ not json").unwrap();
+        let (store, _, error) = ProfileStore::load_from(&corrupt);
+        assert!(!store.read_only);
+        assert!(error.unwrap().contains("mis de côté"));
+        assert!(!corrupt.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tags_sorting_and_recent_connections() {
+        assert_eq!(Profile::parse_tags("Web, prod ,,web"), ["web", "prod"]);
+
+        let mut web = Profile {
+            name: "frontal".into(),
+            ip: "10.0.0.10".into(),
+            ..Profile::default()
+        };
+
+        let mut db = Profile::default();
+        db.record_connection(100);
+        web.record_connection(50);
+        web.record_connection(200);
+        assert_eq!(web.connection_count, 2);
+
+        db.ip = "10.0.0.9".into();
+        assert!(SortColumn::Ip.compare(&db, &web).is_lt(), "compared as addresses");
+
+        let profiles = vec![Profile::default(), db, web];
+        assert_eq!(recent(&profiles, 10), [2, 1]);
+        assert_eq!(recent(&profiles, 1), [2]);
+    }
+
+    #[test]
+    fn reconnection_backs_off_up_to_a_minute() {
+        let delays: Vec<u64> = (0..8).map(|n| ReconnectSettings::delay(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(ReconnectSettings::delay(u32::MAX).as_secs(), 60);
+    }
+}
@@ -0,0 +1,98 @@
+//! TCP connection of the SSH, Telnet and raw sessions, with the socket options of the profile.
+
+use std::net::SocketAddr;
+
+use crate::connect_error::ConnectError;
+use crate::models::NetworkSettings;
+use tokio::net::TcpStream;
+
+/// Connect within the timeout of the profile, then set its socket options
+pub async fn connect(host: &str, port: u16, settings: &NetworkSettings) -> Result<TcpStream, ConnectError> {
+    // resolved first to tell a bad name from an unreachable host
+    let addrs = resolve(host, port).await?;
+    connect_to(host, port, &addrs, settings).await
+}
+
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, ConnectError> {
+    Ok(tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| ConnectError::Dns {
+            host: host.to_string(),
+            detail: e.to_string(),
+        })?
+        .collect())
+}
+
+/// Connect to the first of the resolved addresses that answers
+pub async fn connect_to(
+    host: &str,
+    port: u16,
+    addrs: &[SocketAddr],
+    settings: &NetworkSettings,
+) -> Result<TcpStream, ConnectError> {
+    let connecting = TcpStream::connect(addrs);
+    let result = match settings.connect_timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, connecting).await.map_err(|_| ConnectError::Timeout {
+            host: host.to_string(),
+            port,
+        })?,
+        None => connecting.await,
+    };
+    let stream = result.map_err(|e| match e.kind() {
+        std::io::ErrorKind::ConnectionRefused => ConnectError::Refused {
+            host: host.to_string(),
+            port,
+        },
+        std::io::ErrorKind::TimedOut => ConnectError::Timeout {
+            host: host.to_string(),
+            port,
+        },
+        _ => network_error(host, port, e),
+    })?;
+
+    stream.set_nodelay(settings.tcp_nodelay).map_err(|e| network_error(host, port, e))?;
+    if settings.tcp_keepalive {
+        socket2::SockRef::from(&stream)
+            .set_keepalive(true)
+            .map_err(|e| network_error(host, port, e))?;
+    }
+    Ok(stream)
+}
+
+pub fn network_error(host: &str, port: u16, error: std::io::Error) -> ConnectError {
+    ConnectError::Network {
+        host: host.to_string(),
+        port,
+        detail: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn socket_options_follow_the_profile() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let settings = NetworkSettings {
+            tcp_nodelay: false,
+            tcp_keepalive: true,
+            ..NetworkSettings::default()
+        };
+
+        let stream = connect("127.0.0.1", port, &settings).await.unwrap();
+        assert!(!stream.nodelay().unwrap());
+        assert!(socket2::SockRef::from(&stream).keepalive().unwrap());
+
+        drop(listener);
+        let refused = connect("127.0.0.1", port, &settings).await.unwrap_err();
+        assert_eq!(
+            refused,
+            ConnectError::Refused {
+                host: "127.0.0.1".into(),
+                port
+            }
+        );
+    }
+}
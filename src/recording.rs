@@ -0,0 +1,414 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncWriteExt, BufWriter},
+    sync::{mpsc, oneshot},
+};
+
+/// Folder where the terminal recordings (.cast) are written
+pub fn recordings_dir() -> PathBuf {
+    crate::storage::data_dir().join("recordings")
+}
+
+/// First line of an asciicast v2 file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Events kept for playback ("i" input and "m" marker events are ignored)
+#[derive(Debug, Clone)]
+pub enum CastEvent {
+    Output(String),
+    Resize { cols: u16, rows: u16 },
+}
+
+enum RecordCommand {
+    /// (seconds since the start, event code, event data)
+    Event(f64, &'static str, String),
+    Close(oneshot::Sender<()>),
+}
+
+/// Handle kept by the UI for a terminal window being recorded.
+/// Timestamps are taken when the data reaches the UI, the file is written by a background task.
+pub struct SessionRecorder {
+    sender: mpsc::UnboundedSender<RecordCommand>,
+    started: Instant,
+    /// Trailing bytes of an UTF-8 character split across two SSH packets
+    pending: Vec<u8>,
+}
+
+impl SessionRecorder {
+    /// Create the recorder and the future that writes the file (to run with Task::perform)
+    pub fn start(
+        path: PathBuf,
+        cols: u16,
+        rows: u16,
+        title: String,
+    ) -> (Self, impl Future<Output = Result<PathBuf, String>> + use<>) {
+        let header = CastHeader {
+            version: 2,
+            width: cols,
+            height: rows,
+            timestamp: Some(chrono::Local::now().timestamp()),
+            title: Some(title),
+        };
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let recorder = Self {
+            sender,
+            started: Instant::now(),
+            pending: Vec::new(),
+        };
+        (recorder, write_loop(path, header, receiver))
+    }
+
+    /// Default file name of a new recording: <recordings folder>/<host>-<date>-<time>.cast,
+    /// "<host>-<date>-<time> (2).cast" and so on when it is taken
+    pub fn default_path(host: &str) -> PathBuf {
+        let host: String = host
+            .chars()
+            .map(|c| if matches!(c, ':' | '/' | '\\') { '_' } else { c })
+            .collect();
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        recordings_dir().join(format!("{}-{}.cast", host, stamp))
+    }
+
+    /// Record bytes received from the server ("o" event)
+    pub fn output(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let text = take_utf8(&mut self.pending);
+        if !text.is_empty() {
+            self.send("o", text);
+        }
+    }
+
+    /// Record a new terminal size ("r" event)
+    pub fn resize(&self, cols: u16, rows: u16) {
+        self.send("r", format!("{}x{}", cols, rows));
+    }
+
+    /// Flush and close the file, resolves once everything queued before has been written
+    pub fn close(&self) -> impl Future<Output = ()> + use<> {
+        let (ack, done) = oneshot::channel();
+        let sent = self.sender.send(RecordCommand::Close(ack)).is_ok();
+        async move {
+            if sent {
+                let _ = done.await;
+            }
+        }
+    }
+
+    fn send(&self, code: &'static str, data: String) {
+        let t = self.started.elapsed().as_secs_f64();
+        let _ = self.sender.send(RecordCommand::Event(t, code, data));
+    }
+}
+
+/// Decode the complete UTF-8 prefix of `buf`, keeping an unfinished trailing character for later.
+/// Invalid bytes are replaced (asciicast stores text, not bytes).
+fn take_utf8(buf: &mut Vec<u8>) -> String {
+    let mut text = String::with_capacity(buf.len());
+    let mut rest: &[u8] = buf;
+    while let Err(e) = std::str::from_utf8(rest) {
+        let (valid, after) = rest.split_at(e.valid_up_to());
+        text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+        match e.error_len() {
+            Some(len) => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                rest = &after[len..];
+            }
+            // the input ends in the middle of a character
+            None => {
+                rest = after;
+                break;
+            }
+        }
+    }
+    let rest = match std::str::from_utf8(rest) {
+        Ok(s) => {
+            text.push_str(s);
+            Vec::new()
+        }
+        Err(_) => rest.to_vec(),
+    };
+    *buf = rest;
+    text
+}
+
+async fn write_loop(
+    path: PathBuf,
+    header: CastHeader,
+    mut receiver: mpsc::UnboundedReceiver<RecordCommand>,
+) -> Result<PathBuf, String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    // two recordings started in the same second must not truncate each other
+    let (file, path) = crate::storage::create_unique(&path).await?;
+    let io_err = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let mut writer = BufWriter::new(file);
+
+    let mut line = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await.map_err(io_err)?;
+
+    while let Some(command) = receiver.recv().await {
+        match command {
+            RecordCommand::Event(t, code, data) => {
+                // asciicast v2 event line: [time, code, data]
+                let mut line = serde_json::to_string(&(t, code, data)).map_err(|e| e.to_string())?;
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await.map_err(io_err)?;
+                if receiver.is_empty() {
+                    writer.flush().await.map_err(io_err)?;
+                }
+            }
+            RecordCommand::Close(ack) => {
+                writer.shutdown().await.map_err(io_err)?;
+                let _ = ack.send(());
+                return Ok(path);
+            }
+        }
+    }
+
+    writer.shutdown().await.map_err(io_err)?;
+    Ok(path)
+}
+
+/// A recording loaded in memory for playback
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub path: PathBuf,
+    pub header: CastHeader,
+    /// Sorted by time (seconds since the start)
+    pub events: Vec<(f64, CastEvent)>,
+}
+
+impl Recording {
+    /// Read and parse an asciicast v2 file
+    pub async fn load(path: PathBuf) -> Result<Self, String> {
+        let data = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(path, &data)
+    }
+
+    pub fn parse(path: PathBuf, data: &str) -> Result<Self, String> {
+        let mut lines = data.lines().filter(|l| !l.trim().is_empty());
+
+        let header: CastHeader = lines
+            .next()
+            .ok_or("Fichier vide".to_string())
+            .and_then(|l| serde_json::from_str(l).map_err(|e| format!("En-tête invalide : {}", e)))?;
+        if header.version != 2 {
+            return Err(format!("Version asciicast {} non supportée", header.version));
+        }
+
+        let mut events = Vec::new();
+        for (n, line) in lines.enumerate() {
+            let (t, code, data): (f64, String, String) = serde_json::from_str(line)
+                .map_err(|e| format!("Évènement {} invalide : {}", n + 1, e))?;
+            let event = match code.as_str() {
+                "o" => CastEvent::Output(data),
+                "r" => match data.split_once('x').map(|(c, r)| (c.parse(), r.parse())) {
+                    Some((Ok(cols), Ok(rows))) => CastEvent::Resize { cols, rows },
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            events.push((t, event));
+        }
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(Self { path, header, events })
+    }
+
+    /// Time of the last event, in seconds
+    pub fn duration(&self) -> f64 {
+        self.events.last().map(|(t, _)| *t).unwrap_or(0.0)
+    }
+
+    pub fn title(&self) -> String {
+        self.header.title.clone().unwrap_or_else(|| {
+            self.path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Replays a recording through its own VT100 parser, no connection involved
+pub struct Player {
+    pub recording: Recording,
+    parser: vt100::Parser,
+    /// Index of the next event to apply
+    next: usize,
+    /// Current position in seconds
+    position: f64,
+    pub speed: f64,
+    pub paused: bool,
+}
+
+impl Player {
+    pub const SPEEDS: [f64; 5] = [1.0, 2.0, 4.0, 8.0, 16.0];
+
+    pub fn new(recording: Recording) -> Self {
+        let parser = Self::fresh_parser(&recording.header);
+        Self {
+            recording,
+            parser,
+            next: 0,
+            position: 0.0,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn screen(&self) -> &vt100::Screen {
+        self.parser.screen()
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next >= self.recording.events.len()
+    }
+
+    /// Move forward by `elapsed` real seconds (scaled by the speed), stops at the end
+    pub fn advance(&mut self, elapsed: f64) {
+        if self.paused {
+            return;
+        }
+        let target = (self.position + elapsed * self.speed).min(self.recording.duration());
+        self.apply_until(target);
+        if self.finished() {
+            self.paused = true;
+        }
+    }
+
+    /// Jump to a position: going back replays the recording from the start
+    pub fn seek(&mut self, target: f64) {
+        let target = target.clamp(0.0, self.recording.duration());
+        if target < self.position {
+            self.parser = Self::fresh_parser(&self.recording.header);
+            self.next = 0;
+        }
+        self.apply_until(target);
+    }
+
+    /// Play again from the start once the end is reached
+    pub fn toggle_pause(&mut self) {
+        if self.paused && self.finished() {
+            self.seek(0.0);
+        }
+        self.paused = !self.paused;
+    }
+
+    fn apply_until(&mut self, target: f64) {
+        while let Some((t, event)) = self.recording.events.get(self.next) {
+            if *t > target {
+                break;
+            }
+            match event {
+                CastEvent::Output(data) => self.parser.process(data.as_bytes()),
+                CastEvent::Resize { cols, rows } => self.parser.set_size(*rows, *cols),
+            }
+            self.next += 1;
+        }
+        self.position = target;
+    }
+
+    fn fresh_parser(header: &CastHeader) -> vt100::Parser {
+        vt100::Parser::new(header.height.max(1), header.width.max(1), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn written_recording_parses_back() {
+        let path = std::env::temp_dir().join(format!("rustty-cast-{}.cast", uuid::Uuid::new_v4()));
+        let (mut recorder, worker) = SessionRecorder::start(path.clone(), 80, 24, "test".into());
+        let worker = tokio::spawn(worker);
+
+        // "é" is split across two packets and must come out whole
+        recorder.output(b"caf\xc3");
+        recorder.output(b"\xa9\r\n");
+        recorder.resize(100, 30);
+        recorder.output(b"\xffok");
+        recorder.close().await;
+        assert_eq!(worker.await.unwrap(), Ok(path.clone()));
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let recording = Recording::parse(path, &data).unwrap();
+
+        assert_eq!((recording.header.width, recording.header.height), (80, 24));
+        assert_eq!(recording.title(), "test");
+        let events: Vec<_> = recording.events.iter().map(|(_, e)| e).collect();
+        assert!(matches!(events[..], [
+            CastEvent::Output(a),
+            CastEvent::Output(b),
+            CastEvent::Resize { cols: 100, rows: 30 },
+            CastEvent::Output(c),
+        ] if a == "caf" && b == "é\r\n" && c == "\u{fffd}ok"));
+    }
+
+    #[tokio::test]
+    async fn recordings_never_overwrite_each_other() {
+        let dir = std::env::temp_dir().join(format!("rustty-cast-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("web-20260307-090502.cast");
+        let mut written = Vec::new();
+        for title in ["first", "second"] {
+            let (recorder, worker) = SessionRecorder::start(path.clone(), 80, 24, title.into());
+            let worker = tokio::spawn(worker);
+            recorder.close().await;
+            written.push(worker.await.unwrap().unwrap());
+        }
+
+        assert_eq!(written, [path.clone(), dir.join("web-20260307-090502 (2).cast")]);
+        let first = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(Recording::parse(path, &first).unwrap().title(), "first");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn seek_replays_from_the_start_when_going_back() {
+        let data = "{\"version\":2,\"width\":20,\"height\":2}\n\
+                    [0.5,\"o\",\"one\"]\n\
+                    [1.0,\"o\",\"\\r\\ntwo\"]\n\
+                    [2.0,\"r\",\"30x3\"]\n";
+        let mut player = Player::new(Recording::parse("t.cast".into(), data).unwrap());
+        let contents = |p: &Player| p.screen().contents();
+
+        player.seek(1.5);
+        assert_eq!(contents(&player), "one\ntwo");
+        assert!(!player.finished());
+
+        player.seek(0.7);
+        assert_eq!(player.position(), 0.7);
+        assert_eq!(contents(&player), "one");
+
+        // clamped to the duration, the resize is applied
+        player.seek(10.0);
+        assert_eq!(player.position(), 2.0);
+        assert!(player.finished());
+        assert_eq!(player.screen().size(), (3, 30));
+    }
+}
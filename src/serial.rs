@@ -0,0 +1,365 @@
+//! Serial console sessions: a tty device set to the line settings of the profile.
+
+use crate::messages::{Message, SshMessage};
+use crate::models::SerialSettings;
+use crate::session::{SessionChannel, SessionEnd};
+use iced::{
+    Task,
+    futures::{SinkExt, channel::mpsc},
+};
+
+pub use port::SerialPort;
+
+pub struct SerialService;
+
+impl SerialService {
+    /// Open the device for a terminal window: its channel, what it receives, then why it closed
+    pub fn open(window_id: iced::window::Id, settings: SerialSettings) -> Task<Message> {
+        Task::stream(iced::stream::channel(100, move |mut output| async move {
+            let end = match port::open(&settings) {
+                Ok(port) => forward(window_id, port, &settings, &mut output).await,
+                Err(e) => SessionEnd::lost(format!("Ouverture de {} impossible : {}", settings.device, e)),
+            };
+            let _ = output
+                .send(Message::Ssh(SshMessage::SessionEnded(window_id, end)))
+                .await;
+        }))
+    }
+}
+
+async fn forward(
+    window_id: iced::window::Id,
+    port: SerialPort,
+    settings: &SerialSettings,
+    output: &mut mpsc::Sender<Message>,
+) -> SessionEnd {
+    tracing::info!("Port série {} ouvert pour {:?}", settings.device, window_id);
+    let _ = output
+        .send(Message::Ssh(SshMessage::SetChannel(window_id, SessionChannel::Serial(port.clone()))))
+        .await;
+    let opened = format!("[{} ouvert : {}]\r\n", settings.device, line_summary(settings));
+    let _ = output
+        .send(Message::Ssh(SshMessage::DataReceived(window_id, opened.into_bytes())))
+        .await;
+
+    let mut buf = vec![0; 4096];
+    loop {
+        match port.read(&mut buf).await {
+            Ok(Some(0)) => return SessionEnd::lost(format!("{} déconnecté", settings.device)),
+            Ok(Some(n)) => {
+                let _ = output
+                    .send(Message::Ssh(SshMessage::DataReceived(window_id, buf[..n].to_vec())))
+                    .await;
+            }
+            // window closed
+            Ok(None) => return SessionEnd::expected(format!("{} fermé", settings.device)),
+            Err(e) => return SessionEnd::lost(format!("Erreur sur {} : {}", settings.device, e)),
+        }
+    }
+}
+
+/// "115200 bauds, 8N1, sans contrôle de flux"
+pub fn line_summary(settings: &SerialSettings) -> String {
+    use crate::models::{FlowControl, Parity};
+    let parity = match settings.parity {
+        Parity::None => 'N',
+        Parity::Odd => 'O',
+        Parity::Even => 'E',
+    };
+    let flow = match settings.flow_control {
+        FlowControl::None => "sans contrôle de flux",
+        FlowControl::Hardware => "RTS/CTS",
+        FlowControl::Software => "XON/XOFF",
+    };
+    format!(
+        "{} bauds, {}{}{}, {}",
+        settings.baud_rate, settings.data_bits, parity, settings.stop_bits, flow
+    )
+}
+
+/// Serial devices present in /dev (USB adapters and on-board UARTs), for the profile form
+pub fn available_ports() -> Vec<String> {
+    const PREFIXES: [&str; 5] = ["ttyUSB", "ttyACM", "ttyAMA", "cu.usb", "tty.usb"];
+    let Ok(entries) = std::fs::read_dir("/dev") else {
+        return Vec::new();
+    };
+    let mut ports: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+        .map(|name| format!("/dev/{}", name))
+        .collect();
+    ports.sort();
+    ports
+}
+
+#[cfg(unix)]
+mod port {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::sync::Arc;
+    use tokio::sync::Notify;
+
+    use crate::models::{FlowControl, Parity, SerialSettings};
+    use crate::tty::AsyncTty;
+
+    #[derive(Clone)]
+    pub struct SerialPort {
+        tty: AsyncTty,
+        /// Wakes the reader when the window is closed (the device itself stays open)
+        closed: Arc<Notify>,
+    }
+
+    /// Open the device in raw mode with the line settings
+    pub fn open(settings: &SerialSettings) -> io::Result<SerialPort> {
+        let path = CString::new(settings.device.as_str())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "chemin invalide"))?;
+        // SAFETY: path is a valid C string
+        let fd = unsafe {
+            libc::open(path.as_ptr(), libc::O_RDWR | libc::O_NOCTTY | libc::O_NONBLOCK | libc::O_CLOEXEC)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: open just returned it, nothing else owns it
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        configure(&fd, settings)?;
+        // another program opening the console at the same time would steal half of the bytes
+        // SAFETY: TIOCEXCL takes no argument
+        unsafe { libc::ioctl(std::os::fd::AsRawFd::as_raw_fd(&fd), libc::TIOCEXCL as _) };
+
+        Ok(SerialPort {
+            tty: AsyncTty::new(fd)?,
+            closed: Arc::new(Notify::new()),
+        })
+    }
+
+    impl SerialPort {
+        /// Some(0) when the device went away, None once the window was closed
+        pub async fn read(&self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+            tokio::select! {
+                n = self.tty.read(buf) => n.map(Some),
+                _ = self.closed.notified() => Ok(None),
+            }
+        }
+
+        pub async fn write(&self, data: &[u8]) -> io::Result<()> {
+            self.tty.write(data).await
+        }
+
+        pub fn close(&self) {
+            self.closed.notify_one();
+        }
+    }
+
+    fn configure(fd: &OwnedFd, settings: &SerialSettings) -> io::Result<()> {
+        let raw = std::os::fd::AsRawFd::as_raw_fd(fd);
+        let speed = speed(settings.baud_rate)?;
+        // SAFETY: termios is plain data, filled in by tcgetattr before use
+        unsafe {
+            let mut tio: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(raw, &mut tio) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // no echo, no line editing, no translation: the console gets every byte as typed
+            libc::cfmakeraw(&mut tio);
+            libc::cfsetispeed(&mut tio, speed);
+            libc::cfsetospeed(&mut tio, speed);
+            set_line(&mut tio, settings);
+
+            if libc::tcsetattr(raw, libc::TCSANOW, &tio) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Character size, parity, stop bits and flow control
+    pub fn set_line(tio: &mut libc::termios, settings: &SerialSettings) {
+        tio.c_cflag &= !libc::CSIZE;
+        tio.c_cflag |= match settings.data_bits {
+            5 => libc::CS5,
+            6 => libc::CS6,
+            7 => libc::CS7,
+            _ => libc::CS8,
+        };
+        // receiver on, modem lines ignored
+        tio.c_cflag |= libc::CREAD | libc::CLOCAL;
+        match settings.parity {
+            Parity::None => tio.c_cflag &= !(libc::PARENB | libc::PARODD),
+            Parity::Even => {
+                tio.c_cflag |= libc::PARENB;
+                tio.c_cflag &= !libc::PARODD;
+            }
+            Parity::Odd => tio.c_cflag |= libc::PARENB | libc::PARODD,
+        }
+        if settings.stop_bits == 2 {
+            tio.c_cflag |= libc::CSTOPB;
+        } else {
+            tio.c_cflag &= !libc::CSTOPB;
+        }
+        tio.c_cflag &= !libc::CRTSCTS;
+        tio.c_iflag &= !(libc::IXON | libc::IXOFF | libc::IXANY);
+        match settings.flow_control {
+            FlowControl::None => {}
+            FlowControl::Hardware => tio.c_cflag |= libc::CRTSCTS,
+            FlowControl::Software => tio.c_iflag |= libc::IXON | libc::IXOFF,
+        }
+        tio.c_cc[libc::VMIN] = 1;
+        tio.c_cc[libc::VTIME] = 0;
+    }
+
+    /// termios constant of a baud rate
+    pub fn speed(baud_rate: u32) -> io::Result<libc::speed_t> {
+        Ok(match baud_rate {
+            1200 => libc::B1200,
+            2400 => libc::B2400,
+            4800 => libc::B4800,
+            9600 => libc::B9600,
+            19200 => libc::B19200,
+            38400 => libc::B38400,
+            57600 => libc::B57600,
+            115200 => libc::B115200,
+            230400 => libc::B230400,
+            #[cfg(target_os = "linux")]
+            460800 => libc::B460800,
+            #[cfg(target_os = "linux")]
+            921600 => libc::B921600,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("vitesse non prise en charge : {} bauds", baud_rate),
+                ));
+            }
+        })
+    }
+}
+
+#[cfg(not(unix))]
+mod port {
+    use crate::models::SerialSettings;
+    use std::io;
+
+    #[derive(Clone)]
+    pub struct SerialPort;
+
+    pub fn open(_settings: &SerialSettings) -> io::Result<SerialPort> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "ports série non pris en charge sur ce système",
+        ))
+    }
+
+    impl SerialPort {
+        pub async fn read(&self, _buf: &mut [u8]) -> io::Result<Option<usize>> {
+            Ok(None)
+        }
+
+        pub async fn write(&self, _data: &[u8]) -> io::Result<()> {
+            Ok(())
+        }
+
+        pub fn close(&self) {}
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::models::{FlowControl, Parity};
+    use crate::tty::{AsyncTty, set_flags};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    /// A pseudo-terminal pair standing in for a USB adapter: (master side, slave device path)
+    fn pty_pair() -> (AsyncTty, OwnedFd, String) {
+        let (mut master, mut slave) = (0, 0);
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: valid out pointers, name is large enough for a /dev/pts path
+        let res = unsafe {
+            libc::openpty(&mut master, &mut slave, name.as_mut_ptr(), std::ptr::null_mut(), std::ptr::null_mut())
+        };
+        assert_eq!(res, 0);
+        // SAFETY: just returned by openpty
+        let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+        set_flags(&master, libc::F_SETFL, libc::O_NONBLOCK).unwrap();
+        // SAFETY: openpty wrote a NUL-terminated path
+        let path = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }.to_string_lossy().into_owned();
+        // the slave stays open so the master doesn't see a hang-up before the port is opened
+        (AsyncTty::new(master).unwrap(), slave, path)
+    }
+
+    fn settings(device: String) -> SerialSettings {
+        SerialSettings {
+            device,
+            baud_rate: 9600,
+            data_bits: 7,
+            parity: Parity::Even,
+            stop_bits: 2,
+            flow_control: FlowControl::Software,
+            ..SerialSettings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn line_settings_and_data_both_ways() {
+        let (master, slave, path) = pty_pair();
+        let port = port::open(&settings(path.clone())).unwrap();
+
+        // the settings are on the device (a pty always keeps 8 data bits without parity)
+        // SAFETY: termios is filled in by tcgetattr
+        let tio = unsafe {
+            let mut tio: libc::termios = std::mem::zeroed();
+            assert_eq!(libc::tcgetattr(slave.as_raw_fd(), &mut tio), 0);
+            tio
+        };
+        assert_ne!(tio.c_cflag & libc::CSTOPB, 0);
+        assert_ne!(tio.c_iflag & libc::IXON, 0);
+        assert_eq!(tio.c_lflag & libc::ECHO, 0);
+        // SAFETY: reads a field of a valid termios
+        assert_eq!(unsafe { libc::cfgetospeed(&tio) }, libc::B9600);
+
+        // raw mode: CR goes through untouched
+        port.write(b"AT\r").await.unwrap();
+        let mut buf = [0; 16];
+        let n = master.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"AT\r");
+
+        master.write(b"OK\r\n").await.unwrap();
+        let n = port.read(&mut buf).await.unwrap().unwrap();
+        assert_eq!(&buf[..n], b"OK\r\n");
+
+        port.close();
+        assert_eq!(port.read(&mut buf).await.unwrap(), None);
+    }
+
+    #[test]
+    fn line_flags() {
+        // SAFETY: termios is plain data
+        let mut tio: libc::termios = unsafe { std::mem::zeroed() };
+        port::set_line(&mut tio, &settings(String::new()));
+        assert_eq!(tio.c_cflag & libc::CSIZE, libc::CS7);
+        assert_eq!(tio.c_cflag & (libc::PARENB | libc::PARODD), libc::PARENB);
+        assert_ne!(tio.c_cflag & libc::CSTOPB, 0);
+        assert_eq!(tio.c_cflag & libc::CRTSCTS, 0);
+
+        port::set_line(&mut tio, &SerialSettings {
+            parity: Parity::Odd,
+            stop_bits: 1,
+            flow_control: FlowControl::Hardware,
+            ..SerialSettings::default()
+        });
+        assert_eq!(tio.c_cflag & libc::CSIZE, libc::CS8);
+        assert_eq!(tio.c_cflag & (libc::PARENB | libc::PARODD), libc::PARENB | libc::PARODD);
+        assert_eq!(tio.c_cflag & libc::CSTOPB, 0);
+        assert_ne!(tio.c_cflag & libc::CRTSCTS, 0);
+        assert_eq!(tio.c_iflag & libc::IXON, 0);
+    }
+
+    #[test]
+    fn unknown_device_and_speed_are_refused() {
+        assert!(port::open(&settings("/dev/rustty-absent".into())).is_err());
+        assert!(port::speed(12345).is_err());
+        assert_eq!(line_summary(&SerialSettings::default()), "115200 bauds, 8N1, sans contrôle de flux");
+    }
+}
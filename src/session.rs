@@ -0,0 +1,95 @@
+//! Channel a terminal window types into, whatever carries the session.
+
+use crate::local::LocalPty;
+use crate::serial::SerialPort;
+use crate::ssh::SshChannelArc;
+use crate::telnet::TcpChannel;
+
+/// How a session ended, and whether it was asked for
+#[derive(Clone, Debug)]
+pub struct SessionEnd {
+    pub reason: String,
+    /// Exit, logout or close from the other side: no automatic reconnection
+    pub expected: bool,
+}
+
+impl SessionEnd {
+    pub fn expected(reason: String) -> Self {
+        Self { reason, expected: true }
+    }
+
+    /// Transport error, device gone or connection refused
+    pub fn lost(reason: String) -> Self {
+        Self { reason, expected: false }
+    }
+}
+
+/// Terminal window whose session is lost, kept on its last screen
+#[derive(Debug, Clone)]
+pub struct Disconnected {
+    pub reason: String,
+    /// Reconnections tried since the session was lost
+    pub attempts: u32,
+    /// An attempt is under way
+    pub connecting: bool,
+    /// Wait before the next automatic attempt, None when there is none
+    pub retry_in: Option<std::time::Duration>,
+}
+
+#[derive(Clone)]
+pub enum SessionChannel {
+    Ssh(SshChannelArc),
+    Local(LocalPty),
+    /// Telnet or raw TCP
+    Tcp(TcpChannel),
+    Serial(SerialPort),
+}
+
+impl SessionChannel {
+    /// Keystrokes and pasted text
+    pub async fn send(&self, data: &[u8]) {
+        match self {
+            SessionChannel::Ssh(ch) => {
+                let _ = ch.lock().await.data(data).await;
+            }
+            SessionChannel::Local(pty) => {
+                let _ = pty.write(data).await;
+            }
+            SessionChannel::Tcp(tcp) => {
+                let _ = tcp.send(data).await;
+            }
+            SessionChannel::Serial(port) => {
+                let _ = port.write(data).await;
+            }
+        }
+    }
+
+    /// New size of the terminal, in characters
+    pub async fn resize(&self, rows: u16, cols: u16) {
+        match self {
+            SessionChannel::Ssh(ch) => {
+                let _ = ch.lock().await.window_change(cols as u32, rows as u32, 0, 0).await;
+            }
+            SessionChannel::Local(pty) => {
+                let _ = pty.resize(rows, cols);
+            }
+            SessionChannel::Tcp(tcp) => {
+                let _ = tcp.resize(rows, cols).await;
+            }
+            // a console has no idea of the window size
+            SessionChannel::Serial(_) => {}
+        }
+    }
+
+    /// Window closed: end the session
+    pub async fn close(&self) {
+        match self {
+            SessionChannel::Ssh(ch) => {
+                let _ = ch.lock().await.close().await;
+            }
+            SessionChannel::Local(pty) => pty.close(),
+            SessionChannel::Tcp(tcp) => tcp.close().await,
+            SessionChannel::Serial(port) => port.close(),
+        }
+    }
+}
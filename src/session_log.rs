@@ -0,0 +1,285 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncWriteExt, BufWriter},
+    sync::{mpsc, oneshot},
+};
+
+use crate::models::{LogMode, LogSettings};
+
+/// Folder where relative log file templates are resolved
+pub fn session_log_dir() -> PathBuf {
+    crate::storage::data_dir().join("sessions")
+}
+
+enum LogCommand {
+    Data(Vec<u8>),
+    /// Flush, close the file and acknowledge through the oneshot
+    Close(oneshot::Sender<()>),
+}
+
+/// Handle kept by the UI for one terminal window.
+/// Writing never blocks the UI thread: bytes are queued and a background task owns the file.
+#[derive(Clone)]
+pub struct SessionLogger {
+    sender: mpsc::UnboundedSender<LogCommand>,
+    path: PathBuf,
+}
+
+impl SessionLogger {
+    /// Create the logger of a session, or None when logging is disabled for the profile.
+    /// The returned future owns the file and must be run (Task::perform) for anything to be written,
+    /// data sent before it starts is kept in the channel so the first bytes of the session are not lost.
+    /// `open` are the files of the other windows: one already written gets a "name (n).log" sibling.
+    pub fn start(
+        settings: &LogSettings,
+        host: &str,
+        port: &str,
+        open: &[&Path],
+    ) -> Option<(Self, impl Future<Output = Result<PathBuf, String>> + use<>)> {
+        if settings.mode == LogMode::Off {
+            return None;
+        }
+
+        // an absolute template is kept as is by join
+        let name = expand_template(&settings.file_template, host, port, Local::now());
+        let path = free_path(session_log_dir().join(name), open);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let worker = write_loop(path.clone(), settings.mode, settings.append, receiver);
+
+        Some((Self { sender, path }, worker))
+    }
+
+    /// File this logger writes to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queue raw bytes received from the server
+    pub fn write(&self, bytes: &[u8]) {
+        let _ = self.sender.send(LogCommand::Data(bytes.to_vec()));
+    }
+
+    /// Flush and close the file, resolves once everything queued before has been written
+    pub fn close(&self) -> impl Future<Output = ()> + use<> {
+        let (ack, done) = oneshot::channel();
+        let sent = self.sender.send(LogCommand::Close(ack)).is_ok();
+        async move {
+            if sent {
+                let _ = done.await;
+            }
+        }
+    }
+}
+
+// "stem (n).ext" for the first n not written by another window of this process
+fn free_path(path: PathBuf, open: &[&Path]) -> PathBuf {
+    if !open.contains(&path.as_path()) {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !open.contains(&candidate.as_path()))
+        .unwrap_or(path)
+}
+
+/// Replace the placeholders of a log file name template:
+/// &H host, &P port, &Y year, &M month, &D day, &T time (HHMMSS), && a literal '&'
+pub fn expand_template(template: &str, host: &str, port: &str, now: DateTime<Local>) -> PathBuf {
+    // ':' (IPv6) and path separators are not welcome in a file name
+    let host: String = host
+        .chars()
+        .map(|c| if matches!(c, ':' | '/' | '\\') { '_' } else { c })
+        .collect();
+
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('H') => out.push_str(&host),
+            Some('P') => out.push_str(port),
+            Some('Y') => out.push_str(&now.format("%Y").to_string()),
+            Some('M') => out.push_str(&now.format("%m").to_string()),
+            Some('D') => out.push_str(&now.format("%d").to_string()),
+            Some('T') => out.push_str(&now.format("%H%M%S").to_string()),
+            Some('&') => out.push('&'),
+            // unknown placeholder: kept as is
+            Some(other) => {
+                out.push('&');
+                out.push(other);
+            }
+            None => out.push('&'),
+        }
+    }
+    PathBuf::from(out)
+}
+
+async fn write_loop(
+    path: PathBuf,
+    mode: LogMode,
+    append: bool,
+    mut receiver: mpsc::UnboundedReceiver<LogCommand>,
+) -> Result<PathBuf, String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    let mut filter = PrintableFilter::default();
+    let mut printable = Vec::new();
+
+    let io_err = |e: std::io::Error| format!("{}: {}", path.display(), e);
+
+    while let Some(command) = receiver.recv().await {
+        match command {
+            LogCommand::Data(bytes) => {
+                let chunk = if mode == LogMode::Printable {
+                    printable.clear();
+                    filter.feed(&bytes, &mut printable);
+                    &printable[..]
+                } else {
+                    &bytes[..]
+                };
+                writer.write_all(chunk).await.map_err(io_err)?;
+
+                // flush once the burst is over so the file stays readable while the session runs
+                if receiver.is_empty() {
+                    writer.flush().await.map_err(io_err)?;
+                }
+            }
+            LogCommand::Close(ack) => {
+                writer.shutdown().await.map_err(io_err)?;
+                let _ = ack.send(());
+                return Ok(path);
+            }
+        }
+    }
+
+    // every handle was dropped without an explicit close
+    writer.shutdown().await.map_err(io_err)?;
+    Ok(path)
+}
+
+#[derive(Default, Clone, Copy)]
+enum FilterState {
+    #[default]
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    /// OSC, DCS, APC, PM, SOS: ends with BEL or ESC \
+    String,
+    StringEscape,
+}
+
+/// Strips escape sequences and control characters from the output stream.
+/// Keeps its state between chunks because a sequence can be split across two SSH packets.
+#[derive(Default)]
+struct PrintableFilter {
+    state: FilterState,
+}
+
+impl PrintableFilter {
+    fn feed(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &b in input {
+            self.state = match self.state {
+                FilterState::Ground => match b {
+                    0x1b => FilterState::Escape,
+                    b'\n' | b'\t' => {
+                        out.push(b);
+                        FilterState::Ground
+                    }
+                    // other C0 controls (\r included) and DEL
+                    0x00..=0x1f | 0x7f => FilterState::Ground,
+                    _ => {
+                        out.push(b);
+                        FilterState::Ground
+                    }
+                },
+                FilterState::Escape => match b {
+                    b'[' => FilterState::Csi,
+                    b']' | b'P' | b'X' | b'^' | b'_' => FilterState::String,
+                    0x20..=0x2f => FilterState::EscapeIntermediate,
+                    _ => FilterState::Ground,
+                },
+                FilterState::EscapeIntermediate => match b {
+                    0x20..=0x2f => FilterState::EscapeIntermediate,
+                    _ => FilterState::Ground,
+                },
+                FilterState::Csi => match b {
+                    0x40..=0x7e => FilterState::Ground,
+                    _ => FilterState::Csi,
+                },
+                FilterState::String => match b {
+                    0x07 => FilterState::Ground,
+                    0x1b => FilterState::StringEscape,
+                    _ => FilterState::String,
+                },
+                FilterState::StringEscape => match b {
+                    b'\\' => FilterState::Ground,
+                    _ => FilterState::String,
+                },
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn template_placeholders_are_expanded() {
+        let now = Local.with_ymd_and_hms(2026, 3, 7, 9, 5, 2).unwrap();
+        let path = expand_template("&H-&P-&Y&M&D-&T-&&-&X.log", "fe80::1", "22", now);
+        assert_eq!(path, PathBuf::from("fe80__1-22-20260307-090502-&-&X.log"));
+    }
+
+    #[test]
+    fn windows_of_the_same_session_get_their_own_file() {
+        let settings = LogSettings {
+            mode: LogMode::Raw,
+            file_template: "&H.log".into(),
+            ..LogSettings::default()
+        };
+        let (first, _) = SessionLogger::start(&settings, "web", "22", &[]).unwrap();
+        let (second, _) = SessionLogger::start(&settings, "web", "22", &[first.path()]).unwrap();
+        let (third, _) = SessionLogger::start(&settings, "web", "22", &[first.path(), second.path()]).unwrap();
+
+        assert_eq!(first.path(), session_log_dir().join("web.log"));
+        assert_eq!(second.path(), session_log_dir().join("web (2).log"));
+        assert_eq!(third.path(), session_log_dir().join("web (3).log"));
+        // once a window is closed its name is free again
+        let (again, _) = SessionLogger::start(&settings, "web", "22", &[second.path()]).unwrap();
+        assert_eq!(again.path(), first.path());
+    }
+
+    #[test]
+    fn printable_filter_strips_sequences_split_across_chunks() {
+        let mut filter = PrintableFilter::default();
+        let mut out = Vec::new();
+        filter.feed(b"\x1b]0;title\x07\x1b[1;3", &mut out);
+        filter.feed(b"2mok\x1b[0m\r\n\x1b(Bnext\tline\x1bP+q\x1b\\", &mut out);
+        assert_eq!(out, b"ok\nnext\tline");
+    }
+}
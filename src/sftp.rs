@@ -0,0 +1,991 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use iced::{Task, futures::channel::mpsc};
+use russh_sftp::{
+    client::SftpSession,
+    protocol::{FileAttributes, OpenFlags},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::messages::{Message, SftpMessage};
+use crate::ssh::SshHandle;
+
+// Shared because the listing, the file operations and every transfer run in their own task
+pub type SftpSessionArc = Arc<SftpSession>;
+
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// One line of a remote directory listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Permission bits only (e.g. 0o755)
+    pub permissions: u32,
+    /// Unix timestamp of the last modification
+    pub modified: Option<u32>,
+}
+
+impl RemoteEntry {
+    /// "drwxr-xr-x" style display of the type and permissions
+    pub fn mode_string(&self) -> String {
+        let mut s = String::with_capacity(10);
+        s.push(if self.is_dir { 'd' } else { '-' });
+        for shift in [6, 3, 0] {
+            let bits = (self.permissions >> shift) & 0o7;
+            s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            s.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+        }
+        s
+    }
+}
+
+/// Parse an octal mode typed by the user ("755", "0644")
+pub fn parse_mode(input: &str) -> Option<u32> {
+    let input = input.trim();
+    if input.is_empty() || input.len() > 4 {
+        return None;
+    }
+    u32::from_str_radix(input, 8).ok().filter(|m| *m <= 0o7777)
+}
+
+/// Join a remote directory and a name, SFTP paths always use '/'
+pub fn join(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// Parent of a remote path ("/" stays "/")
+pub fn parent(path: &str) -> String {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some(("", _)) | None => "/".into(),
+        Some((parent, _)) => parent.into(),
+    }
+}
+
+/// Local folder receiving the downloads (the user's Downloads folder when there is one)
+pub fn download_dir() -> PathBuf {
+    directories::UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(|d| d.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Open the "sftp" subsystem on a new channel of an existing SSH connection
+pub async fn open_session(handle: SshHandle) -> Result<SftpSessionArc, String> {
+    let channel = {
+        let h_lock = handle.lock().await;
+        h_lock
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("Ouverture du canal : {}", e))?
+    };
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| format!("Sous-système sftp refusé : {}", e))?;
+    let sftp = SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| format!("Initialisation SFTP : {}", e))?;
+    Ok(Arc::new(sftp))
+}
+
+/// Absolute form of a path, then its entries: folders first, then by name
+pub async fn list_dir(sftp: &SftpSession, path: &str) -> Result<(String, Vec<RemoteEntry>), String> {
+    let path = sftp.canonicalize(path).await.map_err(|e| format!("{}: {}", path, e))?;
+    let mut entries: Vec<RemoteEntry> = sftp
+        .read_dir(path.as_str())
+        .await
+        .map_err(|e| format!("{}: {}", path, e))?
+        .map(|entry| {
+            let meta = entry.metadata();
+            RemoteEntry {
+                name: entry.file_name(),
+                is_dir: meta.is_dir(),
+                size: meta.len(),
+                permissions: meta.permissions.unwrap_or(0) & 0o7777,
+                modified: meta.mtime,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok((path, entries))
+}
+
+pub async fn rename(sftp: &SftpSession, from: &str, to: &str) -> Result<(), String> {
+    sftp.rename(from, to).await.map_err(|e| format!("{}: {}", from, e))
+}
+
+pub async fn remove(sftp: &SftpSession, path: &str, is_dir: bool) -> Result<(), String> {
+    let res = if is_dir {
+        sftp.remove_dir(path).await
+    } else {
+        sftp.remove_file(path).await
+    };
+    res.map_err(|e| format!("{}: {}", path, e))
+}
+
+pub async fn chmod(sftp: &SftpSession, path: &str, mode: u32) -> Result<(), String> {
+    let attrs = FileAttributes {
+        permissions: Some(mode),
+        ..FileAttributes::empty()
+    };
+    sftp.set_metadata(path, attrs)
+        .await
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+pub async fn mkdir(sftp: &SftpSession, path: &str) -> Result<(), String> {
+    sftp.create_dir(path).await.map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Copy a local file to the server. An existing remote file is never replaced,
+/// the copy goes to "name (2).ext", "name (3).ext"... instead.
+/// A cancelled upload removes the partial remote file.
+pub async fn upload(
+    sftp: &SftpSession,
+    local: &std::path::Path,
+    remote: &str,
+    cancel: &AtomicBool,
+    progress: impl FnMut(u64, u64),
+) -> Result<u64, String> {
+    let reader = tokio::fs::File::open(local)
+        .await
+        .map_err(|e| format!("{}: {}", local.display(), e))?;
+    let total = reader.metadata().await.map(|m| m.len()).unwrap_or(0);
+    let (mut writer, remote) = create_unique_remote(sftp, remote).await?;
+    tracing::info!("SFTP upload {} -> {}", local.display(), remote);
+
+    let res = match copy(reader, &mut writer, total, cancel, progress).await {
+        Ok(done) => writer.shutdown().await.map(|_| done).map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    if res.is_err() {
+        let _ = sftp.remove_file(&remote).await;
+    }
+    res
+}
+
+/// Remote counterpart of storage::create_unique: EXCLUDE makes the check and the creation one step
+async fn create_unique_remote(
+    sftp: &SftpSession,
+    remote: &str,
+) -> Result<(russh_sftp::client::fs::File, String), String> {
+    let flags = OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE;
+    let (dir, name) = remote.rsplit_once('/').unwrap_or(("", remote));
+    let mut candidate = remote.to_string();
+    for n in 2.. {
+        match sftp.open_with_flags(candidate.as_str(), flags).await {
+            Ok(file) => return Ok((file, candidate)),
+            // most servers answer a plain failure for an existing file
+            Err(_) if sftp.try_exists(candidate.as_str()).await.unwrap_or(false) => {
+                candidate = format!("{}/{}", dir, numbered(name, n));
+            }
+            Err(e) => return Err(format!("{}: {}", candidate, e)),
+        }
+    }
+    unreachable!()
+}
+
+// "name (n).ext", like the local copies ("archive.tar (2).gz", ".bashrc (2)")
+fn numbered(name: &str, n: u32) -> String {
+    match name.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) => format!("{} ({}){}", &name[..dot], n, &name[dot..]),
+        None => format!("{} ({})", name, n),
+    }
+}
+
+/// Copy a remote file to the local disk. An existing local file is never overwritten,
+/// the copy goes to "name (2).ext", "name (3).ext"... instead.
+/// A cancelled download removes the partial local file.
+pub async fn download(
+    sftp: &SftpSession,
+    remote: &str,
+    local: &std::path::Path,
+    cancel: &AtomicBool,
+    progress: impl FnMut(u64, u64),
+) -> Result<u64, String> {
+    let total = sftp.metadata(remote).await.map(|m| m.len()).unwrap_or(0);
+    let reader = sftp.open(remote).await.map_err(|e| format!("{}: {}", remote, e))?;
+    let (mut writer, local) = crate::storage::create_unique(local).await?;
+    tracing::info!("SFTP download {} -> {}", remote, local.display());
+
+    let res = match copy(reader, &mut writer, total, cancel, progress).await {
+        Ok(done) => writer.shutdown().await.map(|_| done).map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    if res.is_err() {
+        let _ = tokio::fs::remove_file(&local).await;
+    }
+    res
+}
+
+/// Copy everything from `reader`, the caller decides how to end the stream
+async fn copy(
+    mut reader: impl AsyncRead + Unpin,
+    writer: &mut (impl AsyncWrite + Unpin),
+    total: u64,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<u64, String> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut done = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Transfert annulé".into());
+        }
+        let n = reader.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n]).await.map_err(|e| e.to_string())?;
+        done += n as u64;
+        progress(done, total.max(done));
+    }
+    writer.flush().await.map_err(|e| e.to_string())?;
+    Ok(done)
+}
+
+/// Upload through the "scp -t" sink protocol, for servers without the sftp subsystem
+pub async fn scp_upload(
+    handle: &SshHandle,
+    local: &std::path::Path,
+    remote_dir: &str,
+    cancel: &AtomicBool,
+    progress: impl FnMut(u64, u64),
+) -> Result<u64, String> {
+    let local_err = |e: std::io::Error| format!("{}: {}", local.display(), e);
+    let mut reader = tokio::fs::File::open(local).await.map_err(local_err)?;
+    let total = reader.metadata().await.map_err(local_err)?.len();
+    let name = local
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("{}: nom de fichier invalide", local.display()))?;
+
+    let name = scp_free_name(handle, remote_dir, &name).await?;
+    tracing::info!("scp upload {} -> {}", local.display(), join(remote_dir, &name));
+
+    let channel = open_channel(handle).await?;
+    channel
+        .exec(true, format!("scp -t -- {}", shell_quote(remote_dir)))
+        .await
+        .map_err(|e| format!("scp refusé : {}", e))?;
+    let mut stream = channel.into_stream();
+    let res = scp_send(&mut stream, &mut reader, &name, total, cancel, progress).await;
+    let _ = stream.shutdown().await;
+    res
+}
+
+async fn open_channel(handle: &SshHandle) -> Result<russh::Channel<russh::client::Msg>, String> {
+    let h_lock = handle.lock().await;
+    h_lock
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("Ouverture du canal : {}", e))
+}
+
+// the sink replaces an existing file: the shell picks the first free "name (n).ext" beforehand
+async fn scp_free_name(handle: &SshHandle, remote_dir: &str, name: &str) -> Result<String, String> {
+    let (stem, ext) = match name.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) => name.split_at(dot),
+        None => (name, ""),
+    };
+    let script = format!(
+        r#"f={name}; n=2; while [ -e {dir}/"$f" ]; do f={stem}" ($n)"{ext}; n=$((n+1)); done; printf %s "$f""#,
+        name = shell_quote(name),
+        dir = shell_quote(remote_dir),
+        stem = shell_quote(stem),
+        ext = shell_quote(ext),
+    );
+    let mut channel = open_channel(handle).await?;
+    channel.exec(true, script).await.map_err(|e| format!("scp refusé : {}", e))?;
+    let mut out = Vec::new();
+    while let Some(msg) = channel.wait().await {
+        if let russh::ChannelMsg::Data { data } = msg {
+            out.extend_from_slice(&data);
+        }
+    }
+    match String::from_utf8(out) {
+        Ok(free) if !free.is_empty() && !free.contains('/') => Ok(free),
+        _ => Err(format!("scp : aucun nom libre pour {} dans {}", name, remote_dir)),
+    }
+}
+
+// one file of the sink protocol: header, ack, content, trailing zero, ack
+async fn scp_send(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    reader: impl AsyncRead + Unpin,
+    name: &str,
+    total: u64,
+    cancel: &AtomicBool,
+    progress: impl FnMut(u64, u64),
+) -> Result<u64, String> {
+    let io_err = |e: std::io::Error| format!("scp : {}", e);
+
+    scp_ack(stream).await?;
+    let header = format!("C0644 {} {}\n", total, name.replace('\n', " "));
+    stream.write_all(header.as_bytes()).await.map_err(io_err)?;
+    scp_ack(stream).await?;
+
+    let done = copy(reader, stream, total, cancel, progress).await?;
+    stream.write_all(&[0]).await.map_err(io_err)?;
+    scp_ack(stream).await?;
+    Ok(done)
+}
+
+// 0 = ok, 1 = warning, 2 = fatal error, the last two followed by a message line
+async fn scp_ack(stream: &mut (impl AsyncRead + Unpin)) -> Result<(), String> {
+    let code = stream.read_u8().await.map_err(|e| format!("scp : {}", e))?;
+    if code == 0 {
+        return Ok(());
+    }
+    let mut message = Vec::new();
+    while let Ok(byte) = stream.read_u8().await {
+        if byte == b'\n' {
+            break;
+        }
+        message.push(byte);
+    }
+    Err(format!("scp : {}", String::from_utf8_lossy(&message).trim()))
+}
+
+/// Quote a path for the remote POSIX shell
+fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+/// How the files dropped on a terminal window reach the server
+#[derive(Clone)]
+pub enum Uploader {
+    Sftp(SftpSessionArc),
+    /// fallback when the server refuses the sftp subsystem
+    Scp(SshHandle),
+}
+
+impl Uploader {
+    /// Prefer SFTP, fall back on scp on the same connection
+    pub async fn open(handle: SshHandle) -> Result<Self, String> {
+        match open_session(handle.clone()).await {
+            Ok(sftp) => Ok(Uploader::Sftp(sftp)),
+            Err(e) => {
+                tracing::warn!("SFTP unavailable ({}), falling back to scp", e);
+                Ok(Uploader::Scp(handle))
+            }
+        }
+    }
+
+    pub async fn upload(
+        &self,
+        local: &std::path::Path,
+        remote_dir: &str,
+        cancel: &AtomicBool,
+        progress: impl FnMut(u64, u64),
+    ) -> Result<u64, String> {
+        match self {
+            Uploader::Sftp(sftp) => {
+                let name = local.file_name().unwrap_or_default().to_string_lossy();
+                upload(sftp, local, &join(remote_dir, &name), cancel, progress).await
+            }
+            Uploader::Scp(handle) => scp_upload(handle, local, remote_dir, cancel, progress).await,
+        }
+    }
+}
+
+/// Follows the OSC 7 sequences (ESC ] 7 ; file://host/path BEL) shells emit on each prompt
+#[derive(Debug, Default)]
+pub struct CwdTracker {
+    pending: Vec<u8>,
+    cwd: Option<String>,
+}
+
+impl CwdTracker {
+    const PREFIX: &'static [u8] = b"\x1b]7;";
+    /// An unterminated sequence longer than that is garbage
+    const MAX_PENDING: usize = 4096;
+
+    /// Last directory announced by the remote shell
+    pub fn cwd(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let mut consumed = 0;
+        while let Some(start) = find(&self.pending[consumed..], Self::PREFIX) {
+            let body_start = consumed + start + Self::PREFIX.len();
+            let body = &self.pending[body_start..];
+            // BEL or ST (ESC \) terminated
+            let Some(end) = body.iter().position(|&b| b == 0x07 || b == 0x1b) else {
+                consumed += start;
+                break;
+            };
+            if body[end] == 0x1b && body.get(end + 1).is_none() {
+                consumed += start;
+                break;
+            }
+            if let Some(path) = parse_osc7(&body[..end]) {
+                self.cwd = Some(path);
+            }
+            consumed = body_start + end + 1;
+        }
+
+        // keep an unfinished sequence, or the bytes that could start one
+        let tail = if find(&self.pending[consumed..], Self::PREFIX).is_some() {
+            consumed
+        } else {
+            self.pending.len().saturating_sub(Self::PREFIX.len() - 1).max(consumed)
+        };
+        self.pending.drain(..tail);
+        if self.pending.len() > Self::MAX_PENDING {
+            self.pending.clear();
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+// "file://host/some%20dir" -> "/some dir"
+fn parse_osc7(body: &[u8]) -> Option<String> {
+    let url = std::str::from_utf8(body).ok()?.strip_prefix("file://")?;
+    let path = &url[url.find('/')?..];
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut chars = path.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Direction of a file transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Upload,
+    Download,
+}
+
+pub struct SftpService;
+
+impl SftpService {
+    pub fn open(panel_id: iced::window::Id, handle: SshHandle) -> Task<Message> {
+        Task::perform(open_session(handle), move |res| {
+            Message::Sftp(SftpMessage::Ready(panel_id, res))
+        })
+    }
+
+    pub fn list(panel_id: iced::window::Id, sftp: SftpSessionArc, path: String) -> Task<Message> {
+        Task::perform(async move { list_dir(&sftp, &path).await }, move |res| {
+            Message::Sftp(SftpMessage::Listed(panel_id, res))
+        })
+    }
+
+    /// Run a file operation, then the panel refreshes its listing
+    pub fn operation<F>(panel_id: iced::window::Id, op: F) -> Task<Message>
+    where
+        F: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        Task::perform(op, move |res| {
+            Message::Sftp(SftpMessage::OperationDone(panel_id, res))
+        })
+    }
+
+    /// Upload or download a file, reporting progress a few times per second
+    pub fn transfer(
+        panel_id: iced::window::Id,
+        transfer_id: u64,
+        kind: TransferKind,
+        sftp: SftpSessionArc,
+        local: PathBuf,
+        remote: String,
+        cancel: Arc<AtomicBool>,
+    ) -> Task<Message> {
+        Self::report(panel_id, transfer_id, move |progress| async move {
+            match kind {
+                TransferKind::Upload => upload(&sftp, &local, &remote, &cancel, progress).await,
+                TransferKind::Download => download(&sftp, &remote, &local, &cancel, progress).await,
+            }
+        })
+    }
+
+    pub fn open_uploader(terminal_id: iced::window::Id, handle: SshHandle) -> Task<Message> {
+        Task::perform(Uploader::open(handle), move |res| {
+            Message::Sftp(SftpMessage::DropReady(terminal_id, res))
+        })
+    }
+
+    /// Upload a file dropped on a terminal window into `remote_dir`
+    pub fn drop_upload(
+        terminal_id: iced::window::Id,
+        transfer_id: u64,
+        uploader: Uploader,
+        local: PathBuf,
+        remote_dir: String,
+        cancel: Arc<AtomicBool>,
+    ) -> Task<Message> {
+        Self::report(terminal_id, transfer_id, move |progress| async move {
+            uploader.upload(&local, &remote_dir, &cancel, progress).await
+        })
+    }
+
+    // runs a transfer, reporting progress a few times per second, then its result
+    fn report<F, Fut>(window_id: iced::window::Id, transfer_id: u64, run: F) -> Task<Message>
+    where
+        F: FnOnce(Box<dyn FnMut(u64, u64) + Send>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<u64, String>> + Send,
+    {
+        Task::stream(iced::stream::channel(16, move |mut output: mpsc::Sender<Message>| async move {
+            let mut progress_output = output.clone();
+            let mut last_report = Instant::now();
+            let progress = move |done: u64, total: u64| {
+                if last_report.elapsed() >= Duration::from_millis(100) || done == total {
+                    last_report = Instant::now();
+                    let _ = progress_output.try_send(Message::Sftp(SftpMessage::TransferProgress(
+                        window_id,
+                        transfer_id,
+                        done,
+                        total,
+                    )));
+                }
+            };
+
+            let res = run(Box::new(progress)).await;
+
+            use iced::futures::SinkExt;
+            let _ = output
+                .send(Message::Sftp(SftpMessage::TransferFinished(
+                    window_id,
+                    transfer_id,
+                    res.map(|_| ()),
+                )))
+                .await;
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh_sftp::protocol::{
+        Attrs, Data, File, Handle, Name, OpenFlags, Status, StatusCode, Version,
+    };
+    use std::collections::HashMap;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    /// Minimal SFTP server stand-in serving a temporary folder
+    struct LocalFs {
+        root: PathBuf,
+        handles: HashMap<String, OpenHandle>,
+        next_handle: u32,
+    }
+
+    enum OpenHandle {
+        Dir(Option<PathBuf>),
+        File(std::fs::File),
+    }
+
+    fn ok(id: u32) -> Status {
+        Status {
+            id,
+            status_code: StatusCode::Ok,
+            error_message: "Ok".into(),
+            language_tag: "en-US".into(),
+        }
+    }
+
+    fn io_status(_: std::io::Error) -> StatusCode {
+        StatusCode::Failure
+    }
+
+    impl LocalFs {
+        fn path(&self, remote: &str) -> PathBuf {
+            self.root.join(remote.trim_start_matches('/'))
+        }
+
+        fn add_handle(&mut self, handle: OpenHandle) -> String {
+            self.next_handle += 1;
+            let name = self.next_handle.to_string();
+            self.handles.insert(name.clone(), handle);
+            name
+        }
+    }
+
+    impl russh_sftp::server::Handler for LocalFs {
+        type Error = StatusCode;
+
+        fn unimplemented(&self) -> Self::Error {
+            StatusCode::OpUnsupported
+        }
+
+        async fn init(
+            &mut self,
+            _version: u32,
+            _extensions: HashMap<String, String>,
+        ) -> Result<Version, Self::Error> {
+            Ok(Version::new())
+        }
+
+        async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+            let path = if path == "." { "/".to_string() } else { path };
+            Ok(Name {
+                id,
+                files: vec![File::dummy(path)],
+            })
+        }
+
+        async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+            let dir = self.path(&path);
+            if !dir.is_dir() {
+                return Err(StatusCode::NoSuchFile);
+            }
+            let handle = self.add_handle(OpenHandle::Dir(Some(dir)));
+            Ok(Handle { id, handle })
+        }
+
+        async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+            let Some(OpenHandle::Dir(dir)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            // everything in one reply, then EOF
+            let Some(dir) = dir.take() else {
+                return Err(StatusCode::Eof);
+            };
+            let files = std::fs::read_dir(dir)
+                .map_err(io_status)?
+                .flatten()
+                .map(|e| {
+                    let meta = e.metadata().unwrap();
+                    File::new(e.file_name().to_string_lossy(), FileAttributes::from(&meta))
+                })
+                .collect();
+            Ok(Name { id, files })
+        }
+
+        async fn open(
+            &mut self,
+            id: u32,
+            filename: String,
+            pflags: OpenFlags,
+            _attrs: FileAttributes,
+        ) -> Result<Handle, Self::Error> {
+            let file = std::fs::OpenOptions::new()
+                .read(pflags.contains(OpenFlags::READ))
+                .write(pflags.contains(OpenFlags::WRITE))
+                .create(pflags.contains(OpenFlags::CREATE))
+                .create_new(pflags.contains(OpenFlags::EXCLUDE))
+                .truncate(pflags.contains(OpenFlags::TRUNCATE))
+                .open(self.path(&filename))
+                .map_err(|_| StatusCode::NoSuchFile)?;
+            let handle = self.add_handle(OpenHandle::File(file));
+            Ok(Handle { id, handle })
+        }
+
+        async fn read(
+            &mut self,
+            id: u32,
+            handle: String,
+            offset: u64,
+            len: u32,
+        ) -> Result<Data, Self::Error> {
+            let Some(OpenHandle::File(file)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            file.seek(SeekFrom::Start(offset)).map_err(io_status)?;
+            let mut data = vec![0; len as usize];
+            let n = file.read(&mut data).map_err(io_status)?;
+            if n == 0 {
+                return Err(StatusCode::Eof);
+            }
+            data.truncate(n);
+            Ok(Data { id, data })
+        }
+
+        async fn write(
+            &mut self,
+            id: u32,
+            handle: String,
+            offset: u64,
+            data: Vec<u8>,
+        ) -> Result<Status, Self::Error> {
+            let Some(OpenHandle::File(file)) = self.handles.get_mut(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            file.seek(SeekFrom::Start(offset)).map_err(io_status)?;
+            file.write_all(&data).map_err(io_status)?;
+            Ok(ok(id))
+        }
+
+        async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+            self.handles.remove(&handle);
+            Ok(ok(id))
+        }
+
+        async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+            let meta = std::fs::metadata(self.path(&path)).map_err(|_| StatusCode::NoSuchFile)?;
+            Ok(Attrs {
+                id,
+                attrs: FileAttributes::from(&meta),
+            })
+        }
+
+        async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+            let Some(OpenHandle::File(file)) = self.handles.get(&handle) else {
+                return Err(StatusCode::Failure);
+            };
+            let meta = file.metadata().map_err(io_status)?;
+            Ok(Attrs {
+                id,
+                attrs: FileAttributes::from(&meta),
+            })
+        }
+
+        async fn setstat(
+            &mut self,
+            id: u32,
+            path: String,
+            attrs: FileAttributes,
+        ) -> Result<Status, Self::Error> {
+            // modes only exist on Unix, elsewhere the request is accepted and ignored
+            #[cfg(unix)]
+            if let Some(mode) = attrs.permissions {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(self.path(&path), std::fs::Permissions::from_mode(mode))
+                    .map_err(io_status)?;
+            }
+            #[cfg(not(unix))]
+            let _ = (path, attrs);
+            Ok(ok(id))
+        }
+
+        async fn remove(&mut self, id: u32, filename: String) -> Result<Status, Self::Error> {
+            std::fs::remove_file(self.path(&filename)).map_err(|_| StatusCode::NoSuchFile)?;
+            Ok(ok(id))
+        }
+
+        async fn mkdir(
+            &mut self,
+            id: u32,
+            path: String,
+            _attrs: FileAttributes,
+        ) -> Result<Status, Self::Error> {
+            std::fs::create_dir(self.path(&path)).map_err(io_status)?;
+            Ok(ok(id))
+        }
+
+        async fn rmdir(&mut self, id: u32, path: String) -> Result<Status, Self::Error> {
+            std::fs::remove_dir(self.path(&path)).map_err(io_status)?;
+            Ok(ok(id))
+        }
+
+        async fn rename(
+            &mut self,
+            id: u32,
+            oldpath: String,
+            newpath: String,
+        ) -> Result<Status, Self::Error> {
+            std::fs::rename(self.path(&oldpath), self.path(&newpath)).map_err(io_status)?;
+            Ok(ok(id))
+        }
+    }
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("rustty-sftp-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    async fn connect(root: &std::path::Path) -> SftpSession {
+        let (client, server) = tokio::io::duplex(256 * 1024);
+        let fs = LocalFs {
+            root: root.to_path_buf(),
+            handles: HashMap::new(),
+            next_handle: 0,
+        };
+        russh_sftp::server::run(server, fs).await;
+        SftpSession::new(client).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn browse_and_manage_files() {
+        let root = temp_root();
+        std::fs::write(root.join("notes.txt"), b"hello").unwrap();
+        let sftp = connect(&root).await;
+
+        mkdir(&sftp, "/logs").await.unwrap();
+        let (path, entries) = list_dir(&sftp, ".").await.unwrap();
+        assert_eq!(path, "/");
+        let names: Vec<_> = entries.iter().map(|e| (e.name.as_str(), e.is_dir)).collect();
+        assert_eq!(names, vec![("logs", true), ("notes.txt", false)]);
+        assert_eq!(entries[1].size, 5);
+
+        rename(&sftp, "/notes.txt", "/logs/old.txt").await.unwrap();
+        chmod(&sftp, "/logs/old.txt", 0o600).await.unwrap();
+        let (_, entries) = list_dir(&sftp, "/logs").await.unwrap();
+        assert_eq!(entries[0].name, "old.txt");
+        #[cfg(unix)]
+        assert_eq!(entries[0].mode_string(), "-rw-------");
+
+        remove(&sftp, "/logs/old.txt", false).await.unwrap();
+        remove(&sftp, "/logs", true).await.unwrap();
+        assert!(list_dir(&sftp, "/").await.unwrap().1.is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn upload_then_download_reports_progress() {
+        let root = temp_root();
+        let local = root.join("local.bin");
+        let content: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&local, &content).unwrap();
+        std::fs::create_dir(root.join("remote")).unwrap();
+        let sftp = connect(&root.join("remote")).await;
+        let cancel = AtomicBool::new(false);
+
+        let mut reports = Vec::new();
+        let sent = upload(&sftp, &local, "/copy.bin", &cancel, |done, total| {
+            reports.push((done, total))
+        })
+        .await
+        .unwrap();
+        assert_eq!(sent, content.len() as u64);
+        assert_eq!(reports.last(), Some(&(sent, sent)));
+        assert_eq!(std::fs::read(root.join("remote/copy.bin")).unwrap(), content);
+
+        let back = root.join("back.bin");
+        download(&sftp, "/copy.bin", &back, &cancel, |_, _| {}).await.unwrap();
+        assert_eq!(std::fs::read(&back).unwrap(), content);
+
+        // a second download keeps the first file and picks a new name
+        std::fs::write(&back, b"mine").unwrap();
+        download(&sftp, "/copy.bin", &back, &cancel, |_, _| {}).await.unwrap();
+        assert_eq!(std::fs::read(&back).unwrap(), b"mine");
+        assert_eq!(std::fs::read(root.join("back (2).bin")).unwrap(), content);
+
+        // same for a second upload on the server
+        upload(&sftp, &back, "/copy.bin", &cancel, |_, _| {}).await.unwrap();
+        assert_eq!(std::fs::read(root.join("remote/copy.bin")).unwrap(), content);
+        assert_eq!(std::fs::read(root.join("remote/copy (2).bin")).unwrap(), b"mine");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn numbered_names_keep_the_extension() {
+        assert_eq!(numbered("copy.bin", 2), "copy (2).bin");
+        assert_eq!(numbered("archive.tar.gz", 3), "archive.tar (3).gz");
+        assert_eq!(numbered(".bashrc", 2), ".bashrc (2)");
+        assert_eq!(numbered("README", 2), "README (2)");
+    }
+
+    #[tokio::test]
+    async fn cancelled_transfer_leaves_no_partial_file() {
+        let root = temp_root();
+        let local = root.join("big.bin");
+        std::fs::write(&local, vec![7u8; 200_000]).unwrap();
+        std::fs::create_dir(root.join("remote")).unwrap();
+        let sftp = connect(&root.join("remote")).await;
+
+        let cancel = AtomicBool::new(false);
+        let res = upload(&sftp, &local, "/big.bin", &cancel, |done, _| {
+            if done >= CHUNK_SIZE as u64 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        })
+        .await;
+        assert!(res.is_err());
+        assert!(!root.join("remote/big.bin").exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn paths_and_modes() {
+        assert_eq!(join("/", "etc"), "/etc");
+        assert_eq!(join("/etc", "hosts"), "/etc/hosts");
+        assert_eq!(parent("/etc/ssh"), "/etc");
+        assert_eq!(parent("/etc"), "/");
+        assert_eq!(parent("/"), "/");
+        assert_eq!(parse_mode("755"), Some(0o755));
+        assert_eq!(parse_mode("0644"), Some(0o644));
+        assert_eq!(parse_mode("9"), None);
+        assert_eq!(parse_mode(""), None);
+    }
+
+    #[tokio::test]
+    async fn scp_sink_protocol() {
+        let (mut client, mut server) = tokio::io::duplex(64 * 1024);
+        // "scp -t" stand-in: acknowledges each step and keeps what it received
+        let sink = tokio::spawn(async move {
+            let mut received = Vec::new();
+            server.write_all(&[0]).await.unwrap();
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = server.read(&mut buf).await.unwrap();
+                received.extend_from_slice(&buf[..n]);
+                if received.ends_with(b"\n") {
+                    break;
+                }
+            }
+            server.write_all(&[0]).await.unwrap();
+            let header = String::from_utf8(received.clone()).unwrap();
+            let mut content = vec![0u8; 11 + 1];
+            server.read_exact(&mut content).await.unwrap();
+            server.write_all(&[0]).await.unwrap();
+            (header, content)
+        });
+
+        let cancel = AtomicBool::new(false);
+        let done = scp_send(&mut client, &b"hello world"[..], "a.txt", 11, &cancel, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(done, 11);
+        let (header, content) = sink.await.unwrap();
+        assert_eq!(header, "C0644 11 a.txt\n");
+        assert_eq!(content, b"hello world\0");
+
+        // an error reported by the remote scp reaches the user
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        server.write_all(b"\x01scp: /root: Permission denied\n").await.unwrap();
+        let err = scp_send(&mut client, &b"x"[..], "x", 1, &cancel, |_, _| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err, "scp : scp: /root: Permission denied");
+    }
+
+    #[test]
+    fn cwd_from_osc7() {
+        let mut tracker = CwdTracker::default();
+        tracker.feed(b"user@host:~$ ");
+        assert_eq!(tracker.cwd(), None);
+
+        // BEL terminated, split across two packets
+        tracker.feed(b"\x1b]7;file://host/home/us");
+        assert_eq!(tracker.cwd(), None);
+        tracker.feed(b"er\x07user@host:~$ ");
+        assert_eq!(tracker.cwd(), Some("/home/user"));
+
+        // ST terminated, percent-encoded, the prefix itself split
+        tracker.feed(b"ls\r\n\x1b]");
+        tracker.feed(b"7;file://host/tmp/my%20dir\x1b\\$ ");
+        assert_eq!(tracker.cwd(), Some("/tmp/my dir"));
+
+        // other OSC sequences (window title) are ignored
+        tracker.feed(b"\x1b]0;title\x07");
+        assert_eq!(tracker.cwd(), Some("/tmp/my dir"));
+    }
+}
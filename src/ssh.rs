@@ -1,18 +1,25 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::connect_error::ConnectError;
+use crate::connect_progress::{Reporter, Step};
 use crate::messages::{Message, SshMessage};
+use crate::algorithms::{KexSniffer, Negotiated};
+use crate::models::Profile;
+use crate::session::{SessionChannel, SessionEnd};
 use async_trait::async_trait;
 use iced::{
-    Color, Task,
+    Task,
     futures::{SinkExt, channel::mpsc},
-    window,
 };
 use russh::{
-    ChannelId, Pty,
-    client::{self, Session},
+    ChannelId, ChannelMsg, Pty,
+    client::{self, DisconnectReason, Session},
     keys::key,
 };
 use tokio::sync::Mutex;
+use tracing::Instrument;
+use zeroize::Zeroizing;
 
 // Alias for the SSH channel type, simplifying references throughout the code
 pub type SshChannel = russh::Channel<russh::client::Msg>;
@@ -20,27 +27,123 @@ pub type SshChannel = russh::Channel<russh::client::Msg>;
 pub type SshChannelArc = std::sync::Arc<tokio::sync::Mutex<SshChannel>>;
 // Alias for the SSH handle, which manages the connection and channels. Wrapped in Arc and Mutex for safe concurrent access.
 pub type SshHandle = std::sync::Arc<tokio::sync::Mutex<russh::client::Handle<MyHandler>>>;
+// Shared slot telling the handler which window should receive the data of a session
+pub type SharedWindowId = Arc<Mutex<SessionSlot>>;
+// Why the handler refused the server key, read back when the connection fails
+pub type RejectedKey = Arc<std::sync::Mutex<Option<ConnectError>>>;
+
+/// Filled in once the terminal window and its shell exist
+#[derive(Default)]
+pub struct SessionSlot {
+    pub window_id: Option<iced::window::Id>,
+    /// Channel of the shell: when it closes, the session is over
+    pub shell: Option<ChannelId>,
+}
 
 pub struct MyHandler {
-    pub window_id: Arc<Mutex<Option<iced::window::Id>>>,
+    pub window_id: SharedWindowId,
     pub sender: mpsc::Sender<Message>,
+    /// Exit status of the shell, when the server sent one
+    exit_status: Option<u32>,
+    /// The end was already reported to the window
+    ended: bool,
+    /// Host and port the server key is recorded for
+    host: String,
+    port: u16,
+    rejected_key: RejectedKey,
+    progress: Reporter,
+    /// Span of the connection: the callbacks run in the task of the SSH library, outside of it
+    span: tracing::Span,
+}
+
+impl MyHandler {
+    pub fn new(
+        window_id: SharedWindowId,
+        sender: mpsc::Sender<Message>,
+        host: String,
+        port: u16,
+        progress: Reporter,
+    ) -> Self {
+        Self {
+            window_id,
+            sender,
+            exit_status: None,
+            ended: false,
+            host,
+            port,
+            rejected_key: RejectedKey::default(),
+            progress,
+            span: tracing::Span::current(),
+        }
+    }
+
+    /// Tell the window once how its session ended
+    fn end(&mut self, window_id: Option<iced::window::Id>, end: SessionEnd) {
+        if self.ended {
+            return;
+        }
+        if let Some(id) = window_id {
+            self.ended = true;
+            let _ = self.sender.try_send(Message::Ssh(SshMessage::SessionEnded(id, end)));
+        }
+    }
+
+    async fn is_shell(&self, channel: ChannelId) -> bool {
+        self.window_id.lock().await.shell == Some(channel)
+    }
+
+    /// The shell channel closed or reached its end
+    async fn shell_closed(&mut self, channel: ChannelId) {
+        if !self.is_shell(channel).await {
+            return;
+        }
+        tracing::debug!(parent: &self.span, %channel, exit_status = ?self.exit_status, "Fin du shell");
+        let window_id = self.window_id.lock().await.window_id;
+        let end = match self.exit_status {
+            Some(code) => SessionEnd::expected(format!("Session terminée : code {}", code)),
+            None => SessionEnd::lost("Canal fermé par le serveur".into()),
+        };
+        self.end(window_id, end);
+    }
+}
+
+// the connection task is over, whatever the way it ended
+impl Drop for MyHandler {
+    fn drop(&mut self) {
+        let window_id = self.window_id.try_lock().ok().and_then(|slot| slot.window_id);
+        self.end(window_id, SessionEnd::lost("Connexion perdue".into()));
+    }
 }
 
 #[async_trait]
 impl client::Handler for MyHandler {
     type Error = russh::Error;
 
-    async fn check_server_key(&mut self, _key: &key::PublicKey) -> Result<bool, Self::Error> {
-        Ok(true)
+    async fn check_server_key(&mut self, key: &key::PublicKey) -> Result<bool, Self::Error> {
+        // the server signs the exchange with this key: the exchange is over
+        self.progress.done(Step::Kex, "");
+        self.progress.running(Step::HostKey);
+        match verify_server_key(&self.rejected_key, &self.host, self.port, key) {
+            Ok(()) => {
+                self.progress.done(Step::HostKey, format!("SHA256:{}", key.fingerprint()));
+                Ok(true)
+            }
+            Err(e) => {
+                tracing::error!(parent: &self.span, "{}", e);
+                self.progress.failed(Step::HostKey, &e);
+                Ok(false)
+            }
+        }
     }
 
     async fn data(
         &mut self,
-        _id: ChannelId,
+        id: ChannelId,
         data: &[u8],
         _session: &mut Session,
     ) -> Result<(), Self::Error> {
-        let w_id = *self.window_id.lock().await;
+        tracing::trace!(parent: &self.span, channel = %id, bytes = data.len(), "Données reçues");
+        let w_id = self.window_id.lock().await.window_id;
         if let Some(id) = w_id {
             let _ = self
                 .sender
@@ -48,88 +151,387 @@ impl client::Handler for MyHandler {
         }
         Ok(())
     }
+
+    async fn exit_status(
+        &mut self,
+        channel: ChannelId,
+        exit_status: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        tracing::debug!(parent: &self.span, %channel, exit_status, "Code de sortie");
+        if self.is_shell(channel).await {
+            self.exit_status = Some(exit_status);
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(&mut self, channel: ChannelId, _session: &mut Session) -> Result<(), Self::Error> {
+        self.shell_closed(channel).await;
+        Ok(())
+    }
+
+    async fn channel_close(&mut self, channel: ChannelId, _session: &mut Session) -> Result<(), Self::Error> {
+        self.shell_closed(channel).await;
+        Ok(())
+    }
+
+    async fn disconnected(&mut self, reason: DisconnectReason<Self::Error>) -> Result<(), Self::Error> {
+        let window_id = self.window_id.lock().await.window_id;
+        let reason = match reason {
+            DisconnectReason::ReceivedDisconnect(info) if info.message.is_empty() => {
+                "Déconnecté par le serveur".to_string()
+            }
+            DisconnectReason::ReceivedDisconnect(info) => format!("Déconnecté par le serveur : {}", info.message),
+            DisconnectReason::Error(e) => format!("Connexion perdue : {}", e),
+        };
+        tracing::warn!(parent: &self.span, "{}", reason);
+        self.end(window_id, SessionEnd::lost(reason));
+        Ok(())
+    }
+}
+
+/// An authenticated connection, its terminal window not open yet
+#[derive(Clone)]
+pub struct SshConnection {
+    pub handle: SshHandle,
+    pub slot: SharedWindowId,
+    /// None when the offer of the server couldn't be read
+    pub algorithms: Option<Negotiated>,
+    /// Steps left: channel, PTY and shell
+    pub progress: Reporter,
+    /// Parent of the spans of the session opened on it
+    pub span: tracing::Span,
 }
 
 pub struct SshService;
 
 impl SshService {
 
-    pub fn connect(profile_ip: String, port: u16, user: String, pass: String) -> Task<Message> {
+    /// Connect to the profile's host; the profile goes back with the handle for the terminal window
+    pub fn connect(profile: Profile, pass: Zeroizing<String>) -> Task<Message> {
         Task::stream(iced::stream::channel(100, move |mut output| async move {
-            let config = Arc::new(client::Config::default()); 
-            let window_id_container = Arc::new(Mutex::new(None));
-            let handler = MyHandler {
-                sender: output.clone(),
-                window_id: window_id_container.clone(), 
-            };
-
-            match client::connect(config, (profile_ip.as_str(), port), handler).await {
-                Ok(mut handle) => {
-                    if handle
-                        .authenticate_password(user, pass)
-                        .await
-                        .unwrap_or(false)
-                    {
-                        let _ = output
-                            .send(Message::Ssh(SshMessage::Connected(Ok((
-                                Arc::new(Mutex::new(handle)),
-                                window_id_container, 
-                            )))))
-                            .await;
-                    } else {
-                        let _ = output
-                            .send(Message::Ssh(SshMessage::Connected(Err(
-                                "Échec d'authentification".into(),
-                            ))))
-                            .await;
-                    }
-                }
-                Err(_) => {
-                    let _ = output
-                        .send(Message::Ssh(SshMessage::Connected(Err(
-                            "Serveur introuvable".into(),
-                        ))))
-                        .await;
-                }
-            }
+            let result = establish(&profile, &pass, output.clone())
+                .await
+                .map(|connection| (connection, Box::new(profile)));
+            let _ = output.send(Message::Ssh(SshMessage::Connected(result))).await;
         }))
     }
 
+    /// Connect again for a terminal window whose session was lost
+    pub fn reconnect(window_id: iced::window::Id, profile: Profile, pass: Zeroizing<String>) -> Task<Message> {
+        Task::stream(iced::stream::channel(100, move |mut output| async move {
+            let result = establish(&profile, &pass, output.clone()).await;
+            let _ = output
+                .send(Message::Ssh(SshMessage::Reconnected(window_id, result)))
+                .await;
+        }))
+    }
 
-    pub fn open_shell(
+    /// Round trip of a request the server must answer, every `interval` until the connection is gone
+    pub fn measure_latency(
         window_id: iced::window::Id,
-        handle: SshHandle,
-        shared_window_id: Arc<Mutex<Option<iced::window::Id>>>, // <--- Ajoute ceci
+        handle: &SshHandle,
+        interval: std::time::Duration,
     ) -> Task<Message> {
+        // the window owns the connection: don't keep it open once it is closed
+        let handle = Arc::downgrade(handle);
+        Task::stream(iced::stream::channel(10, move |mut output| async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(handle) = handle.upgrade() else { break };
+                let handle = handle.lock().await;
+                if handle.is_closed() {
+                    break;
+                }
+                let start = std::time::Instant::now();
+                // there is no such forward: the server answers with a failure, like to a keepalive
+                match handle.cancel_tcpip_forward("", 0).await {
+                    Ok(()) | Err(russh::Error::RequestDenied) => {}
+                    Err(_) => break,
+                }
+                let latency = start.elapsed();
+                drop(handle);
+                let _ = output
+                    .send(Message::Ssh(SshMessage::Latency(window_id, latency)))
+                    .await;
+            }
+        }))
+    }
+
+    pub fn open_shell(window_id: iced::window::Id, connection: SshConnection) -> Task<Message> {
         let manual_modes: Vec<(Pty, u32)> = vec![(Pty::ICRNL, 1), (Pty::ONLCR, 1)];
+        let SshConnection {
+            handle,
+            slot: shared_window_id,
+            mut progress,
+            span,
+            ..
+        } = connection;
+        let session_span = tracing::info_span!(parent: &span, "session", window = ?window_id);
 
-        Task::perform(
+        Task::stream(iced::stream::channel(10, move |mut output| {
             async move {
                 // 1. update the shared window ID directly via the Arc! 
                 // This ensures that when the SSH handler receives data, it knows which window to send it to.
                 {
                     let mut w_id_lock = shared_window_id.lock().await;
-                    *w_id_lock = Some(window_id);
-                    println!("LOG: ID partagé mis à jour pour {:?}", window_id);
+                    w_id_lock.window_id = Some(window_id);
+                    tracing::debug!("ID partagé mis à jour");
                 }
 
                 // 2. Now we can open the SSH session and channel as before, knowing that the handler has the correct window ID to work with.
-                let mut ch = {
-                    let mut h_lock = handle.lock().await;
-                    h_lock.channel_open_session().await.ok()?
+                let shell = async {
+                    progress.running(Step::Channel);
+                    let mut ch = {
+                        let h_lock = handle.lock().await;
+                        h_lock
+                            .channel_open_session()
+                            .await
+                            .map_err(|e| (Step::Channel, ConnectError::ChannelOpen(e.to_string())))?
+                    };
+                    progress.done(Step::Channel, "");
+                    // its end is the end of the session
+                    shared_window_id.lock().await.shell = Some(ch.id());
+
+                    let channel_span = tracing::info_span!("channel", id = %ch.id());
+                    async {
+                        progress.running(Step::Pty);
+                        ch.request_pty(true, "xterm-256color", 80, 24, 0, 0, &manual_modes)
+                            .await
+                            .map_err(|e| (Step::Pty, ConnectError::ChannelOpen(e.to_string())))?;
+                        if !request_granted(&mut ch).await {
+                            return Err((Step::Pty, ConnectError::PtyRefused));
+                        }
+                        progress.done(Step::Pty, "xterm-256color");
+
+                        progress.running(Step::Shell);
+                        ch.request_shell(true)
+                            .await
+                            .map_err(|e| (Step::Shell, ConnectError::ChannelOpen(e.to_string())))?;
+                        if !request_granted(&mut ch).await {
+                            return Err((Step::Shell, ConnectError::ChannelOpen("shell refusé".into())));
+                        }
+                        progress.done(Step::Shell, "");
+                        Ok(())
+                    }
+                    .instrument(channel_span)
+                    .await?;
+                    Ok(Arc::new(Mutex::new(ch)))
                 };
+                let message = match shell.await {
+                    Ok(channel) => {
+                        progress.finished();
+                        SshMessage::SetChannel(window_id, SessionChannel::Ssh(channel))
+                    }
+                    Err((step, e)) => {
+                        progress.failed(step, &e);
+                        SshMessage::ShellFailed(window_id, e)
+                    }
+                };
+                let _ = output.send(Message::Ssh(message)).await;
+            }
+            .instrument(session_span)
+        }))
+    }
+}
 
-                ch.request_pty(true, "xterm-256color", 80, 24, 0, 0, &manual_modes)
-                    .await
-                    .ok()?;
-                ch.request_shell(true).await.ok()?;
-
-                Some(Arc::new(Mutex::new(ch)))
-            },
-            move |ch| {
-                ch.map(|channel| Message::Ssh(SshMessage::SetChannel(window_id, channel)))
-                    .unwrap_or(Message::DoNothing)
-            },
-        )
+/// Wait for the answer to a request sent with `want_reply`
+async fn request_granted(channel: &mut SshChannel) -> bool {
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            ChannelMsg::Success => return true,
+            ChannelMsg::Failure | ChannelMsg::Close | ChannelMsg::Eof => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Servers already met, with their key
+pub fn known_hosts_path() -> PathBuf {
+    crate::storage::config_dir().join("known_hosts")
+}
+
+/// Trust on first use: an unknown host is recorded, a known one must present the same key
+pub fn check_host_key(host: &str, port: u16, key: &key::PublicKey, known_hosts: &Path) -> Result<(), ConnectError> {
+    match russh::keys::check_known_hosts_path(host, port, key, known_hosts) {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            tracing::info!("Nouvel hôte {}:{}, clé SHA256:{} enregistrée", host, port, key.fingerprint());
+            if let Err(e) = russh::keys::learn_known_hosts_path(host, port, key, known_hosts) {
+                tracing::warn!("Impossible d'enregistrer la clé de {} : {}", host, e);
+            }
+            Ok(())
+        }
+        Err(russh::keys::Error::KeyChanged { line }) => Err(ConnectError::HostKeyMismatch {
+            host: host.to_string(),
+            fingerprint: key.fingerprint(),
+            known_hosts: known_hosts.to_path_buf(),
+            line,
+        }),
+        // an unreadable file must not lock the user out of every server
+        Err(e) => {
+            tracing::warn!("known_hosts illisible : {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Host key check of a handler: remembers why a key is refused
+pub fn verify_server_key(
+    rejected_key: &RejectedKey,
+    host: &str,
+    port: u16,
+    key: &key::PublicKey,
+) -> Result<(), ConnectError> {
+    let result = check_host_key(host, port, key, &known_hosts_path());
+    if let Ok(mut rejected) = rejected_key.lock() {
+        *rejected = result.clone().err();
+    }
+    result
+}
+
+/// Error of a failed handshake: the refused host key, or what russh reported
+pub fn handshake_error(rejected_key: &RejectedKey, host: &str, port: u16, error: russh::Error) -> ConnectError {
+    let rejected = rejected_key.lock().ok().and_then(|mut rejected| rejected.take());
+    rejected.unwrap_or_else(|| session_error(host, port, error))
+}
+
+/// Password authentication of the interactive sessions and the remote commands
+pub async fn authenticate_password<H: client::Handler>(
+    handle: &mut client::Handle<H>,
+    profile: &Profile,
+    pass: &str,
+) -> Result<(), ConnectError> {
+    let user = profile.username.clone();
+    match handle.authenticate_password(user.clone(), pass).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ConnectError::AuthRejected {
+            user,
+            tried: vec!["password".into()],
+        }),
+        Err(russh::Error::NoAuthMethod) => Err(ConnectError::NoAuthMethods { user }),
+        Err(e) => Err(session_error(&profile.ip, profile.port.parse().unwrap_or(22), e)),
+    }
+}
+
+/// Keepalives, inactivity timeout and algorithms of the profile
+pub fn client_config(profile: &Profile) -> client::Config {
+    let settings = &profile.network;
+    client::Config {
+        preferred: crate::algorithms::preferred(&profile.algorithms),
+        keepalive_interval: settings.keepalive_interval(),
+        keepalive_max: settings.keepalive_max as usize,
+        inactivity_timeout: settings.inactivity_timeout(),
+        ..client::Config::default()
+    }
+}
+
+/// Password authentication on the profile's host
+#[tracing::instrument(name = "connection", skip_all, fields(host = %profile.ip, port = %profile.port, user = %profile.username))]
+async fn establish(
+    profile: &Profile,
+    pass: &str,
+    output: mpsc::Sender<Message>,
+) -> Result<SshConnection, ConnectError> {
+    let config = Arc::new(client_config(profile));
+    let window_id_container = Arc::new(Mutex::new(SessionSlot::default()));
+    let port = profile.port.parse().unwrap_or(22);
+    let mut progress = Reporter::start(format!("{}@{}", profile.username, profile.endpoint()), output.clone());
+    let handler = MyHandler::new(window_id_container.clone(), output, profile.ip.clone(), port, progress.clone());
+    let rejected_key = handler.rejected_key.clone();
+
+    progress.running(Step::Resolve);
+    let addrs = crate::net::resolve(&profile.ip, port)
+        .await
+        .inspect_err(|e| progress.failed(Step::Resolve, e))?;
+    let resolved: Vec<String> = addrs.iter().map(|addr| addr.ip().to_string()).collect();
+    progress.done(Step::Resolve, resolved.join(", "));
+
+    progress.running(Step::Tcp);
+    let stream = crate::net::connect_to(&profile.ip, port, &addrs, &profile.network)
+        .await
+        .inspect_err(|e| progress.failed(Step::Tcp, e))?;
+    progress.done(Step::Tcp, stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default());
+
+    progress.running(Step::Kex);
+    let (stream, server_offer) = KexSniffer::new(stream);
+    let mut handle = client::connect_stream(config.clone(), stream, handler)
+        .await
+        .map_err(|e| {
+            let key_refused = rejected_key.lock().is_ok_and(|rejected| rejected.is_some());
+            let e = handshake_error(&rejected_key, &profile.ip, port, e);
+            // a refused key was already reported by the handler
+            if !key_refused {
+                progress.failed(Step::Kex, &e);
+            }
+            e
+        })?;
+
+    let server_offer = server_offer.lock().ok().and_then(|offer| offer.clone());
+    let algorithms = server_offer.and_then(|offer| crate::algorithms::negotiate(&config.preferred, &offer));
+    if let Some(algorithms) = &algorithms {
+        tracing::info!(%algorithms, "Algorithmes négociés");
+        progress.done(Step::Kex, algorithms.to_string());
+    }
+
+    let method = Step::Auth("password".into());
+    progress.running(method.clone());
+    match authenticate_password(&mut handle, profile, pass).await {
+        Ok(()) => progress.done(method, ""),
+        Err(e) => {
+            progress.failed(method, &e);
+            return Err(e);
+        }
+    }
+
+    Ok(SshConnection {
+        handle: Arc::new(Mutex::new(handle)),
+        slot: window_id_container,
+        algorithms,
+        progress,
+        span: tracing::Span::current(),
+    })
+}
+
+/// A failure of the SSH layer once the TCP connection is up
+pub fn session_error(host: &str, port: u16, error: russh::Error) -> ConnectError {
+    match error {
+        russh::Error::IO(e) => crate::net::network_error(host, port, e),
+        e => ConnectError::Negotiation(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public_key() -> key::PublicKey {
+        key::KeyPair::generate_ed25519().unwrap().clone_public_key().unwrap()
+    }
+
+    #[test]
+    fn host_key_is_learned_then_enforced() {
+        let known_hosts = std::env::temp_dir().join(format!("rustty-known-hosts-{}", uuid::Uuid::new_v4()));
+        let key = public_key();
+
+        assert_eq!(check_host_key("srv", 2222, &key, &known_hosts), Ok(()));
+        assert_eq!(check_host_key("srv", 2222, &key, &known_hosts), Ok(()));
+        // same name, another port: another host
+        assert_eq!(check_host_key("srv", 22, &public_key(), &known_hosts), Ok(()));
+
+        let impostor = public_key();
+        let err = check_host_key("srv", 2222, &impostor, &known_hosts).unwrap_err();
+        assert_eq!(
+            err,
+            ConnectError::HostKeyMismatch {
+                host: "srv".into(),
+                fingerprint: impostor.fingerprint(),
+                known_hosts: known_hosts.clone(),
+                // a new file starts with an empty line
+                line: 2,
+            }
+        );
+        let _ = std::fs::remove_file(&known_hosts);
     }
 }
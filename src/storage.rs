@@ -0,0 +1,219 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Number of previous versions kept next to a file (name.bak.1 is the most recent)
+const BACKUP_COUNT: usize = 3;
+/// A new backup is made at most this often: usage stats and favourites save the profiles on
+/// every click, the backups must still hold a version from before a bad edit
+const BACKUP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Per-user configuration folder (~/.config/rustty, %APPDATA%\rustty\config, ...).
+/// Falls back on the working directory when the platform has none.
+pub fn config_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "rustty")
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Per-user data folder (~/.local/share/rustty, %APPDATA%\rustty\data, ...), for the app's log files and session output.
+/// Falls back on the working directory when the platform has none.
+pub fn data_dir() -> PathBuf {
+    directories::ProjectDirs::from("", "", "rustty")
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Create `path`, or the first free "stem (n).ext" next to it.
+/// create_new makes the check and the creation one step, two writers can't pick the same name.
+pub async fn create_unique(path: &Path) -> Result<(tokio::fs::File, PathBuf), String> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut candidate = path.to_path_buf();
+    for n in 2.. {
+        let open = tokio::fs::OpenOptions::new().write(true).create_new(true).open(&candidate).await;
+        match open {
+            Ok(file) => return Ok((file, candidate)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                candidate = path.with_file_name(format!("{} ({}){}", stem, n, ext));
+            }
+            Err(e) => return Err(format!("{}: {}", candidate.display(), e)),
+        }
+    }
+    unreachable!()
+}
+
+/// Copy a file kept in the working directory by older versions into the config folder,
+/// only when the config folder doesn't have it yet. The old file is left untouched.
+pub fn migrate_legacy(legacy: &Path, target: &Path) -> std::io::Result<bool> {
+    if target.exists() || !legacy.is_file() {
+        return Ok(false);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(target, &fs::read(legacy)?)?;
+    Ok(true)
+}
+
+/// Replace `path` without ever leaving a half-written file: write a temp file next to it,
+/// flush it to disk, keep the previous version as a backup, then rename over the original.
+/// Unchanged content is not written again; a backup is made at most every BACKUP_INTERVAL.
+/// The file is only readable by its owner (0600) on Unix.
+pub fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    write_atomic_with(path, data, BACKUP_INTERVAL)
+}
+
+fn write_atomic_with(path: &Path, data: &[u8], backup_interval: Duration) -> std::io::Result<()> {
+    if fs::read(path).is_ok_and(|current| current == data) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = with_suffix(path, ".tmp");
+
+    let mut file = private_file(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    if path.exists() && backup_due(path, backup_interval) {
+        rotate_backups(path)?;
+    }
+    fs::rename(&tmp, path)
+}
+
+// no backup yet, or the last one is older than the interval
+fn backup_due(path: &Path, interval: Duration) -> bool {
+    let last = fs::metadata(with_suffix(path, ".bak.1")).and_then(|m| m.modified());
+    match last {
+        Ok(made) => SystemTime::now().duration_since(made).map_or(true, |age| age >= interval),
+        Err(_) => true,
+    }
+}
+
+// name.bak.2 -> name.bak.3, name.bak.1 -> name.bak.2, name -> name.bak.1
+fn rotate_backups(path: &Path) -> std::io::Result<()> {
+    for n in (1..BACKUP_COUNT).rev() {
+        let from = with_suffix(path, &format!(".bak.{}", n));
+        if from.exists() {
+            fs::rename(&from, with_suffix(path, &format!(".bak.{}", n + 1)))?;
+        }
+    }
+    // copied and not renamed: the original stays in place until the new version replaces it
+    let backup = with_suffix(path, ".bak.1");
+    fs::copy(path, &backup)?;
+    // some platforms copy the mtime too, backup_due needs the time of the backup
+    OpenOptions::new().write(true).open(&backup)?.set_modified(SystemTime::now())
+
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(unix)]
+fn private_file(path: &Path) -> std::io::Result<File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // mode() only applies when the file is created
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn private_file(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustty-storage-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn write_atomic_replaces_and_keeps_three_backups() {
+        let dir = temp_dir();
+        let path = dir.join("profiles.json");
+
+        write_atomic_with(&path, b"v1", Duration::ZERO).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"v1");
+        assert!(!with_suffix(&path, ".bak.1").exists());
+        assert!(!with_suffix(&path, ".tmp").exists());
+
+        for version in ["v2", "v3", "v4", "v4", "v5"] {
+            write_atomic_with(&path, version.as_bytes(), Duration::ZERO).unwrap();
+        }
+        assert_eq!(fs::read(&path).unwrap(), b"v5");
+        // most recent backup first, the oldest one (v1) is gone
+        assert_eq!(fs::read(with_suffix(&path, ".bak.1")).unwrap(), b"v4");
+        assert_eq!(fs::read(with_suffix(&path, ".bak.2")).unwrap(), b"v3");
+        // v4 written twice is backed up once
+        assert_eq!(fs::read(with_suffix(&path, ".bak.3")).unwrap(), b"v2");
+        assert!(!with_suffix(&path, ".bak.4").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1 + BACKUP_COUNT);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn frequent_writes_keep_the_backup_from_before_them() {
+        let dir = temp_dir();
+        let path = dir.join("profiles.json");
+
+        write_atomic(&path, b"good").unwrap();
+        // a bad edit, then favourites and usage stats saved right after it
+        for version in ["bad", "bad + favourite", "bad + stats"] {
+            write_atomic(&path, version.as_bytes()).unwrap();
+        }
+        assert_eq!(fs::read(&path).unwrap(), b"bad + stats");
+        assert_eq!(fs::read(with_suffix(&path, ".bak.1")).unwrap(), b"good");
+        assert!(!with_suffix(&path, ".bak.2").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn migrate_legacy_copies_once_and_never_overwrites() {
+        let dir = temp_dir();
+        let legacy = dir.join("profiles.json");
+        let target = dir.join("config").join("profiles.json");
+        fs::write(&legacy, b"old").unwrap();
+
+        assert!(migrate_legacy(&legacy, &target).unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+        assert!(legacy.exists());
+
+        fs::write(&target, b"new").unwrap();
+        assert!(!migrate_legacy(&legacy, &target).unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+
+        // nothing to migrate
+        assert!(!migrate_legacy(&dir.join("missing.json"), &dir.join("other.json")).unwrap());
+        assert!(!dir.join("other.json").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,492 @@
+//! Telnet (RFC 854) and raw TCP sessions, shown in the same terminal as SSH.
+//! Telnet negotiates ECHO, SGA, NAWS (window size) and TTYPE, refuses every other option.
+
+use std::sync::Arc;
+
+use crate::messages::{Message, SshMessage};
+use crate::models::{Profile, Protocol};
+use crate::session::{SessionChannel, SessionEnd};
+use iced::{
+    Task,
+    futures::{SinkExt, channel::mpsc},
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::Mutex;
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const ECHO: u8 = 1;
+const SGA: u8 = 3;
+const TTYPE: u8 = 24;
+const NAWS: u8 = 31;
+
+const TTYPE_IS: u8 = 0;
+const TTYPE_SEND: u8 = 1;
+const TERMINAL_TYPE: &[u8] = b"XTERM-256COLOR";
+
+/// State of an option on one side (RFC 1143, without the queue)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Side {
+    #[default]
+    No,
+    /// Asked for, waiting for the answer
+    WantYes,
+    Yes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    Iac,
+    /// WILL, WONT, DO or DONT, waiting for the option
+    Command(u8),
+    Sub,
+    SubIac,
+}
+
+/// Telnet protocol state of a connection: splits the received bytes into terminal output
+/// and negotiation, and builds the answers
+pub struct Telnet {
+    state: State,
+    /// Options we perform
+    us: [Side; 256],
+    /// Options the server performs
+    him: [Side; 256],
+    sub: Vec<u8>,
+    /// (rows, cols) reported through NAWS
+    size: (u16, u16),
+    /// The previous data byte was a CR (a NUL after it is padding)
+    after_cr: bool,
+}
+
+impl Telnet {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            state: State::Data,
+            us: [Side::No; 256],
+            him: [Side::No; 256],
+            sub: Vec::new(),
+            size: (rows, cols),
+            after_cr: false,
+        }
+    }
+
+    /// Options offered as soon as the connection is open
+    pub fn start(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for option in [NAWS, TTYPE] {
+            self.us[option as usize] = Side::WantYes;
+            out.extend([IAC, WILL, option]);
+        }
+        for option in [SGA, ECHO] {
+            self.him[option as usize] = Side::WantYes;
+            out.extend([IAC, DO, option]);
+        }
+        out
+    }
+
+    /// Bytes from the server: (terminal output, answers to send back)
+    pub fn receive(&mut self, input: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut output = Vec::with_capacity(input.len());
+        let mut replies = Vec::new();
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (State::Data, IAC) => State::Iac,
+                (State::Data, _) => {
+                    if !(self.after_cr && byte == 0) {
+                        output.push(byte);
+                    }
+                    self.after_cr = byte == b'\r';
+                    State::Data
+                }
+                (State::Iac, IAC) => {
+                    output.push(IAC);
+                    self.after_cr = false;
+                    State::Data
+                }
+                (State::Iac, WILL | WONT | DO | DONT) => State::Command(byte),
+                (State::Iac, SB) => {
+                    self.sub.clear();
+                    State::Sub
+                }
+                // NOP, GA, AYT...: nothing to show
+                (State::Iac, _) => State::Data,
+                (State::Command(command), option) => {
+                    self.negotiate(command, option, &mut replies);
+                    State::Data
+                }
+                (State::Sub, IAC) => State::SubIac,
+                (State::Sub, _) => {
+                    self.sub.push(byte);
+                    State::Sub
+                }
+                (State::SubIac, SE) => {
+                    self.subnegotiation(&mut replies);
+                    State::Data
+                }
+                (State::SubIac, _) => {
+                    // IAC IAC inside a subnegotiation is a 255 byte
+                    self.sub.push(byte);
+                    State::Sub
+                }
+            };
+        }
+        (output, replies)
+    }
+
+    /// Typed bytes as they go on the wire: IAC doubled, a lone CR followed by NUL
+    pub fn encode(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() + 1);
+        for (i, &byte) in data.iter().enumerate() {
+            out.push(byte);
+            match byte {
+                IAC => out.push(IAC),
+                b'\r' if data.get(i + 1) != Some(&b'\n') => out.push(0),
+                _ => {}
+            }
+        }
+        out
+    }
+
+    /// New window size, sent once the server accepted NAWS
+    pub fn resize(&mut self, rows: u16, cols: u16) -> Vec<u8> {
+        self.size = (rows, cols);
+        let mut out = Vec::new();
+        if self.us[NAWS as usize] == Side::Yes {
+            self.send_size(&mut out);
+        }
+        out
+    }
+
+    /// The server echoes what is typed
+    pub fn remote_echo(&self) -> bool {
+        self.him[ECHO as usize] == Side::Yes
+    }
+
+    fn negotiate(&mut self, command: u8, option: u8, replies: &mut Vec<u8>) {
+        let (sides, supported, accept, refuse) = match command {
+            DO | DONT => (&mut self.us, matches!(option, SGA | NAWS | TTYPE), WILL, WONT),
+            _ => (&mut self.him, matches!(option, ECHO | SGA), DO, DONT),
+        };
+        let side = &mut sides[option as usize];
+        let enabled = match (command, *side) {
+            // asked to enable
+            (DO | WILL, Side::No) if supported => {
+                *side = Side::Yes;
+                replies.extend([IAC, accept, option]);
+                true
+            }
+            (DO | WILL, Side::No) => {
+                replies.extend([IAC, refuse, option]);
+                false
+            }
+            // the answer to our own request
+            (DO | WILL, Side::WantYes) => {
+                *side = Side::Yes;
+                true
+            }
+            (DO | WILL, Side::Yes) => false,
+            // asked to disable, or our request refused
+            (_, Side::Yes) => {
+                *side = Side::No;
+                replies.extend([IAC, refuse, option]);
+                false
+            }
+            (_, _) => {
+                *side = Side::No;
+                false
+            }
+        };
+        if enabled && command == DO && option == NAWS {
+            self.send_size(replies);
+        }
+    }
+
+    fn subnegotiation(&mut self, replies: &mut Vec<u8>) {
+        if self.sub == [TTYPE, TTYPE_SEND] && self.us[TTYPE as usize] == Side::Yes {
+            replies.extend([IAC, SB, TTYPE, TTYPE_IS]);
+            replies.extend(TERMINAL_TYPE);
+            replies.extend([IAC, SE]);
+        }
+    }
+
+    fn send_size(&self, out: &mut Vec<u8>) {
+        let (rows, cols) = self.size;
+        out.extend([IAC, SB, NAWS]);
+        for byte in [cols.to_be_bytes(), rows.to_be_bytes()].concat() {
+            out.push(byte);
+            if byte == IAC {
+                out.push(IAC);
+            }
+        }
+        out.extend([IAC, SE]);
+    }
+}
+
+/// Write side of a Telnet or raw TCP session
+#[derive(Clone)]
+pub struct TcpChannel {
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    /// None in raw mode
+    telnet: Option<Arc<std::sync::Mutex<Telnet>>>,
+    /// Typed characters are shown by us when the server doesn't echo them
+    echo: mpsc::Sender<Message>,
+    window_id: iced::window::Id,
+}
+
+impl TcpChannel {
+    pub async fn send(&self, data: &[u8]) -> std::io::Result<()> {
+        let (bytes, local_echo) = match &self.telnet {
+            Some(telnet) => {
+                let remote_echo = telnet.lock().map(|t| t.remote_echo()).unwrap_or(false);
+                (Telnet::encode(data), !remote_echo)
+            }
+            // line-based services expect CR LF for Enter
+            None => (crlf(data), true),
+        };
+        if local_echo {
+            let _ = self
+                .echo
+                .clone()
+                .try_send(Message::Ssh(SshMessage::DataReceived(self.window_id, crlf(data))));
+        }
+        self.writer.lock().await.write_all(&bytes).await
+    }
+
+    pub async fn resize(&self, rows: u16, cols: u16) -> std::io::Result<()> {
+        let Some(telnet) = &self.telnet else {
+            return Ok(());
+        };
+        let bytes = match telnet.lock() {
+            Ok(mut telnet) => telnet.resize(rows, cols),
+            Err(_) => return Ok(()),
+        };
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.writer.lock().await.write_all(&bytes).await
+    }
+
+    pub async fn close(&self) {
+        let _ = self.writer.lock().await.shutdown().await;
+    }
+}
+
+/// A lone CR becomes CR LF
+fn crlf(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 1);
+    for (i, &byte) in data.iter().enumerate() {
+        out.push(byte);
+        if byte == b'\r' && data.get(i + 1) != Some(&b'\n') {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+pub struct TcpService;
+
+impl TcpService {
+    /// Connect a Telnet or raw profile for a terminal window: its channel, its output,
+    /// then why it ended
+    pub fn open(window_id: iced::window::Id, profile: &Profile, rows: u16, cols: u16) -> Task<Message> {
+        let profile = profile.clone();
+        Task::stream(iced::stream::channel(100, move |output| async move {
+            run(window_id, profile, (rows, cols), output).await;
+        }))
+    }
+}
+
+async fn run(
+    window_id: iced::window::Id,
+    profile: Profile,
+    (rows, cols): (u16, u16),
+    mut output: mpsc::Sender<Message>,
+) {
+    let end = match session(window_id, &profile, (rows, cols), &mut output).await {
+        Ok(()) => SessionEnd::expected("Connexion fermée par l'hôte distant".into()),
+        Err(e) => SessionEnd::lost(e),
+    };
+    let _ = output
+        .send(Message::Ssh(SshMessage::SessionEnded(window_id, end)))
+        .await;
+}
+
+async fn session(
+    window_id: iced::window::Id,
+    profile: &Profile,
+    (rows, cols): (u16, u16),
+    output: &mut mpsc::Sender<Message>,
+) -> Result<(), String> {
+    let (host, port) = (profile.ip.as_str(), profile.port.parse().unwrap_or(23));
+    let telnet = profile.protocol == Protocol::Telnet;
+    let connecting = format!("Connexion à {}:{}...\r\n", host, port);
+    let _ = output
+        .send(Message::Ssh(SshMessage::DataReceived(window_id, connecting.into_bytes())))
+        .await;
+    let stream = crate::net::connect(host, port, &profile.network)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (mut reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
+
+    let telnet = telnet.then(|| Arc::new(std::sync::Mutex::new(Telnet::new(rows, cols))));
+    if let Some(telnet) = &telnet {
+        let offers = telnet.lock().map(|mut t| t.start()).unwrap_or_default();
+        writer.lock().await.write_all(&offers).await.map_err(|e| e.to_string())?;
+    }
+    let channel = TcpChannel {
+        writer: writer.clone(),
+        telnet: telnet.clone(),
+        echo: output.clone(),
+        window_id,
+    };
+    let _ = output
+        .send(Message::Ssh(SshMessage::SetChannel(window_id, SessionChannel::Tcp(channel))))
+        .await;
+
+    let mut buf = vec![0; 8192];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("Connexion perdue : {}", e))?;
+        if n == 0 {
+            return Ok(());
+        }
+        let data = match &telnet {
+            Some(telnet) => {
+                let (data, replies) = match telnet.lock() {
+                    Ok(mut telnet) => telnet.receive(&buf[..n]),
+                    Err(_) => return Err("État Telnet corrompu".into()),
+                };
+                if !replies.is_empty() {
+                    let _ = writer.lock().await.write_all(&replies).await;
+                }
+                data
+            }
+            None => buf[..n].to_vec(),
+        };
+        if !data.is_empty() {
+            let _ = output
+                .send(Message::Ssh(SshMessage::DataReceived(window_id, data)))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::StreamExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn negotiation_accepts_known_options_only() {
+        let mut telnet = Telnet::new(24, 80);
+        let offers = telnet.start();
+        assert_eq!(offers, [IAC, WILL, NAWS, IAC, WILL, TTYPE, IAC, DO, SGA, IAC, DO, ECHO]);
+
+        // answers to our offers need no reply, except the size once NAWS is accepted
+        let (data, replies) = telnet.receive(&[IAC, DO, NAWS, IAC, WILL, ECHO, IAC, DO, TTYPE]);
+        assert!(data.is_empty());
+        assert_eq!(replies, [IAC, SB, NAWS, 0, 80, 0, 24, IAC, SE]);
+        assert!(telnet.remote_echo());
+
+        // unknown options are refused, repeats don't loop
+        let (_, replies) = telnet.receive(&[IAC, DO, 42, IAC, WILL, 42, IAC, DO, NAWS]);
+        assert_eq!(replies, [IAC, WONT, 42, IAC, DONT, 42]);
+
+        // the server stops echoing
+        let (_, replies) = telnet.receive(&[IAC, WONT, ECHO]);
+        assert_eq!(replies, [IAC, DONT, ECHO]);
+        assert!(!telnet.remote_echo());
+
+        // sizes with a 255 byte are escaped
+        assert_eq!(telnet.resize(24, 255), [IAC, SB, NAWS, 0, IAC, IAC, 0, 24, IAC, SE]);
+    }
+
+    #[test]
+    fn data_and_subnegotiation_split_across_reads() {
+        let mut telnet = Telnet::new(24, 80);
+        telnet.receive(&[IAC, DO, TTYPE]);
+        let (data, replies) = telnet.receive(&[b'a', IAC, IAC, b'\r', 0, b'b', IAC, SB, TTYPE]);
+        assert_eq!(data, [b'a', IAC, b'\r', b'b']);
+        assert!(replies.is_empty());
+
+        let (data, replies) = telnet.receive(&[TTYPE_SEND, IAC, SE, b'c']);
+        assert_eq!(data, b"c");
+        assert_eq!(replies, [&[IAC, SB, TTYPE, TTYPE_IS][..], TERMINAL_TYPE, &[IAC, SE]].concat());
+
+        assert_eq!(Telnet::encode(&[b'l', b's', b'\r', IAC]), [b'l', b's', b'\r', 0, IAC, IAC]);
+        assert_eq!(Telnet::encode(b"x\r\n"), b"x\r\n");
+    }
+
+    async fn read_until(stream: &mut TcpStream, received: &mut Vec<u8>, wanted: &[u8]) {
+        let mut buf = [0; 256];
+        while !received.windows(wanted.len()).any(|w| w == wanted) {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "closed before {:?}, got {:?}", wanted, received);
+            received.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    #[tokio::test]
+    async fn session_with_a_telnet_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(&[IAC, DO, NAWS, IAC, DO, TTYPE, IAC, WILL, ECHO, IAC, SB, TTYPE, TTYPE_SEND, IAC, SE])
+                .await
+                .unwrap();
+            stream.write_all(b"login: ").await.unwrap();
+            let mut received = Vec::new();
+            read_until(&mut stream, &mut received, &[IAC, SB, NAWS, 0, 80, 0, 24, IAC, SE]).await;
+            read_until(&mut stream, &mut received, b"XTERM-256COLOR").await;
+            read_until(&mut stream, &mut received, b"admin\r\0").await;
+        });
+
+        let window_id = iced::window::Id::unique();
+        let (sender, mut messages) = mpsc::channel(100);
+        let profile = Profile {
+            ip: "127.0.0.1".into(),
+            port: port.to_string(),
+            protocol: Protocol::Telnet,
+            ..Profile::default()
+        };
+        let client = tokio::spawn(run(window_id, profile, (24, 80), sender));
+
+        let (mut screen, mut channel) = (Vec::new(), None);
+        while let Some(message) = messages.next().await {
+            match message {
+                Message::Ssh(SshMessage::SetChannel(_, ch)) => channel = Some(ch),
+                Message::Ssh(SshMessage::DataReceived(_, data)) => {
+                    screen.extend(data);
+                    // typed once the server said it echoes
+                    if screen.ends_with(b"login: ") {
+                        channel.as_ref().expect("channel before the output").send(b"admin\r").await;
+                    }
+                }
+                Message::Ssh(SshMessage::SessionEnded(_, end)) => {
+                    assert_eq!(end.reason, "Connexion fermée par l'hôte distant");
+                    assert!(end.expected);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        server.await.unwrap();
+        client.await.unwrap();
+        let screen = String::from_utf8(screen).unwrap();
+        assert!(screen.ends_with("login: "), "{:?}", screen);
+    }
+}
@@ -0,0 +1,72 @@
+//! Non-blocking terminal device (pseudo-terminal master or serial port) read and written
+//! from tokio, shared by the local and serial sessions.
+
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::sync::Arc;
+use tokio::io::unix::AsyncFd;
+
+#[derive(Clone)]
+pub struct AsyncTty {
+    fd: Arc<AsyncFd<OwnedFd>>,
+}
+
+impl AsyncTty {
+    /// Take a descriptor opened (or switched) to non-blocking mode
+    pub fn new(fd: OwnedFd) -> io::Result<Self> {
+        // SAFETY: the descriptor is owned by the AsyncFd and only closed when it is dropped
+        let fd = unsafe { AsyncFd::register(fd)? };
+        Ok(Self { fd: Arc::new(fd) })
+    }
+
+    pub fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.fd.as_raw_fd()
+    }
+
+    /// 0 once the other side is closed or the device is gone (Linux reports both as EIO)
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.fd.readable().await?;
+            let res = guard.try_io(|fd| {
+                // SAFETY: buf is valid for buf.len() bytes
+                let n = unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+                if n < 0 { Err(io::Error::last_os_error()) } else { Ok(n as usize) }
+            });
+            match res {
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::EIO) => return Ok(0),
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    pub async fn write(&self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let mut guard = self.fd.writable().await?;
+            let res = guard.try_io(|fd| {
+                // SAFETY: data is valid for data.len() bytes
+                let n = unsafe { libc::write(fd.as_raw_fd(), data.as_ptr().cast(), data.len()) };
+                if n < 0 { Err(io::Error::last_os_error()) } else { Ok(n as usize) }
+            });
+            match res {
+                Ok(Ok(n)) => data = &data[n..],
+                Ok(Err(e)) => return Err(e),
+                Err(_would_block) => continue,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Add `flag` to the descriptor flags (F_SETFD) or the file status flags (F_SETFL)
+pub fn set_flags(fd: &OwnedFd, command: libc::c_int, flag: libc::c_int) -> io::Result<()> {
+    let get = if command == libc::F_SETFD { libc::F_GETFD } else { libc::F_GETFL };
+    // SAFETY: plain fcntl on a descriptor we own
+    unsafe {
+        let flags = libc::fcntl(fd.as_raw_fd(), get);
+        if flags < 0 || libc::fcntl(fd.as_raw_fd(), command, flags | flag) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
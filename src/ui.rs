@@ -4,23 +4,52 @@ use iced::keyboard::{Key, Modifiers};
 use iced::widget::{scrollable, text_input};
 use iced::{Element, Task, window};
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use zeroize::{Zeroize, Zeroizing};
 
 // Internal module imports
-use crate::messages::{ConfigMessage, LoginMessage, Message, ProfileMessage, SshMessage};
-use crate::models::{EditSection, Profile};
-use crate::ssh::{SshChannel, SshService};
+use crate::messages::{
+    ConfigMessage, EventLogMessage, ExecMessage, FolderMessage, ImportMessage, LoginMessage, VaultMessage, Message, PlaybackMessage, ProfileMessage, QuickConnectMessage, SftpMessage,
+    SshMessage,
+};
+use crate::exec::{ExecResult, ExecTarget};
+use crate::destination;
+use crate::folders;
+use crate::import::{ImportCandidate, ImportSource, ImportSummary};
+use crate::models::{EditSection, Profile, ProfileStore, ProfileView, Protocol, ReconnectSettings, SortColumn};
+use crate::vault::{Vault, VaultEntry};
+use crate::recording::{Player, Recording, SessionRecorder};
+use crate::session_log::SessionLogger;
+use crate::sftp::{SftpService, TransferKind};
+use crate::local::LocalService;
+use crate::serial::SerialService;
+use crate::telnet::TcpService;
+use crate::session::{Disconnected, SessionChannel, SessionEnd};
+use crate::ssh::{SshConnection, SshHandle, SshService};
+use crate::algorithms::Negotiated;
+use crate::connect_error::ConnectError;
+use crate::connect_progress::{self, ConnectProgress};
+use crate::event_log;
+use crate::ui::sftp::{DropUploads, SftpPanel, Transfer};
+use crate::ui::components::search_table::Listing;
 use crate::ui::constants::*;
 
 pub mod constants;
 pub mod dashboard;
+pub mod events;
+pub mod playback;
+pub mod sftp;
 pub mod terminal;
 pub mod theme;
 pub mod components {
     pub mod actions_bar;
     pub mod brand;
+    pub mod exec_panel;
+    pub mod folder_panel;
+    pub mod import_panel;
+    pub mod quick_connect;
+    pub mod vault_panel;
     pub mod forms;
+    pub mod recordings_list;
     pub mod search_table;
     pub mod sidebar;
 }
@@ -31,8 +60,8 @@ const MAX_TERMINAL_LINES: usize = 1000;
 /// Main Application State
 pub struct MyApp {
     // --- Authentication & Connection ---
-    /// Temporary password storage for SSH authentication.
-    pub password: String,
+    /// Temporary password storage for SSH authentication (wiped from memory when replaced or dropped).
+    pub password: Zeroizing<String>,
     pub ssh_handle: Option<crate::ssh::SshHandle>,
 
     // --- Window Management ---
@@ -40,57 +69,277 @@ pub struct MyApp {
     pub login_window_id: Option<window::Id>,
     pub terminal_window_ids: Vec<window::Id>,
     pub focused_window_id: Option<window::Id>,
+    /// Command-line sessions still waiting for their connection
+    pub launch_pending: usize,
     /// Tracks the number of opened terminals to calculate grid positioning
     pub spawn_index: usize,
 
     // --- Terminal Data ---
     /// Maps each window to its own VT100 state parser
     pub parsers: HashMap<window::Id, vt100::Parser>,
-    /// Maps each window to its active SSH communication channel
-    pub active_channels: HashMap<window::Id, Arc<Mutex<SshChannel>>>,
+    /// Maps each window to the channel of its session (SSH or local shell)
+    pub active_channels: HashMap<window::Id, SessionChannel>,
+    /// Maps each window whose session was lost to what happened to it since
+    pub disconnected: HashMap<window::Id, Disconnected>,
+    /// Maps each SSH window to the algorithms of its connection
+    pub session_algorithms: HashMap<window::Id, Negotiated>,
+    /// Maps each SSH window to the last round trip measured on its connection
+    pub latencies: HashMap<window::Id, std::time::Duration>,
+    /// Maps each window to the SSH connection it runs on (to open more channels, e.g. SFTP)
+    pub session_handles: HashMap<window::Id, SshHandle>,
+    /// Maps each terminal window to the profile it was opened for
+    pub session_profiles: HashMap<window::Id, Profile>,
+    /// Maps each window to its session log, only for profiles with logging enabled
+    pub session_logs: HashMap<window::Id, SessionLogger>,
+    /// Maps each window being recorded (asciicast) to its recorder
+    pub recorders: HashMap<window::Id, SessionRecorder>,
+
+    // --- Playback ---
+    /// Maps each playback window to the recording it replays
+    pub players: HashMap<window::Id, Player>,
+    /// Recordings found in the recordings folder
+    pub recordings: Vec<std::path::PathBuf>,
+    /// Time of the previous playback tick
+    pub last_tick: Option<std::time::Instant>,
+
+    // --- Event log ---
+    pub event_log_window: Option<window::Id>,
+    /// Lowest level shown in the event log window
+    pub event_log_level: event_log::Level,
+
+    // --- SFTP ---
+    /// Maps each SFTP window to its browser state
+    pub sftp_panels: HashMap<window::Id, SftpPanel>,
+    /// Id given to the next file transfer
+    pub next_transfer_id: u64,
+    /// Maps each terminal window to the uploads of the files dropped on it
+    pub drop_uploads: HashMap<window::Id, DropUploads>,
+
+    // --- Remote commands ---
+    pub exec_command: String,
+    pub exec_target: Option<ExecTarget>,
+    /// One row per host of the last run, in the order of the profiles
+    pub exec_results: Vec<ExecResult>,
+    /// Incremented on each run, results of an older run are dropped
+    pub exec_run: u64,
+    /// Set to stop the hosts of the current run
+    pub exec_cancel: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// Outcome of the last export (file written or error)
+    pub exec_export: Option<Result<std::path::PathBuf, String>>,
+
+    // --- Vault ---
+    /// Some while unlocked
+    pub vault: Option<Vault>,
+    pub vault_master: Zeroizing<String>,
+    /// Master password typed again when creating the vault
+    pub vault_confirm: Zeroizing<String>,
+    /// Key passphrase of the selected profile
+    pub vault_passphrase: Zeroizing<String>,
+    /// Last use of the unlocked vault, for the auto-lock
+    pub vault_last_used: std::time::Instant,
+    /// The KDF is running (create/unlock)
+    pub vault_busy: bool,
+    pub vault_error: Option<String>,
+
+    // --- Quick connect ---
+    /// user@host:port or ssh:// URI typed in the dashboard bar
+    pub quick_input: String,
+    pub quick_password: Zeroizing<String>,
+    pub quick_error: Option<String>,
+    /// Target of the last quick connection, offered as a new profile once connected
+    pub quick_profile: Option<Profile>,
+    pub quick_connected: bool,
+
+    // --- Serial ---
+    /// Serial devices found when the Serial type was last shown in the form
+    pub serial_ports: Vec<String>,
+
+    // --- Import ---
+    pub import_source: ImportSource,
+    /// File exported by another tool
+    pub import_path: String,
+    /// Preview of the last analysed file
+    pub import_candidates: Vec<ImportCandidate>,
+    pub import_busy: bool,
+    /// Outcome of the last analysis or import
+    pub import_status: Option<Result<ImportSummary, String>>,
+    /// Profiles written to the ssh_config snippet or to the bundle
+    pub export_selection: std::collections::HashSet<uuid::Uuid>,
+    /// Bundle file, TOML when it ends in .toml, JSON otherwise
+    pub bundle_path: String,
+    /// Outcome of the last export (file written or error)
+    pub export_status: Option<Result<std::path::PathBuf, String>>,
 
     // --- UI State ---
     pub profiles: Vec<Profile>,
+    /// Settings and unknown fields of profiles.json, written back with the profiles
+    pub store: ProfileStore,
+    /// Problem with profiles.json (unreadable, newer version, write failure), shown on the dashboard
+    pub store_error: Option<String>,
+    /// Last failed connection, with its suggestion, shown on the dashboard until dismissed
+    pub connect_error: Option<ConnectError>,
+    /// SSH connections being established, and the failed ones until dismissed
+    pub connections: Vec<(uuid::Uuid, ConnectProgress)>,
     pub current_profile: Profile,
+    /// Theme the windows are drawn with: the folder's one when the profile inherits it.
+    /// Kept apart so that saving the profile keeps its own theme
+    pub display_theme: theme::ThemeChoice,
     pub selected_profile_id: Option<uuid::Uuid>,
     pub search_query: String,
+    /// All profiles, favourites or the last connected ones
+    pub profile_view: ProfileView,
+    /// Tags a listed profile must all have
+    pub tag_filter: std::collections::BTreeSet<String>,
+    /// Tags of the edited profile as typed, split on save
+    pub tags_input: String,
+    /// Column the table is sorted by, None for the folder tree
+    pub sort_column: Option<SortColumn>,
+    pub sort_descending: bool,
+    /// Folders of the tree shown collapsed
+    pub collapsed_folders: std::collections::HashSet<String>,
+    /// Folder whose actions are shown instead of the profile form
+    pub selected_folder: Option<String>,
+    pub folder_rename: String,
+    pub folder_default_user: String,
+    /// Delete pressed once, waiting for confirmation
+    pub folder_delete_armed: bool,
+    /// Profile being dragged to another folder, and the folder under the cursor
+    pub dragging: Option<uuid::Uuid>,
+    pub drop_target: Option<String>,
     pub active_section: EditSection,
     /// Focus for TextInput in the login form (IP, Port, User, Pass)
     pub focused_id: &'static str,
 }
 
 impl MyApp {
-    pub fn new(login_id: window::Id) -> Self {
-        let loaded_profiles = Profile::load_all();
-        println!(
-            "DEBUG: {} profils chargés au démarrage",
-            loaded_profiles.len()
-        );
+    /// `login_id` is the dashboard window, None when sessions are launched from the command line
+    pub fn new(login_id: Option<window::Id>) -> Self {
+        let (store, loaded_profiles, store_error) = ProfileStore::load();
+        crate::logging::apply_settings(store.settings.log_level);
+        tracing::info!(count = loaded_profiles.len(), "Profils chargés au démarrage");
         Self {
-            password: "".into(),
-            login_window_id: Some(login_id),
+            password: Zeroizing::new(String::new()),
+            login_window_id: login_id,
             terminal_window_ids: Vec::new(),
             focused_window_id: None,
+            launch_pending: 0,
             spawn_index: 0,
             parsers: HashMap::new(),
             active_channels: HashMap::new(),
-            profiles: Profile::load_all(),
+            disconnected: HashMap::new(),
+            latencies: HashMap::new(),
+            session_algorithms: HashMap::new(),
+            session_handles: HashMap::new(),
+            session_profiles: HashMap::new(),
+            session_logs: HashMap::new(),
+            recorders: HashMap::new(),
+            players: HashMap::new(),
+            recordings: Vec::new(),
+            last_tick: None,
+            event_log_window: None,
+            event_log_level: event_log::Level::Info,
+            sftp_panels: HashMap::new(),
+            next_transfer_id: 0,
+            drop_uploads: HashMap::new(),
+            exec_command: String::new(),
+            exec_target: None,
+            exec_results: Vec::new(),
+            exec_run: 0,
+            exec_cancel: Default::default(),
+            exec_export: None,
+            vault: None,
+            vault_master: Zeroizing::new(String::new()),
+            vault_confirm: Zeroizing::new(String::new()),
+            vault_passphrase: Zeroizing::new(String::new()),
+            vault_last_used: std::time::Instant::now(),
+            vault_busy: false,
+            vault_error: None,
+            quick_input: String::new(),
+            quick_password: Zeroizing::new(String::new()),
+            quick_error: None,
+            quick_profile: None,
+            quick_connected: false,
+            serial_ports: Vec::new(),
+            import_source: ImportSource::default(),
+            import_path: String::new(),
+            import_candidates: Vec::new(),
+            import_busy: false,
+            import_status: None,
+            export_selection: std::collections::HashSet::new(),
+            bundle_path: "rustty-profiles.json".into(),
+            export_status: None,
+            profiles: loaded_profiles,
+            store,
+            store_error,
+            connect_error: None,
+            connections: Vec::new(),
             current_profile: Profile::default(),
+            display_theme: Profile::default().theme,
             selected_profile_id: None,
             search_query: "".into(),
+            profile_view: ProfileView::default(),
+            tag_filter: std::collections::BTreeSet::new(),
+            tags_input: String::new(),
+            sort_column: None,
+            sort_descending: false,
+            collapsed_folders: std::collections::HashSet::new(),
+            selected_folder: None,
+            folder_rename: String::new(),
+            folder_default_user: String::new(),
+            folder_delete_armed: false,
+            dragging: None,
+            drop_target: None,
             active_section: EditSection::General,
             focused_id: ID_PROFILE,
             ssh_handle: None,
         }
     }
 
+    /// Connect the sessions given on the command line, their terminals open without the dashboard
+    pub fn launch(&mut self, sessions: Vec<Profile>, password: Zeroizing<String>) -> Task<Message> {
+        // the terminals take their colours from it
+        if let Some(first) = sessions.first() {
+            self.current_profile = first.clone();
+            self.refresh_display_theme();
+        }
+        // kept to reconnect
+        self.password = password.clone();
+        let mut tasks = Vec::new();
+        for session in sessions {
+            tracing::info!("Lancement de {}", session.endpoint());
+            for _ in 0..session.terminal_count.max(1) {
+                tasks.push(Self::start_session(session.clone(), password.clone()));
+            }
+        }
+        self.launch_pending = tasks.len();
+        Task::batch(tasks)
+    }
+
+    /// One session of the profile: SSH connects before its window opens, the others after
+    fn start_session(profile: Profile, password: Zeroizing<String>) -> Task<Message> {
+        match profile.protocol {
+            Protocol::Ssh => SshService::connect(profile, password),
+            Protocol::Local | Protocol::Telnet | Protocol::Raw | Protocol::Serial => {
+                Task::done(Message::Ssh(SshMessage::OpenSession(Box::new(profile))))
+            }
+        }
+    }
+
     // router message
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::Login(msg) => self.handle_login_msg(msg),
             Message::Profile(msg) => self.handle_profile_msg(msg),
+            Message::Folder(msg) => self.handle_folder_msg(msg),
             Message::Config(msg) => self.handle_config_msg(msg),
             Message::Ssh(msg) => self.handle_ssh_msg(msg),
+            Message::Playback(msg) => self.handle_playback_msg(msg),
+            Message::Sftp(msg) => self.handle_sftp_msg(msg),
+            Message::Exec(msg) => self.handle_exec_msg(msg),
+            Message::Vault(msg) => self.handle_vault_msg(msg),
+            Message::Import(msg) => self.handle_import_msg(msg),
+            Message::QuickConnect(msg) => self.handle_quick_connect_msg(msg),
+            Message::EventLog(msg) => self.handle_event_log_msg(msg),
             Message::Event(event) => self.handle_keyboard_event(event),
 
             Message::QuitRequested => std::process::exit(0),
@@ -102,6 +351,7 @@ impl MyApp {
                 Task::none()
             }
             Message::WindowClosed(id) => self.handle_window_closed(id),
+            Message::WindowResized(id, size) => self.handle_window_resized(id, size),
 
             _ => Task::none(),
         }
@@ -111,6 +361,12 @@ impl MyApp {
     pub fn view(&self, window_id: window::Id) -> Element<'_, Message> {
         if self.terminal_window_ids.contains(&window_id) {
             terminal::render(self, window_id)
+        } else if self.players.contains_key(&window_id) {
+            playback::render(self, window_id)
+        } else if self.sftp_panels.contains_key(&window_id) {
+            sftp::render(self, window_id)
+        } else if self.event_log_window == Some(window_id) {
+            events::render(self)
         } else {
             dashboard::render(self)
         }
@@ -118,8 +374,14 @@ impl MyApp {
 
     /// proxy method
     /// if save logic changes, only update this method without touching the rest of the codebase
-    pub fn save_profiles(&self) {
-        Profile::save_all(&self.profiles);
+    pub fn save_profiles(&mut self) {
+        if let Err(e) = self.store.save(&self.profiles) {
+            tracing::error!("{}", e);
+            // a read-only file already explains why on load
+            if !self.store.read_only || self.store_error.is_none() {
+                self.store_error = Some(e);
+            }
+        }
     }
 
     /// Logic to close a terminal window and clean up associated SSH resources
@@ -128,33 +390,182 @@ impl MyApp {
         // 1. Remove it from the list of active terminal windows
         // 2. Close the associated SSH channel if it exists
         // 3. Clean up the VT100 parser to free memory
+        // 4. Flush and close the session log file and the recording
         if self.terminal_window_ids.contains(&id) {
             self.terminal_window_ids.retain(|&w_id| w_id != id);
             let channel_to_close = self.active_channels.remove(&id);
+            self.disconnected.remove(&id);
+            self.latencies.remove(&id);
+            self.session_algorithms.remove(&id);
+            self.session_handles.remove(&id);
+            self.session_profiles.remove(&id);
             self.parsers.remove(&id);
+            let drops_task = match self.drop_uploads.remove(&id) {
+                Some(drops) => {
+                    for transfer in &drops.transfers {
+                        transfer.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    match drops.uploader {
+                        Some(crate::sftp::Uploader::Sftp(sftp)) => {
+                            Task::perform(async move { sftp.close().await }, |_| Message::DoNothing)
+                        }
+                        _ => Task::none(),
+                    }
+                }
+                None => Task::none(),
+            };
+            let log_task = match self.session_logs.remove(&id) {
+                Some(logger) => Task::perform(logger.close(), |_| Message::DoNothing),
+                None => Task::none(),
+            };
+            let record_task = match self.recorders.remove(&id) {
+                Some(recorder) => Task::perform(recorder.close(), |_| Message::DoNothing),
+                None => Task::none(),
+            };
 
-            let close_task = if let Some(ch_arc) = channel_to_close {
-                Task::perform(
-                    async move {
-                        let mut ch = ch_arc.lock().await;
-                        let _ = ch.close().await;
-                    },
-                    |_| Message::DoNothing,
-                )
+            let close_task = if let Some(channel) = channel_to_close {
+                Task::perform(async move { channel.close().await }, |_| Message::DoNothing)
             } else {
                 Task::none()
             };
-            return Task::batch(vec![close_task, window::close(id)]);
+            let cleanup = Task::batch(vec![
+                close_task,
+                log_task,
+                record_task,
+                drops_task,
+                window::close(id),
+            ]);
+            // launched from the command line: the last terminal closed ends the app
+            return match self.quit_when_no_window() {
+                Some(quit) => cleanup.chain(quit),
+                None => cleanup,
+            };
         }
 
         // if the closed window is the login/dashboard, we want to exit the entire application
+        // (once the session logs and recordings still open are flushed to disk)
         if Some(id) == self.login_window_id {
-            std::process::exit(0);
+            if self.session_logs.is_empty() && self.recorders.is_empty() {
+                std::process::exit(0);
+            }
+            let logs: Vec<_> = self.session_logs.drain().map(|(_, l)| l.close()).collect();
+            let records: Vec<_> = self.recorders.drain().map(|(_, r)| r.close()).collect();
+            return Task::perform(
+                async move {
+                    for close in logs {
+                        close.await;
+                    }
+                    for close in records {
+                        close.await;
+                    }
+                },
+                |_| Message::QuitRequested,
+            );
+        }
+
+        // playback window: nothing but the player to drop
+        self.players.remove(&id);
+        if self.event_log_window == Some(id) {
+            self.event_log_window = None;
+        }
+
+        // SFTP window: stop its transfers and close the subsystem channel
+        if let Some(panel) = self.sftp_panels.remove(&id) {
+            for transfer in &panel.transfers {
+                transfer.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+            if let Some(sftp) = panel.session {
+                return Task::batch(vec![
+                    Task::perform(async move { sftp.close().await }, |_| Message::DoNothing),
+                    window::close(id),
+                ]);
+            }
         }
 
         window::close(id)
     }
 
+    /// Fit the VT100 grid to the new window size and tell the server (and the recording) about it
+    fn handle_window_resized(&mut self, id: window::Id, size: iced::Size) -> Task<Message> {
+        let Some(parser) = self.parsers.get_mut(&id) else {
+            return Task::none();
+        };
+        let (rows, cols) = terminal::grid_size(size);
+        if parser.screen().size() == (rows, cols) {
+            return Task::none();
+        }
+        parser.set_size(rows, cols);
+        if let Some(recorder) = self.recorders.get(&id) {
+            recorder.resize(cols, rows);
+        }
+        self.resize_pty(id)
+    }
+
+    /// Open a terminal window, placed on a 2x2 grid with the ones opened before it
+    fn open_terminal_window(&mut self) -> Task<window::Id> {
+        let win_w = 850.0;
+        let win_h = 550.0;
+
+        // --- rules for window placement ---
+        let gap_x = 15.0; // horizontal gap between windows
+        let gap_y = 45.0; // vertical gap between windows 
+        let margin_x = 40.0;
+        let margin_y = 30.0;
+        // --------------------------------------
+
+        // Grid 2x2
+        let col = (self.spawn_index % 2) as f32;
+        let row = (self.spawn_index / 2) as f32;
+
+        let x = margin_x + (col * (win_w + gap_x));
+        let y = margin_y + (row * (win_h + gap_y));
+
+        self.spawn_index += 1;
+
+        let settings = window::Settings {
+            size: (win_w, win_h).into(),
+            position: window::Position::Specific(iced::Point::new(x, y)),
+            exit_on_close_request: true,
+            ..Default::default()
+        };
+
+        window::open(settings).1
+    }
+
+    /// Shell of an SSH window on its (new) connection, and the latency probe of the profile
+    fn start_ssh_shell(&mut self, id: window::Id, connection: SshConnection) -> Task<Message> {
+        self.session_handles.insert(id, connection.handle.clone());
+        match connection.algorithms.clone() {
+            Some(algorithms) => self.session_algorithms.insert(id, algorithms),
+            None => self.session_algorithms.remove(&id),
+        };
+        let latency_task = match self.session_profile(id).network.keepalive_interval() {
+            Some(interval) => SshService::measure_latency(id, &connection.handle, interval),
+            None => Task::none(),
+        };
+        Task::batch(vec![
+            latency_task,
+            SshService::open_shell(id, connection),
+        ])
+    }
+
+    /// Span of the events about a terminal window and its session
+    fn session_span(&self, id: window::Id) -> tracing::Span {
+        let endpoint = self.session_profiles.get(&id).map(Profile::endpoint).unwrap_or_default();
+        tracing::info_span!("session", window = ?id, %endpoint)
+    }
+
+    /// Send the size of the window's parser to the PTY of the session
+    fn resize_pty(&self, id: window::Id) -> Task<Message> {
+        let (Some(parser), Some(channel)) = (self.parsers.get(&id), self.active_channels.get(&id))
+        else {
+            return Task::none();
+        };
+        let (rows, cols) = parser.screen().size();
+        let channel = channel.clone();
+        Task::perform(async move { channel.resize(rows, cols).await }, |_| Message::DoNothing)
+    }
+
     fn handle_login_msg(&mut self, msg: LoginMessage) -> Task<Message> {
         match msg {
             // Mise à jour des champs du profil "brouillon"
@@ -171,33 +582,45 @@ impl MyApp {
                 Task::none()
             }
             LoginMessage::InputPass(pass) => {
-                self.password = pass;
+                self.password = Zeroizing::new(pass);
                 Task::none()
             }
 
             // Lancement de la connexion SSH
             LoginMessage::Submit => {
-                // 1. Validation de sécurité
-                if self.current_profile.ip.is_empty() || self.current_profile.username.is_empty() {
-                    println!("LOG: Champs manquants pour la connexion.");
+                // the user name may come from the folder
+                let profile = folders::resolve(&self.current_profile, &self.store.settings);
+
+                // 1. Validation de sécurité (un shell local n'a ni adresse ni utilisateur)
+                if profile.protocol.is_remote() {
+                    if profile.ip.is_empty()
+                        || (profile.protocol.needs_login() && profile.username.is_empty())
+                    {
+                        tracing::warn!("Champs manquants pour la connexion.");
+                        return Task::none();
+                    }
+                    if let Err(e) = destination::validate_host(&profile.ip)
+                        .and(destination::validate_port(&profile.port).map(|_| ()))
+                    {
+                        tracing::warn!("Connexion refusée : {}", e);
+                        return Task::none();
+                    }
+                }
+                if profile.protocol == Protocol::Serial && profile.serial.device.trim().is_empty() {
+                    tracing::warn!("Aucun périphérique série indiqué.");
                     return Task::none();
                 }
 
                 self.spawn_index = 0; // On reset l'index de placement
 
-                // 2. Appel au service SSH (on utilise ce que tu as déjà écrit)
-                println!("LOG: Connexion vers {}...", self.current_profile.ip);
+                // 2. Une session par fenêtre demandée
+                tracing::info!("Connexion vers {}...", profile.endpoint());
 
-                let count = self.current_profile.terminal_count.max(1);
+                let count = profile.terminal_count.max(1);
                 let mut tasks = Vec::new();
 
                 for _ in 0..count {
-                    tasks.push(SshService::connect(
-                        self.current_profile.ip.clone(),
-                        self.current_profile.port.parse().unwrap_or(22),
-                        self.current_profile.username.clone(),
-                        self.password.clone(),
-                    ));
+                    tasks.push(Self::start_session(profile.clone(), self.password.clone()));
                 }
                 Task::batch(tasks)
             }
@@ -207,11 +630,18 @@ impl MyApp {
     fn handle_config_msg(&mut self, msg: ConfigMessage) -> Task<Message> {
         match msg {
             ConfigMessage::SectionChanged(section) => {
-                println!("LOG: Changement de section vers : {:?}", section);
+                tracing::debug!(?section, "Changement de section");
                 self.active_section = section;
+                if section == EditSection::Recordings {
+                    return Task::done(Message::Playback(PlaybackMessage::RefreshList));
+                }
             }
+            ConfigMessage::DismissStoreError => self.store_error = None,
             ConfigMessage::ThemeChanged(new_theme) => {
                 self.current_profile.theme = new_theme;
+                // an explicit choice stops following the folder
+                self.current_profile.inherit_theme = false;
+                self.refresh_display_theme();
                 // On sauvegarde immédiatement pour que le choix persiste au redémarrage
                 self.save_profiles();
             }
@@ -222,47 +652,25 @@ impl MyApp {
     fn handle_ssh_msg(&mut self, msg: SshMessage) -> Task<Message> {
         match msg {
             // SSH Connection established, we receive the handle and the ID controller for this session
-            SshMessage::Connected(Ok((handle, id_controller))) => {
-                let win_w = 850.0;
-                let win_h = 550.0;
-
-                // --- rules for window placement ---
-                let gap_x = 15.0; // horizontal gap between windows
-                let gap_y = 45.0; // vertical gap between windows 
-                let margin_x = 40.0;
-                let margin_y = 30.0;
-                // --------------------------------------
-
-                // Grid 2x2
-                let col = (self.spawn_index % 2) as f32;
-                let row = (self.spawn_index / 2) as f32;
-
-                let x = margin_x + (col * (win_w + gap_x));
-                let y = margin_y + (row * (win_h + gap_y));
-
-                self.spawn_index += 1;
-
-                let settings = window::Settings {
-                    size: (win_w, win_h).into(),
-                    position: window::Position::Specific(iced::Point::new(x, y)),
-                    exit_on_close_request: true,
-                    ..Default::default()
-                };
+            SshMessage::Connected(Ok((connection, profile))) => {
+                self.launch_pending = self.launch_pending.saturating_sub(1);
+                self.connect_error = None;
+                self.record_connection(profile.id);
+                if self.quick_profile.as_ref().is_some_and(|quick| quick.id == profile.id) {
+                    self.quick_connected = true;
+                }
 
-                let (_id, win_task) = window::open(settings);
+                let win_task = self.open_terminal_window();
 
                 win_task.map(move |id| {
-                    Message::Ssh(SshMessage::TerminalWindowOpened(
-                        id,
-                        handle.clone(),
-                        id_controller.clone(),
-                    ))
+                    Message::Ssh(SshMessage::TerminalWindowOpened(id, connection.clone(), profile.clone()))
                 })
             }
 
             // window opened, we need to initialize the VT100 parser for this window and start the SSH shell
-            SshMessage::TerminalWindowOpened(id, handle, id_controller) => {
+            SshMessage::TerminalWindowOpened(id, connection, profile) => {
                 self.terminal_window_ids.push(id);
+                self.drop_uploads.insert(id, DropUploads::default());
 
                 // default size for the VT100 parser, it will adapt to the actual window size later when we receive the first data chunk
                 let rows = 28; 
@@ -271,7 +679,170 @@ impl MyApp {
                 let parser = vt100::Parser::new(rows, cols, MAX_TERMINAL_LINES);
                 self.parsers.insert(id, parser);
 
-                crate::ssh::SshService::open_shell(id, handle, id_controller)
+                // the log must exist before the shell sends its first bytes
+                let log_task = match SessionLogger::start(&profile.logging, &profile.ip, &profile.port, &self.open_logs()) {
+                    Some((logger, worker)) => {
+                        self.session_logs.insert(id, logger);
+                        Task::perform(worker, move |res| {
+                            Message::Ssh(SshMessage::LogFinished(id, res))
+                        })
+                    }
+                    None => Task::none(),
+                };
+                self.session_profiles.insert(id, *profile);
+
+                Task::batch(vec![log_task, self.start_ssh_shell(id, connection)])
+            }
+
+            // local shell, Telnet, raw or serial: the window opens right away and shows the connection
+            SshMessage::OpenSession(profile) => {
+                self.launch_pending = self.launch_pending.saturating_sub(1);
+                self.open_terminal_window()
+                    .map(move |id| Message::Ssh(SshMessage::SessionWindowOpened(id, profile.clone())))
+            }
+
+            SshMessage::SessionWindowOpened(id, profile) => {
+                self.terminal_window_ids.push(id);
+                let (rows, cols) = (28, 100);
+                self.parsers.insert(id, vt100::Parser::new(rows, cols, MAX_TERMINAL_LINES));
+
+                let port = if profile.protocol.is_remote() { profile.port.as_str() } else { "" };
+                let log_task = match SessionLogger::start(&profile.logging, profile.host(), port, &self.open_logs()) {
+                    Some((logger, worker)) => {
+                        self.session_logs.insert(id, logger);
+                        Task::perform(worker, move |res| {
+                            Message::Ssh(SshMessage::LogFinished(id, res))
+                        })
+                    }
+                    None => Task::none(),
+                };
+                let session_task = match profile.protocol {
+                    Protocol::Local => LocalService::open(id, rows, cols),
+                    Protocol::Serial => SerialService::open(id, profile.serial.clone()),
+                    _ => TcpService::open(id, &profile, rows, cols),
+                };
+                self.session_profiles.insert(id, *profile);
+
+                Task::batch(vec![log_task, session_task])
+            }
+
+            // the process exited or the connection closed: the window stays open on its last screen
+            SshMessage::SessionEnded(id, end) => {
+                let _session = self.session_span(id).entered();
+                if end.expected {
+                    tracing::info!(reason = %end.reason, "Session terminée");
+                } else {
+                    tracing::warn!(reason = %end.reason, "Session perdue");
+                }
+                // a window closed since
+                if !self.terminal_window_ids.contains(&id) {
+                    return Task::none();
+                }
+                self.active_channels.remove(&id);
+                self.session_handles.remove(&id);
+                self.latencies.remove(&id);
+                if let Some(parser) = self.parsers.get_mut(&id) {
+                    parser.process(format!("\r\n[{}]\r\n", end.reason).as_bytes());
+                }
+
+                let attempts = self.disconnected.get(&id).map_or(0, |d| d.attempts);
+                let settings = &self.session_profile(id).reconnect;
+                let retry_in = (!end.expected && settings.auto && attempts < settings.max_attempts)
+                    .then(|| ReconnectSettings::delay(attempts));
+                self.disconnected.insert(
+                    id,
+                    Disconnected {
+                        reason: end.reason,
+                        attempts,
+                        connecting: false,
+                        retry_in,
+                    },
+                );
+                match retry_in {
+                    Some(delay) => Task::perform(tokio::time::sleep(delay), move |_| {
+                        Message::Ssh(SshMessage::Reconnect(id))
+                    }),
+                    None => Task::none(),
+                }
+            }
+
+            // R pressed, the banner button or the automatic retry: same window, same parser
+            SshMessage::Reconnect(id) => {
+                let Some(state) = self.disconnected.get_mut(&id) else {
+                    return Task::none();
+                };
+                if state.connecting {
+                    return Task::none();
+                }
+                state.attempts += 1;
+                state.connecting = true;
+                state.retry_in = None;
+                let attempt = state.attempts;
+                if let Some(parser) = self.parsers.get_mut(&id) {
+                    parser.process(b"[Reconnexion...]\r\n");
+                }
+
+                let profile = self.session_profile(id).clone();
+                let _session = self.session_span(id).entered();
+                tracing::info!(attempt, "Reconnexion");
+                let (rows, cols) = self.parsers.get(&id).map_or((28, 100), |p| p.screen().size());
+                match profile.protocol {
+                    Protocol::Ssh => {
+                        let password = self.password_for(&profile);
+                        SshService::reconnect(id, profile, password)
+                    }
+                    Protocol::Local => LocalService::open(id, rows, cols),
+                    Protocol::Serial => SerialService::open(id, profile.serial),
+                    Protocol::Telnet | Protocol::Raw => TcpService::open(id, &profile, rows, cols),
+                }
+            }
+
+            SshMessage::Reconnected(id, Ok(connection)) => {
+                if !self.terminal_window_ids.contains(&id) {
+                    return Task::none();
+                }
+                self.record_connection(self.session_profile(id).id);
+                self.start_ssh_shell(id, connection)
+            }
+
+            SshMessage::Reconnected(id, Err(e)) => {
+                Task::done(Message::Ssh(SshMessage::SessionEnded(id, SessionEnd::lost(e.to_string()))))
+            }
+
+            // the window stays, disconnected, and the dashboard tells why
+            SshMessage::ShellFailed(id, e) => {
+                let _session = self.session_span(id).entered();
+                tracing::warn!("Session refusée : {}", e);
+                let reason = e.to_string();
+                self.connect_error = Some(e);
+                Task::done(Message::Ssh(SshMessage::SessionEnded(id, SessionEnd::lost(reason))))
+            }
+
+            SshMessage::DismissConnectError => {
+                self.connect_error = None;
+                Task::none()
+            }
+
+            SshMessage::Progress(progress_id, update) => {
+                match update {
+                    connect_progress::Update::Started(target) => {
+                        // a new attempt replaces the failed one (automatic reconnection)
+                        self.connections.retain(|(_, p)| p.target != target || !p.failed());
+                        self.connections.push((progress_id, ConnectProgress::new(target)));
+                    }
+                    connect_progress::Update::Step(step, state) => {
+                        if let Some((_, progress)) = self.connections.iter_mut().find(|(p, _)| *p == progress_id) {
+                            progress.update(step, state);
+                        }
+                    }
+                    connect_progress::Update::Finished => self.connections.retain(|(p, _)| *p != progress_id),
+                }
+                Task::none()
+            }
+
+            SshMessage::DismissProgress(progress_id) => {
+                self.connections.retain(|(p, _)| *p != progress_id);
+                Task::none()
             }
 
             // Data received from SSH, we need to feed it to the correct VT100 parser based on the window ID
@@ -280,6 +851,15 @@ impl MyApp {
                 if let Some(parser) = self.parsers.get_mut(&id) {
                     parser.process(&raw_bytes);
                 }
+                if let Some(logger) = self.session_logs.get(&id) {
+                    logger.write(&raw_bytes);
+                }
+                if let Some(recorder) = self.recorders.get_mut(&id) {
+                    recorder.output(&raw_bytes);
+                }
+                if let Some(drops) = self.drop_uploads.get_mut(&id) {
+                    drops.cwd.feed(&raw_bytes);
+                }
                 // auto scroll to bottom on new data
                 let scroll_id = scrollable::Id::new(format!("scroll_{:?}", id));
                 scrollable::snap_to(scroll_id, scrollable::RelativeOffset::END)
@@ -287,13 +867,100 @@ impl MyApp {
             }
 
             // store the active channel for this window to be able to send data back later
+            // (the PTY was requested with a default size, align it on the parser)
             SshMessage::SetChannel(id, ch) => {
-                self.active_channels.insert(id, ch);
+                // SSH sessions count once authenticated, the others once started
+                if !matches!(ch, SessionChannel::Ssh(_)) {
+                    self.record_connection(self.session_profile(id).id);
+                }
+                self.disconnected.remove(&id);
+                self.active_channels.insert(id, ch.clone());
+
+                let commands: Vec<u8> = self
+                    .session_profile(id)
+                    .startup_commands
+                    .iter()
+                    .filter(|command| !command.trim().is_empty())
+                    .flat_map(|command| format!("{}\r", command).into_bytes())
+                    .collect();
+                let startup_task = if commands.is_empty() {
+                    Task::none()
+                } else {
+                    Task::perform(async move { ch.send(&commands).await }, |_| Message::DoNothing)
+                };
+                Task::batch(vec![self.resize_pty(id), startup_task])
+            }
+
+            SshMessage::ToggleRecording(id) => {
+                if let Some(recorder) = self.recorders.remove(&id) {
+                    return Task::perform(recorder.close(), |_| Message::DoNothing);
+                }
+                let Some(parser) = self.parsers.get(&id) else {
+                    return Task::none();
+                };
+                let (rows, cols) = parser.screen().size();
+                let profile = self.session_profile(id);
+                let (mut recorder, worker) = SessionRecorder::start(
+                    SessionRecorder::default_path(&profile.ip),
+                    cols,
+                    rows,
+                    format!("{}@{}", profile.username, profile.ip),
+                );
+                // start from what is on screen, not from a blank terminal
+                recorder.output(&parser.screen().contents_formatted());
+                self.recorders.insert(id, recorder);
+                Task::perform(worker, move |res| {
+                    Message::Ssh(SshMessage::RecordingFinished(id, res))
+                })
+            }
+
+            SshMessage::RecordingFinished(id, res) => match res {
+                Ok(path) => {
+                    tracing::info!(parent: &self.session_span(id), path = %path.display(), "Enregistrement terminé");
+                    Task::done(Message::Playback(PlaybackMessage::RefreshList))
+                }
+                Err(e) => {
+                    tracing::warn!(parent: &self.session_span(id), "Erreur d'enregistrement : {}", e);
+                    self.recorders.remove(&id);
+                    Task::none()
+                }
+            },
+
+            // the log writer stopped: closed normally, or an I/O error (we stop logging for this window)
+            SshMessage::LogFinished(id, res) => {
+                match res {
+                    Ok(path) => tracing::info!(parent: &self.session_span(id), path = %path.display(), "Journal de session fermé"),
+                    Err(e) => {
+                        tracing::warn!(parent: &self.session_span(id), "Erreur du journal de session : {}", e);
+                        self.session_logs.remove(&id);
+                    }
+                }
                 Task::none()
             }
 
             SshMessage::Connected(Err(e)) => {
-                println!("Erreur de connexion : {}", e);
+                tracing::error!("Erreur de connexion : {}", e);
+                // nothing on screen to report it when launched from the command line
+                if self.launch_pending > 0 {
+                    self.launch_pending -= 1;
+                    eprintln!("rustty: {}", e);
+                    eprintln!("rustty: {}", e.suggestion());
+                    if let Some(quit) = self.quit_when_no_window() {
+                        return quit;
+                    }
+                }
+                // a quick connection waiting for its result: the panel below tells why
+                if self.quick_profile.is_some() && !self.quick_connected {
+                    self.quick_profile = None;
+                }
+                self.connect_error = Some(e);
+                Task::none()
+            }
+            SshMessage::Latency(id, latency) => {
+                // late reply of a connection lost since
+                if self.session_handles.contains_key(&id) {
+                    self.latencies.insert(id, latency);
+                }
                 Task::none()
             }
             SshMessage::WindowFocused(id) => {
@@ -305,7 +972,782 @@ impl MyApp {
         }
     }
 
+    fn handle_vault_msg(&mut self, msg: VaultMessage) -> Task<Message> {
+        match msg {
+            VaultMessage::InputMaster(value) => self.vault_master = Zeroizing::new(value),
+            VaultMessage::InputConfirm(value) => self.vault_confirm = Zeroizing::new(value),
+            VaultMessage::InputPassphrase(value) => self.vault_passphrase = Zeroizing::new(value),
+            VaultMessage::Create => {
+                if self.vault_master.chars().count() < 8 {
+                    self.vault_error = Some("Le mot de passe maître doit faire au moins 8 caractères".into());
+                } else if self.vault_master != self.vault_confirm {
+                    self.vault_error = Some("Les deux mots de passe ne correspondent pas".into());
+                } else {
+                    self.vault_busy = true;
+                    self.vault_error = None;
+                    return Task::perform(
+                        Vault::create(Vault::default_path(), self.vault_master.clone()),
+                        |res| Message::Vault(VaultMessage::Opened(res)),
+                    );
+                }
+            }
+            VaultMessage::Unlock => {
+                self.vault_busy = true;
+                self.vault_error = None;
+                return Task::perform(
+                    Vault::unlock(Vault::default_path(), self.vault_master.clone()),
+                    |res| Message::Vault(VaultMessage::Opened(res)),
+                );
+            }
+            VaultMessage::Opened(res) => {
+                self.vault_busy = false;
+                self.vault_master.zeroize();
+                self.vault_confirm.zeroize();
+                match res {
+                    Ok(vault) => {
+                        tracing::info!("Coffre déverrouillé ({})", vault.path().display());
+                        self.vault = Some(vault);
+                        self.vault_last_used = std::time::Instant::now();
+                        self.fill_from_vault();
+                    }
+                    Err(e) => self.vault_error = Some(e),
+                }
+            }
+            VaultMessage::Lock => self.lock_vault(),
+            VaultMessage::StoreSecrets | VaultMessage::ForgetSecrets => {
+                let (Some(vault), Some(id)) = (self.vault.as_mut(), self.selected_profile_id) else {
+                    return Task::none();
+                };
+                if matches!(msg, VaultMessage::StoreSecrets) {
+                    vault.set_entry(
+                        id,
+                        VaultEntry {
+                            password: self.password.to_string(),
+                            key_passphrase: self.vault_passphrase.to_string(),
+                        },
+                    );
+                } else {
+                    vault.remove_entry(&id);
+                    self.vault_passphrase.zeroize();
+                }
+                self.vault_error = vault.save().err();
+                self.vault_last_used = std::time::Instant::now();
+            }
+            VaultMessage::AutoLockChanged(minutes) => {
+                self.store.settings.vault_auto_lock_minutes = minutes;
+                self.save_profiles();
+            }
+            VaultMessage::Tick(now) => {
+                let minutes = self.store.settings.vault_auto_lock_minutes;
+                if self.vault.is_some()
+                    && minutes > 0
+                    && now.duration_since(self.vault_last_used).as_secs() >= u64::from(minutes) * 60
+                {
+                    tracing::info!("Coffre verrouillé automatiquement après {} min", minutes);
+                    self.lock_vault();
+                }
+            }
+        }
+        Task::none()
+    }
+
+    /// Forget the key and every secret taken from the vault (zeroized on drop)
+    fn lock_vault(&mut self) {
+        self.vault = None;
+        self.password.zeroize();
+        self.vault_passphrase.zeroize();
+    }
+
+    /// Put the secrets of the selected profile in the form, when the vault is unlocked
+    fn fill_from_vault(&mut self) {
+        let (Some(vault), Some(id)) = (&self.vault, self.selected_profile_id) else {
+            return;
+        };
+        match vault.entry(&id) {
+            Some(entry) => {
+                self.password = Zeroizing::new(entry.password.clone());
+                self.vault_passphrase = Zeroizing::new(entry.key_passphrase.clone());
+                self.vault_last_used = std::time::Instant::now();
+            }
+            None => self.vault_passphrase.zeroize(),
+        }
+    }
+
+    /// Password to connect the profile again: from the vault, else the one it was typed for
+    fn password_for(&self, profile: &Profile) -> Zeroizing<String> {
+        if let Some(entry) = self.vault.as_ref().and_then(|v| v.entry(&profile.id)) {
+            return Zeroizing::new(entry.password.clone());
+        }
+        if self.quick_profile.as_ref().is_some_and(|quick| quick.id == profile.id) {
+            return self.quick_password.clone();
+        }
+        self.password.clone()
+    }
+
+    /// The selected profile has secrets in the unlocked vault
+    pub fn has_vault_entry(&self) -> bool {
+        match (&self.vault, self.selected_profile_id) {
+            (Some(vault), Some(id)) => vault.entry(&id).is_some(),
+            _ => false,
+        }
+    }
+
+    fn handle_event_log_msg(&mut self, msg: EventLogMessage) -> Task<Message> {
+        match msg {
+            EventLogMessage::Open => {
+                if let Some(id) = self.event_log_window {
+                    return window::gain_focus(id);
+                }
+                let (id, win_task) = window::open(window::Settings {
+                    size: (900.0, 500.0).into(),
+                    ..Default::default()
+                });
+                self.event_log_window = Some(id);
+                return win_task.discard();
+            }
+            EventLogMessage::LevelChanged(level) => self.event_log_level = level,
+            EventLogMessage::VerbosityChanged(level) => {
+                self.store.settings.log_level = level;
+                crate::logging::set_level(level);
+                self.save_profiles();
+            }
+            EventLogMessage::Copy => {
+                return iced::clipboard::write(event_log::to_text(&event_log::events(self.event_log_level)));
+            }
+            EventLogMessage::Clear => event_log::clear(),
+            // the view reads the events again
+            EventLogMessage::Tick => {}
+        }
+        Task::none()
+    }
+
+    fn handle_quick_connect_msg(&mut self, msg: QuickConnectMessage) -> Task<Message> {
+        match msg {
+            QuickConnectMessage::Input(input) => {
+                self.quick_input = input;
+                self.quick_error = None;
+            }
+            QuickConnectMessage::InputPass(pass) => self.quick_password = Zeroizing::new(pass),
+            QuickConnectMessage::Connect => {
+                let destination = match destination::parse(&self.quick_input) {
+                    Ok(destination) => destination,
+                    Err(e) => {
+                        self.quick_error = Some(e);
+                        return Task::none();
+                    }
+                };
+                // like ssh, the local user name when none is given
+                let Some(username) = destination
+                    .user
+                    .clone()
+                    .or_else(|| std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok())
+                else {
+                    self.quick_error = Some("Utilisateur manquant : saisissez utilisateur@hôte".into());
+                    return Task::none();
+                };
+                let profile = Profile {
+                    name: destination.host.clone(),
+                    ip: destination.host,
+                    port: destination.port.unwrap_or(22).to_string(),
+                    username,
+                    ..Profile::default()
+                };
+
+                tracing::info!("Connexion rapide vers {}@{}:{}", profile.username, profile.ip, profile.port);
+                self.quick_error = None;
+                self.quick_connected = false;
+                self.quick_profile = Some(profile.clone());
+                self.spawn_index = 0;
+                return SshService::connect(profile, self.quick_password.clone());
+            }
+            QuickConnectMessage::SaveAsProfile => {
+                let Some(mut profile) = self.quick_profile.take() else {
+                    return Task::none();
+                };
+                self.quick_connected = false;
+                if self.profiles.iter().any(|p| p.name.eq_ignore_ascii_case(&profile.name)) {
+                    profile.name = crate::import::unique_name(&profile.name, &self.profiles);
+                }
+                profile.group = folders::normalize(&profile.group);
+                profile.record_connection(chrono::Local::now().timestamp());
+                let id = profile.id;
+                tracing::info!("Connexion rapide enregistrée comme profil '{}'", profile.name);
+                self.profiles.push(profile);
+                self.profiles
+                    .sort_by(|a, b| a.group.cmp(&b.group).then(a.name.cmp(&b.name)));
+                self.save_profiles();
+                self.quick_input.clear();
+                // opened in the form to be named and completed
+                self.active_section = EditSection::General;
+                return self.handle_profile_msg(ProfileMessage::Selected(id));
+            }
+            QuickConnectMessage::Dismiss => {
+                self.quick_profile = None;
+                self.quick_connected = false;
+            }
+        }
+        Task::none()
+    }
+
+    fn handle_import_msg(&mut self, msg: ImportMessage) -> Task<Message> {
+        match msg {
+            ImportMessage::SourceChanged(source) => {
+                self.import_source = source;
+                self.import_candidates.clear();
+                self.import_status = None;
+                if let Some(path) = source.default_path() {
+                    self.import_path = path.display().to_string();
+                }
+            }
+            ImportMessage::InputPath(path) => self.import_path = path,
+            ImportMessage::Analyse => {
+                let path = self.import_path.trim();
+                if path.is_empty() {
+                    return Task::none();
+                }
+                self.import_busy = true;
+                self.import_status = None;
+                return Task::perform(
+                    crate::import::analyse(self.import_source, path.into(), self.profiles.clone()),
+                    |res| Message::Import(ImportMessage::Analysed(res)),
+                );
+            }
+            ImportMessage::Analysed(res) => {
+                self.import_busy = false;
+                match res {
+                    Ok(candidates) => {
+                        tracing::info!("{} session(s) trouvée(s) dans {}", candidates.len(), self.import_path);
+                        self.import_candidates = candidates;
+                    }
+                    Err(e) => {
+                        self.import_candidates.clear();
+                        self.import_status = Some(Err(e));
+                    }
+                }
+            }
+            ImportMessage::Toggle(row, selected) => {
+                if let Some(candidate) = self.import_candidates.get_mut(row) {
+                    candidate.selected = selected;
+                }
+            }
+            ImportMessage::ToggleAll(selected) => {
+                self.import_candidates.iter_mut().for_each(|c| c.selected = selected);
+            }
+            ImportMessage::ResolutionChanged(row, resolution) => {
+                if let Some(candidate) = self.import_candidates.get_mut(row) {
+                    candidate.resolution = resolution;
+                }
+            }
+            ImportMessage::Apply => {
+                let summary = crate::import::apply(&mut self.profiles, &self.import_candidates);
+                tracing::info!("Import terminé : {}", summary);
+                self.save_profiles();
+                self.import_candidates.clear();
+                self.import_status = Some(Ok(summary));
+            }
+            ImportMessage::ExportToggled(id, included) => {
+                if included {
+                    self.export_selection.insert(id);
+                } else {
+                    self.export_selection.remove(&id);
+                }
+            }
+            ImportMessage::ExportGroup(group, included) => {
+                for profile in self.profiles.iter().filter(|p| p.group == group) {
+                    if included {
+                        self.export_selection.insert(profile.id);
+                    } else {
+                        self.export_selection.remove(&profile.id);
+                    }
+                }
+            }
+            ImportMessage::ExportAll(included) => {
+                self.export_selection = if included {
+                    self.profiles.iter().map(|p| p.id).collect()
+                } else {
+                    Default::default()
+                };
+            }
+            ImportMessage::CopySshConfig => {
+                self.export_status = None;
+                return iced::clipboard::write(self.ssh_config_snippet());
+            }
+            ImportMessage::SaveSshConfig => {
+                return Task::perform(
+                    crate::import::openssh::save(
+                        crate::import::openssh::default_export_path(),
+                        self.ssh_config_snippet(),
+                    ),
+                    |res| Message::Import(ImportMessage::ExportSaved(res)),
+                );
+            }
+            ImportMessage::InputBundlePath(path) => self.bundle_path = path,
+            ImportMessage::SaveBundle => {
+                let path = self.bundle_path.trim();
+                if path.is_empty() {
+                    return Task::none();
+                }
+                let profiles: Vec<Profile> = self
+                    .profiles
+                    .iter()
+                    .filter(|p| self.export_selection.contains(&p.id))
+                    .cloned()
+                    .collect();
+                return Task::perform(
+                    crate::import::bundle::save(path.into(), profiles),
+                    |res| Message::Import(ImportMessage::ExportSaved(res)),
+                );
+            }
+            ImportMessage::ExportSaved(res) => {
+                match &res {
+                    Ok(path) => tracing::info!("Profils exportés dans {}", path.display()),
+                    Err(e) => tracing::warn!("Export impossible : {}", e),
+                }
+                self.export_status = Some(res);
+            }
+        }
+        Task::none()
+    }
+
+    /// Host blocks of the profiles picked for the ssh_config export, in the profiles order
+    pub fn ssh_config_snippet(&self) -> String {
+        let profiles: Vec<&Profile> = self
+            .profiles
+            .iter()
+            .filter(|p| self.export_selection.contains(&p.id))
+            .collect();
+        crate::import::openssh::to_config(&profiles)
+    }
+
+    fn handle_exec_msg(&mut self, msg: ExecMessage) -> Task<Message> {
+        match msg {
+            ExecMessage::InputCommand(command) => self.exec_command = command,
+            ExecMessage::TargetChanged(target) => self.exec_target = Some(target),
+            ExecMessage::Run => {
+                let command = self.exec_command.trim().to_string();
+                let Some(target) = &self.exec_target else {
+                    return Task::none();
+                };
+                if command.is_empty() {
+                    return Task::none();
+                }
+                let profiles: Vec<Profile> = target
+                    .profiles(&self.profiles)
+                    .into_iter()
+                    .map(|p| folders::resolve(p, &self.store.settings))
+                    .collect();
+                tracing::info!("Exécution de '{}' sur {} hôte(s)", command, profiles.len());
+
+                self.exec_run += 1;
+                self.exec_export = None;
+                self.exec_cancel = Default::default();
+                self.exec_results = profiles
+                    .iter()
+                    .map(|p| ExecResult::pending(p, &command))
+                    .collect();
+
+                // every host runs at the same time, on its own connection
+                let run = self.exec_run;
+                let tasks = profiles.into_iter().enumerate().map(|(row, profile)| {
+                    Task::perform(
+                        crate::exec::run(
+                            profile.clone(),
+                            self.password_for(&profile),
+                            command.clone(),
+                            self.exec_cancel.clone(),
+                        ),
+                        move |result| Message::Exec(ExecMessage::Finished(run, row, result)),
+                    )
+                });
+                return Task::batch(tasks);
+            }
+            ExecMessage::Cancel => {
+                tracing::info!("Commandes à distance annulées");
+                self.exec_cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+            ExecMessage::Finished(run, row, result) => {
+                if run == self.exec_run
+                    && let Some(slot) = self.exec_results.get_mut(row)
+                {
+                    *slot = result;
+                }
+            }
+            ExecMessage::Export(format) => {
+                return Task::perform(
+                    crate::exec::export(self.exec_results.clone(), format),
+                    |res| Message::Exec(ExecMessage::Exported(res)),
+                );
+            }
+            ExecMessage::Exported(res) => {
+                match &res {
+                    Ok(path) => tracing::info!("Résultats exportés dans {}", path.display()),
+                    Err(e) => tracing::warn!("Export impossible : {}", e),
+                }
+                self.exec_export = Some(res);
+            }
+        }
+        Task::none()
+    }
+
+    fn handle_playback_msg(&mut self, msg: PlaybackMessage) -> Task<Message> {
+        match msg {
+            PlaybackMessage::RefreshList => {
+                return Task::perform(list_recordings(), |list| {
+                    Message::Playback(PlaybackMessage::ListLoaded(list))
+                });
+            }
+            PlaybackMessage::ListLoaded(list) => self.recordings = list,
+            PlaybackMessage::Open(path) => {
+                return Task::perform(Recording::load(path), |res| {
+                    Message::Playback(PlaybackMessage::Loaded(res))
+                });
+            }
+            PlaybackMessage::Loaded(Ok(recording)) => {
+                let (id, win_task) = window::open(window::Settings {
+                    size: (850.0, 600.0).into(),
+                    ..Default::default()
+                });
+                self.players.insert(id, Player::new(recording));
+                self.last_tick = None;
+                return win_task.discard();
+            }
+            PlaybackMessage::Loaded(Err(e)) => {
+                tracing::warn!("Impossible de lire l'enregistrement : {}", e);
+            }
+            PlaybackMessage::TogglePause(id) => {
+                if let Some(player) = self.players.get_mut(&id) {
+                    player.toggle_pause();
+                    self.last_tick = None;
+                }
+            }
+            PlaybackMessage::Seek(id, position) => {
+                if let Some(player) = self.players.get_mut(&id) {
+                    player.seek(position);
+                }
+            }
+            PlaybackMessage::SpeedChanged(id, speed) => {
+                if let Some(player) = self.players.get_mut(&id) {
+                    player.speed = speed;
+                }
+            }
+            PlaybackMessage::Tick(now) => {
+                let elapsed = self
+                    .last_tick
+                    .map(|last| now.duration_since(last).as_secs_f64())
+                    .unwrap_or(0.0);
+                self.last_tick = Some(now);
+                for player in self.players.values_mut() {
+                    player.advance(elapsed);
+                }
+            }
+        }
+        Task::none()
+    }
+
+    fn handle_sftp_msg(&mut self, msg: SftpMessage) -> Task<Message> {
+        let id = msg.window_id();
+        if self.drop_uploads.contains_key(&id) && !matches!(msg, SftpMessage::Open(_)) {
+            return self.handle_drop_msg(msg);
+        }
+
+        // from a terminal window: open a new SFTP window on the same connection
+        if let SftpMessage::Open(terminal_id) = msg {
+            let Some(handle) = self.session_handles.get(&terminal_id).cloned() else {
+                return Task::none();
+            };
+            let (panel_id, win_task) = window::open(window::Settings {
+                size: (760.0, 640.0).into(),
+                ..Default::default()
+            });
+            // the profile of that session, not the one selected in the dashboard
+            let profile = self.session_profile(terminal_id);
+            let title = format!("{}@{}", profile.username, profile.ip);
+            self.sftp_panels.insert(panel_id, SftpPanel::new(title));
+            return Task::batch(vec![win_task.discard(), SftpService::open(panel_id, handle)]);
+        }
+
+        let transfer_id = self.next_transfer_id;
+        let Some(panel) = self.sftp_panels.get_mut(&id) else {
+            return Task::none();
+        };
+        let Some(sftp) = panel.session.clone() else {
+            if let SftpMessage::Ready(_, res) = msg {
+                return match res {
+                    Ok(sftp) => {
+                        panel.session = Some(sftp.clone());
+                        SftpService::list(id, sftp, ".".into())
+                    }
+                    Err(e) => {
+                        panel.error = Some(e);
+                        Task::none()
+                    }
+                };
+            }
+            return Task::none();
+        };
+        let selected_path = panel
+            .selected_entry()
+            .map(|e| (crate::sftp::join(&panel.cwd, &e.name), e.is_dir));
+
+        match msg {
+            SftpMessage::Navigate(_, path) => return SftpService::list(id, sftp, path),
+            SftpMessage::Listed(_, Ok((path, entries))) => {
+                panel.cwd = path;
+                panel.entries = entries;
+                panel.selected = None;
+                panel.rename_input.clear();
+                panel.chmod_input.clear();
+                panel.error = None;
+            }
+            SftpMessage::Listed(_, Err(e)) | SftpMessage::OperationDone(_, Err(e)) => {
+                panel.error = Some(e)
+            }
+            SftpMessage::OperationDone(_, Ok(())) => {
+                panel.error = None;
+                return SftpService::list(id, sftp, panel.cwd.clone());
+            }
+            SftpMessage::EntryClicked(_, name) => {
+                if let Some((path, true)) = selected_path.filter(|_| panel.selected.as_ref() == Some(&name)) {
+                    return SftpService::list(id, sftp, path);
+                }
+                if let Some(entry) = panel.entries.iter().find(|e| e.name == name) {
+                    panel.chmod_input = format!("{:o}", entry.permissions);
+                }
+                panel.rename_input = name.clone();
+                panel.selected = Some(name);
+            }
+            SftpMessage::InputRename(_, v) => panel.rename_input = v,
+            SftpMessage::InputChmod(_, v) => panel.chmod_input = v,
+            SftpMessage::InputMkdir(_, v) => panel.mkdir_input = v,
+            SftpMessage::InputUpload(_, v) => panel.upload_input = v,
+            SftpMessage::Rename(_) => {
+                let new_name = panel.rename_input.trim();
+                if let Some((from, _)) = selected_path
+                    && !new_name.is_empty()
+                {
+                    let to = crate::sftp::join(&panel.cwd, new_name);
+                    return SftpService::operation(id, async move {
+                        crate::sftp::rename(&sftp, &from, &to).await
+                    });
+                }
+            }
+            SftpMessage::Chmod(_) => {
+                let Some((path, _)) = selected_path else {
+                    return Task::none();
+                };
+                match crate::sftp::parse_mode(&panel.chmod_input) {
+                    Some(mode) => {
+                        return SftpService::operation(id, async move {
+                            crate::sftp::chmod(&sftp, &path, mode).await
+                        });
+                    }
+                    None => panel.error = Some("Droits invalides (octal attendu, ex. 755)".into()),
+                }
+            }
+            SftpMessage::Delete(_) => {
+                if let Some((path, is_dir)) = selected_path {
+                    return SftpService::operation(id, async move {
+                        crate::sftp::remove(&sftp, &path, is_dir).await
+                    });
+                }
+            }
+            SftpMessage::Mkdir(_) => {
+                // never send an empty or blank name, whatever sent the message
+                let name = panel.mkdir_input.trim();
+                if name.is_empty() {
+                    panel.error = Some("Nom de dossier vide".into());
+                    return Task::none();
+                }
+                let path = crate::sftp::join(&panel.cwd, name);
+                panel.mkdir_input.clear();
+                return SftpService::operation(id, async move {
+                    crate::sftp::mkdir(&sftp, &path).await
+                });
+            }
+            SftpMessage::Upload(_) => {
+                let local = std::path::PathBuf::from(panel.upload_input.trim());
+                let Some(name) = local
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .filter(|_| local.is_file())
+                else {
+                    panel.error = Some(format!("Fichier local introuvable : {}", local.display()));
+                    return Task::none();
+                };
+                panel.upload_input.clear();
+                let remote = crate::sftp::join(&panel.cwd, &name);
+                let cancel = panel.start_transfer(transfer_id, name, TransferKind::Upload);
+                self.next_transfer_id += 1;
+                return SftpService::transfer(
+                    id,
+                    transfer_id,
+                    TransferKind::Upload,
+                    sftp,
+                    local,
+                    remote,
+                    cancel,
+                );
+            }
+            SftpMessage::Download(_) => {
+                let Some((remote, false)) = selected_path else {
+                    return Task::none();
+                };
+                let name = panel.selected.clone().unwrap_or_default();
+                let local = crate::sftp::download_dir().join(&name);
+                let cancel = panel.start_transfer(transfer_id, name, TransferKind::Download);
+                self.next_transfer_id += 1;
+                return SftpService::transfer(
+                    id,
+                    transfer_id,
+                    TransferKind::Download,
+                    sftp,
+                    local,
+                    remote,
+                    cancel,
+                );
+            }
+            SftpMessage::TransferProgress(_, transfer_id, done, total) => {
+                if let Some(t) = panel.transfers.iter_mut().find(|t| t.id == transfer_id) {
+                    t.done = done;
+                    t.total = total;
+                }
+            }
+            SftpMessage::TransferFinished(_, transfer_id, res) => {
+                let Some(t) = panel.transfers.iter_mut().find(|t| t.id == transfer_id) else {
+                    return Task::none();
+                };
+                let refresh = t.kind == TransferKind::Upload && res.is_ok();
+                t.result = Some(res);
+                if refresh {
+                    return SftpService::list(id, sftp, panel.cwd.clone());
+                }
+            }
+            SftpMessage::CancelTransfer(_, transfer_id) => {
+                if let Some(t) = panel.transfers.iter().find(|t| t.id == transfer_id) {
+                    t.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+            _ => {}
+        }
+        Task::none()
+    }
+
+    /// Files dropped on a terminal window, uploaded into the shell's current directory
+    fn handle_drop_msg(&mut self, msg: SftpMessage) -> Task<Message> {
+        let id = msg.window_id();
+        let Some(handle) = self.session_handles.get(&id).cloned() else {
+            return Task::none();
+        };
+        let Some(drops) = self.drop_uploads.get_mut(&id) else {
+            return Task::none();
+        };
+
+        let mut tasks = Vec::new();
+        match msg {
+            SftpMessage::FileDropped(_, path) => {
+                drops.queued.push(path);
+                if drops.uploader.is_none() && !drops.opening {
+                    drops.opening = true;
+                    tasks.push(SftpService::open_uploader(id, handle));
+                }
+            }
+            SftpMessage::DropReady(_, res) => {
+                drops.opening = false;
+                match res {
+                    Ok(uploader) => drops.uploader = Some(uploader),
+                    Err(e) => {
+                        for path in drops.queued.drain(..) {
+                            let mut transfer = Transfer::new(0, file_label(&path), TransferKind::Upload);
+                            transfer.result = Some(Err(e.clone()));
+                            drops.transfers.push(transfer);
+                        }
+                        drops.prompt = None;
+                    }
+                }
+            }
+            SftpMessage::InputDropDir(_, value) => {
+                if let Some(dir) = drops.prompt.as_mut() {
+                    *dir = value;
+                }
+            }
+            SftpMessage::ConfirmDropDir(_) => {
+                if let Some(dir) = drops.prompt.take() {
+                    // sftp and scp both resolve relative paths from the home folder
+                    let dir = dir.trim().trim_start_matches("~/").trim_start_matches('~');
+                    drops.target_dir = Some(if dir.is_empty() { ".".into() } else { dir.into() });
+                }
+            }
+            SftpMessage::CancelDropDir(_) => {
+                drops.prompt = None;
+                drops.queued.clear();
+            }
+            SftpMessage::DismissDrops(_) => drops.transfers.retain(|t| t.result.is_none()),
+            SftpMessage::TransferProgress(_, transfer_id, done, total) => {
+                if let Some(t) = drops.transfers.iter_mut().find(|t| t.id == transfer_id) {
+                    t.done = done;
+                    t.total = total;
+                }
+            }
+            SftpMessage::TransferFinished(_, transfer_id, res) => {
+                if let Some(t) = drops.transfers.iter_mut().find(|t| t.id == transfer_id) {
+                    if let Err(e) = &res {
+                        tracing::warn!("Upload of {} failed: {}", t.name, e);
+                    }
+                    t.result = Some(res);
+                }
+            }
+            SftpMessage::CancelTransfer(_, transfer_id) => {
+                if let Some(t) = drops.transfers.iter().find(|t| t.id == transfer_id) {
+                    t.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+            _ => {}
+        }
+
+        if drops.queued.is_empty() || drops.prompt.is_some() {
+            return Task::batch(tasks);
+        }
+        // destination: the prompt answer, or the directory announced by the shell
+        let Some(dir) = drops
+            .target_dir
+            .clone()
+            .or_else(|| drops.cwd.cwd().map(str::to_string))
+        else {
+            drops.prompt = Some(String::new());
+            return Task::batch(tasks);
+        };
+        let Some(uploader) = drops.uploader.clone() else {
+            return Task::batch(tasks);
+        };
+
+        drops.target_dir = None;
+        for path in drops.queued.drain(..) {
+            let mut transfer = Transfer::new(self.next_transfer_id, file_label(&path), TransferKind::Upload);
+            self.next_transfer_id += 1;
+            if !path.is_file() {
+                transfer.result = Some(Err("Seuls les fichiers peuvent être envoyés".into()));
+            } else {
+                tasks.push(SftpService::drop_upload(
+                    id,
+                    transfer.id,
+                    uploader.clone(),
+                    path,
+                    dir.clone(),
+                    transfer.cancel.clone(),
+                ));
+            }
+            drops.transfers.push(transfer);
+        }
+        Task::batch(tasks)
+    }
+
     fn handle_keyboard_event(&mut self, event: iced::Event) -> Task<Message> {
+        // a drag released anywhere but on a folder is cancelled
+        if self.dragging.is_some()
+            && let iced::Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) = event
+        {
+            return Task::done(Message::Folder(FolderMessage::CancelDrag));
+        }
         if let iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, modifiers, .. }) =
             event
         {
@@ -314,23 +1756,52 @@ impl MyApp {
                 .focused_window_id
                 .or_else(|| self.terminal_window_ids.last().cloned());
 
-            if let Some(window_id) = target_window_id {
-                if let Some(channel_arc) = self.active_channels.get(&window_id) {
-                    if let Some(bytes) = map_key_to_ssh(&key, modifiers) {
-                        let arc = channel_arc.clone();
-                        return Task::perform(
-                            async move {
-                                let mut ch = arc.lock().await;
-                                let _ = ch.data(&bytes[..]).await;
-                            },
-                            |_| Message::DoNothing,
-                        );
+            // session lost: R reconnects, nothing else is sent anywhere
+            if let Some(window_id) = target_window_id
+                && self.disconnected.contains_key(&window_id)
+            {
+                if let Key::Character(c) = &key
+                    && c.eq_ignore_ascii_case("r")
+                    && !modifiers.control()
+                    && !modifiers.alt()
+                {
+                    return Task::done(Message::Ssh(SshMessage::Reconnect(window_id)));
+                }
+                return Task::none();
+            }
+
+            if let Some(window_id) = target_window_id
+                && let Some(channel) = self.active_channels.get(&window_id)
+            {
+                if let Some(bytes) = map_key_to_ssh(&key, modifiers) {
+                    let channel = channel.clone();
+                    return Task::perform(
+                        async move { channel.send(&bytes).await },
+                        |_| Message::DoNothing,
+                    );
+                }
+                return Task::none();
+            }
+
+            // --- CASE B : PROFILE TABLE (DASHBOARD) ---
+            let dashboard_focused = self
+                .focused_window_id
+                .is_none_or(|id| Some(id) == self.login_window_id);
+            if dashboard_focused
+                && self.active_section == EditSection::General
+                && self.selected_folder.is_none()
+            {
+                match key {
+                    Key::Named(Named::ArrowUp) => return self.move_selection(-1),
+                    Key::Named(Named::ArrowDown) => return self.move_selection(1),
+                    Key::Named(Named::Enter) => {
+                        return Task::done(Message::Profile(ProfileMessage::ConnectSelected));
                     }
-                    return Task::none();
+                    _ => {}
                 }
             }
 
-            // --- CASE B : NAVIGATION TAB (LOGIN) ---
+            // --- CASE C : NAVIGATION TAB (LOGIN) ---
             if key == Key::Named(Named::Tab) {
                 let next_id = match self.focused_id {
                     ID_PROFILE => ID_GROUP,
@@ -352,16 +1823,140 @@ impl MyApp {
             ProfileMessage::Selected(id) => {
                 if let Some(profile) = self.profiles.iter().find(|p| p.id == id) {
                     self.selected_profile_id = Some(id);
+                    self.selected_folder = None;
                     self.current_profile = profile.clone();
+                    self.tags_input = profile.tags.join(", ");
+                    if profile.protocol == Protocol::Serial {
+                        self.serial_ports = crate::serial::available_ports();
+                    }
+                    // show the theme it inherits, the user name stays empty to keep inheriting it
+                    self.refresh_display_theme();
+                    self.fill_from_vault();
                 }
             }
             ProfileMessage::InputName(name) => self.current_profile.name = name,
-            ProfileMessage::InputGroup(group) => self.current_profile.group = group,
+            ProfileMessage::InputGroup(group) => {
+                self.current_profile.group = group;
+                self.refresh_display_theme();
+            }
             ProfileMessage::SearchChanged(query) => self.search_query = query,
             ProfileMessage::TerminalCountChanged(new_count) => {
                 // max terminal windows allowed is 4, min is 1
                 self.current_profile.terminal_count = new_count.clamp(1, 4);
             }
+            ProfileMessage::ProtocolChanged(protocol) => {
+                // the usual port of the new type, unless another one was typed
+                let previous = self.current_profile.protocol.default_port();
+                if let Some(port) = protocol.default_port()
+                    && (self.current_profile.port.is_empty() || Some(self.current_profile.port.as_str()) == previous)
+                {
+                    self.current_profile.port = port.into();
+                }
+                self.current_profile.protocol = protocol;
+                if protocol == Protocol::Serial {
+                    self.serial_ports = crate::serial::available_ports();
+                }
+            }
+            ProfileMessage::InputSerialDevice(device) => self.current_profile.serial.device = device,
+            ProfileMessage::SerialBaudRateChanged(rate) => self.current_profile.serial.baud_rate = rate,
+            ProfileMessage::SerialDataBitsChanged(bits) => self.current_profile.serial.data_bits = bits,
+            ProfileMessage::SerialParityChanged(parity) => self.current_profile.serial.parity = parity,
+            ProfileMessage::SerialStopBitsChanged(bits) => self.current_profile.serial.stop_bits = bits,
+            ProfileMessage::SerialFlowControlChanged(flow) => {
+                self.current_profile.serial.flow_control = flow
+            }
+            ProfileMessage::LogModeChanged(mode) => self.current_profile.logging.mode = mode,
+            ProfileMessage::InputLogFile(template) => {
+                self.current_profile.logging.file_template = template
+            }
+            ProfileMessage::LogAppendToggled(append) => self.current_profile.logging.append = append,
+            ProfileMessage::InputKeepaliveInterval(value) => {
+                if let Some(seconds) = parse_number(&value) {
+                    self.current_profile.network.keepalive_interval = seconds;
+                }
+            }
+            ProfileMessage::InputKeepaliveMax(value) => {
+                if let Some(count) = parse_number(&value).and_then(|n| u32::try_from(n).ok()) {
+                    self.current_profile.network.keepalive_max = count;
+                }
+            }
+            ProfileMessage::InputInactivityTimeout(value) => {
+                if let Some(seconds) = parse_number(&value) {
+                    self.current_profile.network.inactivity_timeout = seconds;
+                }
+            }
+            ProfileMessage::InputConnectTimeout(value) => {
+                if let Some(seconds) = parse_number(&value) {
+                    self.current_profile.network.connect_timeout = seconds;
+                }
+            }
+            ProfileMessage::TcpNodelayToggled(on) => self.current_profile.network.tcp_nodelay = on,
+            ProfileMessage::TcpKeepaliveToggled(on) => self.current_profile.network.tcp_keepalive = on,
+            ProfileMessage::AlgorithmPresetChosen(preset) => {
+                self.current_profile.algorithms = preset.apply_to(&self.current_profile.algorithms)
+            }
+            ProfileMessage::AlgorithmToggled(kind, name) => self.current_profile.algorithms.toggle(kind, &name),
+            ProfileMessage::AlgorithmRaised(kind, index) => self.current_profile.algorithms.raise(kind, index),
+            ProfileMessage::ReconnectAutoToggled(auto) => self.current_profile.reconnect.auto = auto,
+            ProfileMessage::ReconnectAttemptsChanged(attempts) => {
+                self.current_profile.reconnect.max_attempts = attempts
+            }
+            ProfileMessage::StartupCommandChanged(index, command) => {
+                if let Some(line) = self.current_profile.startup_commands.get_mut(index) {
+                    *line = command;
+                }
+            }
+            ProfileMessage::StartupCommandAdded => self.current_profile.startup_commands.push(String::new()),
+            ProfileMessage::StartupCommandRemoved(index) => {
+                if index < self.current_profile.startup_commands.len() {
+                    self.current_profile.startup_commands.remove(index);
+                }
+            }
+            ProfileMessage::InheritThemeToggled(inherit) => {
+                self.current_profile.inherit_theme = inherit;
+                self.refresh_display_theme();
+            }
+
+            ProfileMessage::InputTags(tags) => self.tags_input = tags,
+            ProfileMessage::FavouriteToggled(id) => {
+                if let Some(profile) = self.profiles.iter_mut().find(|p| p.id == id) {
+                    profile.favourite = !profile.favourite;
+                    if self.selected_profile_id == Some(id) {
+                        self.current_profile.favourite = profile.favourite;
+                    }
+                    self.save_profiles();
+                }
+            }
+            ProfileMessage::ViewChanged(view) => self.profile_view = view,
+            ProfileMessage::TagFilterToggled(tag) => {
+                if !self.tag_filter.remove(&tag) {
+                    self.tag_filter.insert(tag);
+                }
+            }
+
+            ProfileMessage::SortBy(column) => {
+                (self.sort_column, self.sort_descending) = match (self.sort_column, self.sort_descending) {
+                    (Some(sorted), false) if sorted == column => (Some(column), true),
+                    (Some(sorted), true) if sorted == column => (None, false),
+                    _ => (Some(column), false),
+                };
+            }
+            ProfileMessage::ConnectSelected => {
+                // the selected row if it is listed, else the first one (best match of the search)
+                let order = Listing::new(self).order(&self.collapsed_folders);
+                let listed = |id| order.iter().any(|&i| self.profiles[i].id == id);
+                let id = match self.selected_profile_id {
+                    Some(id) if listed(id) => id,
+                    _ => match order.first() {
+                        Some(&index) => self.profiles[index].id,
+                        None => return Task::none(),
+                    },
+                };
+                if self.selected_profile_id != Some(id) {
+                    let _ = self.handle_profile_msg(ProfileMessage::Selected(id));
+                }
+                return self.handle_login_msg(LoginMessage::Submit);
+            }
 
             ProfileMessage::Save => {
                 self.perform_save_profile();
@@ -370,13 +1965,29 @@ impl MyApp {
             ProfileMessage::New => {
                 self.selected_profile_id = None;
                 self.current_profile = Profile::default();
+                self.tags_input.clear();
+                // created in the selected folder, following its defaults
+                if let Some(folder) = self.selected_folder.take() {
+                    self.current_profile.inherit_theme =
+                        folders::defaults_for(&self.store.settings, &folder).theme.is_some();
+                    self.current_profile.group = folder;
+                }
+                self.refresh_display_theme();
             }
 
             ProfileMessage::Delete => {
                 if let Some(id) = self.selected_profile_id {
                     self.profiles.retain(|p| p.id != id);
+                    if let Some(vault) = self.vault.as_mut()
+                        && vault.remove_entry(&id)
+                        && let Err(e) = vault.save()
+                    {
+                        self.vault_error = Some(e);
+                    }
                     self.selected_profile_id = None;
                     self.current_profile = Profile::default();
+                    self.refresh_display_theme();
+                    self.tags_input.clear();
                     self.save_profiles();
                 }
             }
@@ -384,22 +1995,276 @@ impl MyApp {
         Task::none()
     }
 
-    fn perform_save_profile(&mut self) {
-        if self.current_profile.ip.is_empty() || self.current_profile.name.is_empty() {
+    fn handle_folder_msg(&mut self, msg: FolderMessage) -> Task<Message> {
+        match msg {
+            FolderMessage::Toggle(path) => {
+                if !self.collapsed_folders.remove(&path) {
+                    self.collapsed_folders.insert(path.clone());
+                }
+                if self.selected_folder.as_ref() != Some(&path) {
+                    self.folder_rename = path.clone();
+                    self.folder_default_user = self
+                        .store
+                        .settings
+                        .folders
+                        .get(&path)
+                        .and_then(|d| d.username.clone())
+                        .unwrap_or_default();
+                    self.folder_delete_armed = false;
+                    self.selected_folder = Some(path);
+                }
+            }
+            FolderMessage::Grab(id) => {
+                self.dragging = Some(id);
+                return self.handle_profile_msg(ProfileMessage::Selected(id));
+            }
+            FolderMessage::Hover(path) => self.drop_target = Some(path),
+            FolderMessage::Unhover(path) => {
+                if self.drop_target.as_ref() == Some(&path) {
+                    self.drop_target = None;
+                }
+            }
+            FolderMessage::DropOn(path) => {
+                let Some(id) = self.dragging.take() else {
+                    return Task::none();
+                };
+                self.drop_target = None;
+                if let Some(profile) = self.profiles.iter_mut().find(|p| p.id == id)
+                    && profile.group != path
+                {
+                    tracing::info!("Profil '{}' déplacé de {} vers {}", profile.name, profile.group, path);
+                    profile.group = path.clone();
+                    if self.selected_profile_id == Some(id) {
+                        self.current_profile.group = path;
+                        self.refresh_display_theme();
+                    }
+                    self.profiles
+                        .sort_by(|a, b| a.group.cmp(&b.group).then(a.name.cmp(&b.name)));
+                    self.save_profiles();
+                }
+            }
+            FolderMessage::CancelDrag => {
+                self.dragging = None;
+                self.drop_target = None;
+            }
+            FolderMessage::InputRename(path) => self.folder_rename = path,
+            FolderMessage::Rename => {
+                let Some(folder) = self.selected_folder.clone() else {
+                    return Task::none();
+                };
+                let new_path = folders::normalize(&self.folder_rename);
+                // moving a folder into itself would loop
+                if new_path == folder || folders::is_within(&new_path, &folder) {
+                    return Task::none();
+                }
+                let moved = folders::rename(&mut self.profiles, &mut self.store.settings, &folder, &new_path);
+                tracing::info!("Dossier {} renommé en {} ({} profil(s))", folder, new_path, moved);
+                if folders::is_within(&self.current_profile.group, &folder) {
+                    self.current_profile.group =
+                        format!("{}{}", new_path, &self.current_profile.group[folder.len()..]);
+                }
+                self.profiles
+                    .sort_by(|a, b| a.group.cmp(&b.group).then(a.name.cmp(&b.name)));
+                self.folder_rename = new_path.clone();
+                self.selected_folder = Some(new_path);
+                self.save_profiles();
+            }
+            FolderMessage::Delete => {
+                if !self.folder_delete_armed {
+                    self.folder_delete_armed = true;
+                    return Task::none();
+                }
+                let Some(folder) = self.selected_folder.take() else {
+                    return Task::none();
+                };
+                let removed: Vec<uuid::Uuid> = self
+                    .profiles
+                    .iter()
+                    .filter(|p| folders::is_within(&p.group, &folder))
+                    .map(|p| p.id)
+                    .collect();
+                self.profiles.retain(|p| !removed.contains(&p.id));
+                self.store.settings.folders.retain(|path, _| !folders::is_within(path, &folder));
+                if let Some(vault) = self.vault.as_mut() {
+                    let mut changed = false;
+                    for id in &removed {
+                        changed |= vault.remove_entry(id);
+                    }
+                    if changed && let Err(e) = vault.save() {
+                        self.vault_error = Some(e);
+                    }
+                }
+                if self.selected_profile_id.is_some_and(|id| removed.contains(&id)) {
+                    self.selected_profile_id = None;
+                    self.current_profile = Profile::default();
+                }
+                self.refresh_display_theme();
+                tracing::info!("Dossier {} supprimé ({} profil(s))", folder, removed.len());
+                self.folder_delete_armed = false;
+                self.save_profiles();
+            }
+            FolderMessage::CancelDelete => self.folder_delete_armed = false,
+            FolderMessage::ConnectAll => {
+                let Some(folder) = &self.selected_folder else {
+                    return Task::none();
+                };
+                let profiles: Vec<Profile> = self
+                    .profiles
+                    .iter()
+                    .filter(|p| folders::is_within(&p.group, folder))
+                    .map(|p| folders::resolve(p, &self.store.settings))
+                    .filter(|p| {
+                        !p.protocol.is_remote()
+                            || (!p.ip.is_empty() && (!p.protocol.needs_login() || !p.username.is_empty()))
+                    })
+                    .collect();
+                tracing::info!("Connexion à {} profil(s) du dossier {}", profiles.len(), folder);
+                self.spawn_index = 0;
+
+                // one window per profile; the vault has the passwords, else the one typed in the form
+                let tasks: Vec<_> = profiles
+                    .into_iter()
+                    .map(|profile| {
+                        let password = self
+                            .vault
+                            .as_ref()
+                            .and_then(|v| v.entry(&profile.id))
+                            .map(|entry| Zeroizing::new(entry.password.clone()))
+                            .unwrap_or_else(|| self.password.clone());
+                        Self::start_session(profile, password)
+                    })
+                    .collect();
+                return Task::batch(tasks);
+            }
+            FolderMessage::DefaultTheme(theme) => {
+                let Some(folder) = self.selected_folder.clone() else {
+                    return Task::none();
+                };
+                let defaults = self.store.settings.folders.entry(folder.clone()).or_default();
+                defaults.theme = theme;
+                if defaults.is_empty() {
+                    self.store.settings.folders.remove(&folder);
+                }
+                // the profiles already in the folder follow it
+                if theme.is_some() {
+                    for profile in self.profiles.iter_mut().filter(|p| folders::is_within(&p.group, &folder)) {
+                        profile.inherit_theme = true;
+                    }
+                    if folders::is_within(&self.current_profile.group, &folder) {
+                        self.current_profile.inherit_theme = true;
+                    }
+                }
+                self.refresh_display_theme();
+                self.save_profiles();
+            }
+            FolderMessage::InputDefaultUser(user) => self.folder_default_user = user,
+            FolderMessage::ApplyDefaultUser => {
+                let Some(folder) = self.selected_folder.clone() else {
+                    return Task::none();
+                };
+                let user = self.folder_default_user.trim();
+                let defaults = self.store.settings.folders.entry(folder.clone()).or_default();
+                defaults.username = (!user.is_empty()).then(|| user.to_string());
+                if defaults.is_empty() {
+                    self.store.settings.folders.remove(&folder);
+                }
+                self.save_profiles();
+            }
+        }
+        Task::none()
+    }
+
+    /// Without the dashboard, quit once no window and no connection is left
+    fn quit_when_no_window(&self) -> Option<Task<Message>> {
+        let idle = self.login_window_id.is_none()
+            && self.launch_pending == 0
+            && self.terminal_window_ids.is_empty()
+            && self.sftp_panels.is_empty()
+            && self.players.is_empty();
+        idle.then(|| Task::done(Message::QuitRequested))
+    }
+
+    /// Select the previous or next row of the profile table and scroll to it
+    fn move_selection(&mut self, step: isize) -> Task<Message> {
+        let order = Listing::new(self).order(&self.collapsed_folders);
+        if order.is_empty() {
+            return Task::none();
+        }
+        let current = order
+            .iter()
+            .position(|&i| Some(self.profiles[i].id) == self.selected_profile_id);
+        let position = match current {
+            Some(position) => position.saturating_add_signed(step).min(order.len() - 1),
+            None if step < 0 => order.len() - 1,
+            None => 0,
+        };
+        let id = self.profiles[order[position]].id;
+        let _ = self.handle_profile_msg(ProfileMessage::Selected(id));
+
+        // rows are about the same height: good enough to keep the selection in view
+        let offset = if order.len() > 1 { position as f32 / (order.len() - 1) as f32 } else { 0.0 };
+        scrollable::snap_to(
+            scrollable::Id::new(ID_PROFILE_LIST),
+            scrollable::RelativeOffset { x: 0.0, y: offset },
+        )
+    }
+
+    /// Usage stats of a saved profile, after a successful connection
+    fn record_connection(&mut self, id: uuid::Uuid) {
+        let Some(profile) = self.profiles.iter_mut().find(|p| p.id == id) else {
             return;
+        };
+        profile.record_connection(chrono::Local::now().timestamp());
+        if self.selected_profile_id == Some(id) {
+            self.current_profile.last_connected = profile.last_connected;
+            self.current_profile.connection_count = profile.connection_count;
         }
+        self.save_profiles();
+    }
 
-        // Normalisation du groupe
-        if self.current_profile.group.is_empty() {
-            self.current_profile.group = "DEFAUT".to_string();
+    /// Profile a terminal window was opened for
+    /// Log files of the open windows, a new window must not write into one of them
+    fn open_logs(&self) -> Vec<&std::path::Path> {
+        self.session_logs.values().map(SessionLogger::path).collect()
+    }
+
+    /// Follow the folder of the current profile when it inherits its theme
+    fn refresh_display_theme(&mut self) {
+        self.display_theme = folders::resolve(&self.current_profile, &self.store.settings).theme;
+    }
+
+    pub fn session_profile(&self, window_id: window::Id) -> &Profile {
+        self.session_profiles
+            .get(&window_id)
+            .unwrap_or(&self.current_profile)
+    }
+
+    fn perform_save_profile(&mut self) {
+        if self.current_profile.name.is_empty() {
+            return;
+        }
+        // the form shows what is wrong under the field
+        if self.current_profile.protocol.is_remote()
+            && (self.current_profile.ip.is_empty()
+                || destination::validate_host(&self.current_profile.ip).is_err()
+                || destination::validate_port(&self.current_profile.port).is_err())
+        {
+            return;
         }
-        self.current_profile.group = self.current_profile.group.to_uppercase();
+
+        // Normalisation du dossier ("prod / eu" -> "PROD/EU")
+        self.current_profile.group = folders::normalize(&self.current_profile.group);
+        self.refresh_display_theme();
+        self.current_profile.tags = Profile::parse_tags(&self.tags_input);
 
         match self.selected_profile_id {
             Some(id) => {
                 if let Some(index) = self.profiles.iter().position(|p| p.id == id) {
                     let mut updated = self.current_profile.clone();
                     updated.id = id;
+                    // counted since the profile was selected
+                    updated.last_connected = self.profiles[index].last_connected;
+                    updated.connection_count = self.profiles[index].connection_count;
                     self.profiles[index] = updated;
                 }
             }
@@ -417,18 +2282,34 @@ impl MyApp {
     }
 }
 
+/// .cast files of the recordings folder, most recent first
+async fn list_recordings() -> Vec<std::path::PathBuf> {
+    let mut list = Vec::new();
+    if let Ok(mut dir) = tokio::fs::read_dir(crate::recording::recordings_dir()).await {
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "cast") {
+                let modified = entry.metadata().await.and_then(|m| m.modified()).ok();
+                list.push((modified, path));
+            }
+        }
+    }
+    list.sort_by(|a, b| b.cmp(a));
+    list.into_iter().map(|(_, path)| path).collect()
+}
+
 // pure function no self needed
 fn map_key_to_ssh(key: &Key, mods: Modifiers) -> Option<Vec<u8>> {
     // shortcut keyboard combinations with Control (e.g., Ctrl+C, Ctrl+D, etc.)
     // control key is pressed ? 
-    if mods.control() {
-        if let Key::Character(c) = key {
-            let b = c.as_bytes();
-            if !b.is_empty() {
-                // In ASCII, Ctrl + key corresponds to the key MASKED by 0x1f
-                // Example : 'c' (99) & 0x1f = 3 (Code ETX/Ctrl+C)
-                return Some(vec![b[0] & 0x1f]);
-            }
+    if mods.control()
+        && let Key::Character(c) = key
+    {
+        let b = c.as_bytes();
+        if !b.is_empty() {
+            // In ASCII, Ctrl + key corresponds to the key MASKED by 0x1f
+            // Example : 'c' (99) & 0x1f = 3 (Code ETX/Ctrl+C)
+            return Some(vec![b[0] & 0x1f]);
         }
     }
 
@@ -455,3 +2336,16 @@ fn map_key_to_ssh(key: &Key, mods: Modifiers) -> Option<Vec<u8>> {
         _ => None,
     }
 }
+
+// name shown in the transfer list for a local file
+fn file_label(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// number field of the network page: empty is 0, anything else but digits is ignored
+fn parse_number(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if value.is_empty() { Some(0) } else { value.parse().ok() }
+}
@@ -1,4 +1,4 @@
-use crate::messages::{LoginMessage, Message, ProfileMessage};
+use crate::messages::{EventLogMessage, LoginMessage, Message, ProfileMessage};
 use crate::ui::theme;
 use crate::ui::{theme::TerminalColors};
 use iced::widget::{button, container, row, text};
@@ -35,6 +35,11 @@ pub fn buttons_form<'a>(colors: TerminalColors, is_editing: bool) -> Element<'a,
             .padding(10)
             .style(move |_, s| theme::button_style(c3, s,theme::ButtonVariant::Primary)),
 
+        button(text("Événements").center())
+            .on_press(Message::EventLog(EventLogMessage::Open))
+            .padding(10)
+            .style(move |_, s| theme::button_style(c4, s,theme::ButtonVariant::Secondary)),
+
         button(text("Quitter").center())
             .padding(10)
             .on_press(Message::QuitRequested)
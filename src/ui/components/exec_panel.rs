@@ -0,0 +1,160 @@
+use crate::exec::{ExecResult, ExecTarget, ExportFormat};
+use crate::messages::{ExecMessage, Message};
+use crate::ui::theme;
+use crate::ui::{MyApp, theme::TerminalColors};
+use iced::font::Weight;
+use iced::widget::{button, column, container, pick_list, row, scrollable, text, text_input};
+use iced::{Alignment, Element, Font, Length};
+
+// one-off command (exec channel, no shell) on a profile or on every profile of a group
+pub fn render<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let running = app.exec_results.iter().any(|r| r.running);
+    let can_run = !running && app.exec_target.is_some() && !app.exec_command.trim().is_empty();
+
+    let command_bar = row![
+        pick_list(
+            ExecTarget::all(&app.profiles),
+            app.exec_target.clone(),
+            |target| Message::Exec(ExecMessage::TargetChanged(target))
+        )
+        .placeholder("Profil ou groupe")
+        .width(Length::FillPortion(1))
+        .padding(10),
+        text_input("Commande, ex. uptime ou df -h", &app.exec_command)
+            .on_input(|v| Message::Exec(ExecMessage::InputCommand(v)))
+            .on_submit_maybe(can_run.then_some(Message::Exec(ExecMessage::Run)))
+            .font(Font::MONOSPACE)
+            .padding(10)
+            .width(Length::FillPortion(2))
+            .style(move |_, status| theme::input_style(colors, status)),
+        if running {
+            button(text("Annuler").center())
+                .on_press(Message::Exec(ExecMessage::Cancel))
+                .padding(10)
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary))
+        } else {
+            button(text("Exécuter").center())
+                .on_press_maybe(can_run.then_some(Message::Exec(ExecMessage::Run)))
+                .padding(10)
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary))
+        },
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    let has_results = !app.exec_results.is_empty() && !running;
+    let export_button = |label, format| {
+        button(text(label).size(12))
+            .on_press_maybe(has_results.then_some(Message::Exec(ExecMessage::Export(format))))
+            .padding([4, 10])
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary))
+    };
+    let export_status = match &app.exec_export {
+        Some(Ok(path)) => text(format!("Exporté : {}", path.display())).color(colors.prompt),
+        Some(Err(e)) => text(format!("⚠️ {}", e)).color(colors.accent),
+        None => text(""),
+    };
+
+    let mut table = column![].spacing(1);
+    for (i, result) in app.exec_results.iter().enumerate() {
+        let zebra_color = if i % 2 == 0 { colors.surface } else { colors.bg };
+        table = table.push(
+            container(result_row(result, colors))
+                .padding(8)
+                .width(Length::Fill)
+                .style(move |_| container::Style {
+                    background: Some(zebra_color.into()),
+                    ..Default::default()
+                }),
+        );
+    }
+    if app.exec_results.is_empty() {
+        table = table.push(
+            text(format!(
+                "Chaque hôte utilise son mot de passe du coffre, sinon celui saisi dans le formulaire. Une commande est arrêtée après {} min.",
+                crate::exec::RUN_TIMEOUT.as_secs() / 60
+            ))
+                .size(13)
+                .color(colors.prompt),
+        );
+    }
+
+    column![
+        text("COMMANDES À DISTANCE")
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        command_bar,
+        row![
+            export_button("Exporter texte", ExportFormat::Text),
+            export_button("Exporter CSV", ExportFormat::Csv),
+            export_button("Exporter JSON", ExportFormat::Json),
+            export_status.size(12),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        scrollable(table).height(Length::Fill),
+    ]
+    .spacing(15)
+    .into()
+}
+
+// host and status on the first line, then stdout and stderr
+fn result_row<'a>(result: &'a ExecResult, colors: TerminalColors) -> Element<'a, Message> {
+    let status = if result.running {
+        text("⏳ en cours").color(colors.text)
+    } else if let Some(e) = &result.error {
+        text(format!("✖ {}", e)).color(colors.accent)
+    } else {
+        match result.exit_status {
+            Some(0) => text("✔ 0").color(colors.prompt),
+            Some(code) => text(format!("✖ {}", code)).color(colors.accent),
+            None => text("? sans code").color(colors.accent),
+        }
+    };
+
+    let mut block = column![
+        row![
+            text(&result.profile)
+                .width(Length::FillPortion(2))
+                .font(Font {
+                    weight: Weight::Bold,
+                    ..Font::default()
+                })
+                .color(colors.text),
+            text(&result.host).width(Length::FillPortion(2)).color(colors.text),
+            status.width(Length::FillPortion(2)),
+            text(if result.running {
+                String::new()
+            } else {
+                format!("{} ms", result.duration_ms)
+            })
+            .size(12)
+            .width(Length::FillPortion(1))
+            .color(colors.text),
+        ]
+        .spacing(10),
+    ]
+    .spacing(4);
+
+    if !result.stdout.is_empty() {
+        block = block.push(
+            text(result.stdout.trim_end())
+                .size(12)
+                .font(Font::MONOSPACE)
+                .color(colors.text),
+        );
+    }
+    if !result.stderr.is_empty() {
+        block = block.push(
+            text(result.stderr.trim_end())
+                .size(12)
+                .font(Font::MONOSPACE)
+                .color(colors.accent),
+        );
+    }
+    block.into()
+}
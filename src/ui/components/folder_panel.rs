@@ -0,0 +1,118 @@
+use crate::folders;
+use crate::messages::{FolderMessage, Message};
+use crate::ui::theme::{self, ThemeChoice};
+use crate::ui::{MyApp, theme::TerminalColors};
+use iced::font::Weight;
+use iced::widget::{button, column, pick_list, row, text, text_input};
+use iced::{Alignment, Element, Font, Length};
+
+// actions on the selected folder and on every profile below it
+pub fn render<'a>(app: &'a MyApp, folder: &'a str, colors: TerminalColors) -> Element<'a, Message> {
+    let count = app
+        .profiles
+        .iter()
+        .filter(|p| folders::is_within(&p.group, folder))
+        .count();
+    let own = app.store.settings.folders.get(folder);
+    let inherited = folders::defaults_for(&app.store.settings, folder);
+
+    let delete: Element<_> = if app.folder_delete_armed {
+        row![
+            text(format!("Supprimer {} profil(s) ?", count)).size(13).color(colors.accent),
+            action("Confirmer", FolderMessage::Delete, theme::ButtonVariant::Danger, colors),
+            action("Annuler", FolderMessage::CancelDelete, theme::ButtonVariant::Secondary, colors),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+    } else {
+        action("Supprimer le dossier", FolderMessage::Delete, theme::ButtonVariant::Secondary, colors)
+    };
+
+    let theme_hint = match (own.and_then(|d| d.theme), inherited.theme) {
+        (None, Some(theme)) => format!("hérité : {}", theme),
+        _ => String::new(),
+    };
+    let user_hint = match (own.and_then(|d| d.username.as_ref()), &inherited.username) {
+        (None, Some(user)) => format!("hérité : {}", user),
+        _ => String::new(),
+    };
+
+    column![
+        text(format!("DOSSIER {} — {} profil(s)", folder, count))
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        row![
+            action("Tout connecter", FolderMessage::ConnectAll, theme::ButtonVariant::Primary, colors),
+            delete,
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        row![
+            input("Nouveau chemin, ex. PROD/EU", &app.folder_rename, FolderMessage::InputRename, FolderMessage::Rename, colors),
+            action("Renommer / déplacer", FolderMessage::Rename, theme::ButtonVariant::Secondary, colors),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        text("VALEURS PAR DÉFAUT DES PROFILS DU DOSSIER").size(12).color(colors.accent),
+        row![
+            text("Thème").size(13).width(Length::Fixed(120.0)).color(colors.text),
+            pick_list(&ThemeChoice::ALL[..], own.and_then(|d| d.theme), |theme| {
+                Message::Folder(FolderMessage::DefaultTheme(Some(theme)))
+            })
+            .placeholder("Aucun")
+            .text_size(13)
+            .padding(6),
+            action("Retirer", FolderMessage::DefaultTheme(None), theme::ButtonVariant::Secondary, colors),
+            text(theme_hint).size(12).color(colors.prompt),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        row![
+            text("Utilisateur").size(13).width(Length::Fixed(120.0)).color(colors.text),
+            input("Aucun", &app.folder_default_user, FolderMessage::InputDefaultUser, FolderMessage::ApplyDefaultUser, colors),
+            action("Appliquer", FolderMessage::ApplyDefaultUser, theme::ButtonVariant::Secondary, colors),
+            text(user_hint).size(12).color(colors.prompt),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        text("Les profils sans utilisateur prennent celui du dossier le plus proche ; le thème s'applique aux profils qui le suivent (onglet Thèmes). Glissez un profil sur un dossier pour l'y déplacer.")
+            .size(11)
+            .color(colors.prompt),
+    ]
+    .spacing(15)
+    .into()
+}
+
+fn input<'a>(
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> FolderMessage,
+    on_submit: FolderMessage,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    text_input(placeholder, value)
+        .on_input(move |v| Message::Folder(on_input(v)))
+        .on_submit(Message::Folder(on_submit))
+        .padding(8)
+        .width(Length::Fixed(260.0))
+        .style(move |_, status| theme::input_style(colors, status))
+        .into()
+}
+
+fn action<'a>(
+    label: &'a str,
+    msg: FolderMessage,
+    variant: theme::ButtonVariant,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    button(text(label).size(13).center())
+        .on_press(Message::Folder(msg))
+        .padding([6, 12])
+        .style(move |_, s| theme::button_style(colors, s, variant))
+        .into()
+}
@@ -1,13 +1,93 @@
+use crate::destination;
 use crate::messages::{ConfigMessage, LoginMessage, Message, ProfileMessage};
+use crate::models::{AlgorithmKind, AlgorithmPreset, FlowControl, LogMode, Parity, Protocol, ReconnectSettings, SerialSettings};
 use crate::ui::theme::{self, ThemeChoice};
 use crate::ui::{MyApp, theme::TerminalColors};
 use iced::alignment::{Horizontal, Vertical};
 use iced::font::Weight;
-use iced::widget::{button, column, container, row, scrollable, text, text_input};
+use iced::widget::{button, checkbox, column, container, pick_list, row, scrollable, text, text_input};
 use iced::{Alignment, Color, Element, Font, Length};
 
 // general form (sidebar)
 pub fn general_form<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let remote = app.current_profile.protocol.is_remote();
+    // address and account, only for the sessions that connect somewhere
+    let address = row![
+        render_input_with_label(
+            "Adresse IP",
+            &app.current_profile.ip,
+            text_input::Id::new("ip_input"),
+            colors,
+            field_error(&app.current_profile.ip, destination::validate_host),
+            false,
+            |s| Message::Login(LoginMessage::InputIP(s)),
+            None
+        ),
+        render_input_with_label(
+            "Port",
+            &app.current_profile.port,
+            text_input::Id::new("port_input"),
+            colors,
+            field_error(&app.current_profile.port, destination::validate_port),
+            false,
+            |s| Message::Login(LoginMessage::InputPort(s)),
+            None
+        ),
+    ]
+    .spacing(10);
+    let account = row![
+        render_input_with_label(
+            "Nom d'utilisateur",
+            &app.current_profile.username,
+            text_input::Id::new("user_input"),
+            colors,
+            (app.current_profile.username.is_empty()
+                && crate::folders::defaults_for(&app.store.settings, &app.current_profile.group)
+                    .username
+                    .is_some())
+            .then(|| "Vide : celui du dossier est utilisé".into()),
+            false,
+            |s| Message::Login(LoginMessage::InputUsername(s)),
+            None,
+        ),
+        render_input_with_label(
+            "Mot de passe",
+            &app.password,
+            text_input::Id::new("pass_input"),
+            colors,
+            Some(if app.has_vault_entry() {
+                "🔒 Enregistré dans le coffre chiffré".into()
+            } else {
+                "⚠️ Non enregistré dans le profil pour votre sécurité".into()
+            }),
+            true,
+            |s| Message::Login(LoginMessage::InputPass(s)),
+            Some(Message::Login(LoginMessage::Submit))
+        ),
+    ]
+    .spacing(10);
+    let tags = column![
+        render_input_with_label(
+            "Tags (séparés par des virgules)",
+            &app.tags_input,
+            text_input::Id::new("tags_input"),
+            colors,
+            None,
+            false,
+            |s| Message::Profile(ProfileMessage::InputTags(s)),
+            None,
+        ),
+        text(usage_summary(&app.current_profile)).size(11).color(colors.prompt),
+    ]
+    .spacing(5);
+    let options = column![
+        text("OPTIONS DE SESSION").size(12).color(colors.accent),
+        terminal_count_selector(app.current_profile.terminal_count, colors),
+        reconnect_fields(&app.current_profile.reconnect, colors),
+        startup_commands(&app.current_profile.startup_commands, colors),
+    ]
+    .spacing(8);
+
     column![
         text("ÉDITION DU PROFIL")
             .size(14)
@@ -28,7 +108,7 @@ pub fn general_form<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, M
                 None,
             ),
             render_input_with_label(
-                "Groupe",
+                "Dossier (ex. PROD/EU)",
                 &app.current_profile.group,
                 text_input::Id::new("group_input"),
                 colors,
@@ -40,67 +120,174 @@ pub fn general_form<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, M
         ]
         .spacing(10),
         row![
-            render_input_with_label(
-                "Adresse IP",
-                &app.current_profile.ip,
-                text_input::Id::new("ip_input"),
-                colors,
-                None,
-                false,
-                |s| Message::Login(LoginMessage::InputIP(s)),
-                None
-            ),
-            render_input_with_label(
-                "Port",
-                &app.current_profile.port,
-                text_input::Id::new("port_input"),
-                colors,
-                None,
-                false,
-                |s| Message::Login(LoginMessage::InputPort(s)),
-                None
-            ),
-        ]
-        .spacing(10),
-        row![
-            render_input_with_label(
-                "Nom d'utilisateur",
-                &app.current_profile.username,
-                text_input::Id::new("user_input"),
-                colors,
-                None,
-                false,
-                |s| Message::Login(LoginMessage::InputUsername(s)),
-                None,
-            ),
-            render_input_with_label(
-                "Mot de passe",
-                &app.password,
-                text_input::Id::new("pass_input"),
-                colors,
-                Some("⚠️ Non enregistré dans le profil pour votre sécurité"),
-                true,
-                |s| Message::Login(LoginMessage::InputPass(s)),
-                Some(Message::Login(LoginMessage::Submit))
-            ),
-        ]
-        .spacing(10),
-        column![
-            text("OPTIONS DE SESSION").size(12).color(colors.accent),
-            terminal_count_selector(app.current_profile.terminal_count, colors),
+            text("Type de session :").width(Length::Fill).color(colors.text),
+            pick_list(&Protocol::ALL[..], Some(app.current_profile.protocol), |protocol| {
+                Message::Profile(ProfileMessage::ProtocolChanged(protocol))
+            })
+            .padding(8),
         ]
-        .spacing(8),
+        .spacing(10)
+        .align_y(Alignment::Center),
     ]
+    .push_maybe(remote.then_some(address))
+    .push_maybe(app.current_profile.protocol.needs_login().then_some(account))
+    .push_maybe((app.current_profile.protocol == Protocol::Serial).then(|| serial_fields(app, colors)))
+    .push(tags)
+    .push(options)
     .spacing(15)
     .into()
 }
 
+// device and line settings of a Serial profile
+fn serial_fields<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let serial = &app.current_profile.serial;
+    let detected = if app.serial_ports.is_empty() {
+        "Aucun port série détecté".to_string()
+    } else {
+        format!("Détectés : {}", app.serial_ports.join(", "))
+    };
+    let setting = |label: &'a str, control: Element<'a, Message>| {
+        row![text(label).width(Length::Fill).color(colors.text), control]
+            .spacing(10)
+            .align_y(Alignment::Center)
+    };
+
+    column![
+        render_input_with_label(
+            "Périphérique",
+            &serial.device,
+            text_input::Id::new("serial_device_input"),
+            colors,
+            Some(detected),
+            false,
+            |s| Message::Profile(ProfileMessage::InputSerialDevice(s)),
+            Some(Message::Login(LoginMessage::Submit)),
+        ),
+        setting(
+            "Vitesse (bauds) :",
+            pick_list(&SerialSettings::BAUD_RATES[..], Some(serial.baud_rate), |rate| {
+                Message::Profile(ProfileMessage::SerialBaudRateChanged(rate))
+            })
+            .padding(8)
+            .into(),
+        ),
+        setting(
+            "Bits de données :",
+            pick_list(&SerialSettings::DATA_BITS[..], Some(serial.data_bits), |bits| {
+                Message::Profile(ProfileMessage::SerialDataBitsChanged(bits))
+            })
+            .padding(8)
+            .into(),
+        ),
+        setting(
+            "Parité :",
+            pick_list(&Parity::ALL[..], Some(serial.parity), |parity| {
+                Message::Profile(ProfileMessage::SerialParityChanged(parity))
+            })
+            .padding(8)
+            .into(),
+        ),
+        setting(
+            "Bits d'arrêt :",
+            pick_list(&SerialSettings::STOP_BITS[..], Some(serial.stop_bits), |bits| {
+                Message::Profile(ProfileMessage::SerialStopBitsChanged(bits))
+            })
+            .padding(8)
+            .into(),
+        ),
+        setting(
+            "Contrôle de flux :",
+            pick_list(&FlowControl::ALL[..], Some(serial.flow_control), |flow| {
+                Message::Profile(ProfileMessage::SerialFlowControlChanged(flow))
+            })
+            .padding(8)
+            .into(),
+        ),
+    ]
+    .spacing(8)
+    .into()
+}
+
+// automatic reconnection when the session is lost
+fn reconnect_fields<'a>(settings: &ReconnectSettings, colors: TerminalColors) -> Element<'a, Message> {
+    row![
+        checkbox("Reconnexion automatique", settings.auto)
+            .on_toggle(|v| Message::Profile(ProfileMessage::ReconnectAutoToggled(v)))
+            .text_size(13)
+            .width(Length::Fill)
+            .style(move |_, _| checkbox::Style {
+                background: colors.surface.into(),
+                icon_color: colors.accent,
+                border: iced::Border {
+                    color: colors.accent,
+                    width: 1.0,
+                    radius: 3.0.into(),
+                },
+                text_color: Some(colors.text),
+            }),
+        text("Tentatives :").color(colors.text),
+        pick_list(&ReconnectSettings::ATTEMPTS[..], Some(settings.max_attempts), |attempts| {
+            Message::Profile(ProfileMessage::ReconnectAttemptsChanged(attempts))
+        })
+        .padding(8),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+// one line per command typed when the session opens
+fn startup_commands<'a>(commands: &'a [String], colors: TerminalColors) -> Element<'a, Message> {
+    let mut list = column![text("Commandes au démarrage :").color(colors.text)].spacing(5);
+    for (index, command) in commands.iter().enumerate() {
+        list = list.push(
+            row![
+                text_input("Commande", command)
+                    .on_input(move |s| Message::Profile(ProfileMessage::StartupCommandChanged(index, s)))
+                    .padding(8),
+                button(text("✕"))
+                    .on_press(Message::Profile(ProfileMessage::StartupCommandRemoved(index)))
+                    .style(move |_, status| theme::button_style(colors, status, theme::ButtonVariant::Secondary)),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center),
+        );
+    }
+    list.push(
+        button(text("+ Ajouter une commande").size(13))
+            .on_press(Message::Profile(ProfileMessage::StartupCommandAdded))
+            .style(move |_, status| theme::button_style(colors, status, theme::ButtonVariant::Secondary)),
+    )
+    .into()
+}
+
+// "⚠️ Port invalide : 0" under a field filled in wrong, nothing while it is empty
+fn field_error<T>(value: &str, validate: fn(&str) -> Result<T, String>) -> Option<String> {
+    if value.is_empty() {
+        return None;
+    }
+    validate(value).err().map(|e| format!("⚠️ {}", e))
+}
+
+// "Connecté 3 fois, dernière connexion le 19/10 14:32"
+fn usage_summary(profile: &crate::models::Profile) -> String {
+    match profile.last_connected {
+        Some(timestamp) => format!(
+            "Connecté {} fois, dernière connexion le {}",
+            profile.connection_count,
+            super::search_table::format_timestamp(timestamp)
+        ),
+        None => "Jamais connecté".into(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn render_input_with_label<'a>(
     label: &'a str,
     value: &'a str,
     id: text_input::Id, // ID for focus management
     colors: TerminalColors,
-    helper_text: Option<&'a str>,
+    helper_text: Option<String>,
     is_secure: bool,
     msg: impl Fn(String) -> Message + 'a,
     on_submit_message: Option<Message>,
@@ -108,7 +295,7 @@ fn render_input_with_label<'a>(
     let mut col = column![
         // label
         text(label).size(13).style(move |_| text::Style {
-            color: Some(colors.text.into())
+            color: Some(colors.text)
         }),
         // input
         text_input(label, value)
@@ -125,7 +312,7 @@ fn render_input_with_label<'a>(
     // optional text helper below the input (e.g., for password warning)
     if let Some(help) = helper_text {
         col = col.push(text(help).size(11).style(move |_| text::Style {
-            color: Some(colors.prompt.into()),
+            color: Some(colors.prompt),
         }));
     }
 
@@ -194,13 +381,272 @@ pub fn terminal_count_selector<'a>(
 }
 */
 
+// keepalives, timeouts and socket options (PuTTY "Connection" page)
+pub fn network_form<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let settings = &app.current_profile.network;
+    let toggle = |label: &'a str, value: bool, msg: fn(bool) -> ProfileMessage| {
+        checkbox(label, value)
+            .on_toggle(move |v| Message::Profile(msg(v)))
+            .text_size(13)
+            .style(move |_, _| checkbox::Style {
+                background: colors.surface.into(),
+                icon_color: colors.accent,
+                border: iced::Border {
+                    color: colors.accent,
+                    width: 1.0,
+                    radius: 3.0.into(),
+                },
+                text_color: Some(colors.text),
+            })
+    };
+
+    column![
+        text("RÉSEAU")
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        row![
+            number_field(
+                "Keepalive SSH toutes les (s)",
+                settings.keepalive_interval,
+                "Sans nouvelles du serveur, 0 : jamais",
+                colors,
+                ProfileMessage::InputKeepaliveInterval,
+            ),
+            number_field(
+                "Keepalives sans réponse",
+                settings.keepalive_max as u64,
+                "Avant de couper la connexion, 0 : illimité",
+                colors,
+                ProfileMessage::InputKeepaliveMax,
+            ),
+        ]
+        .spacing(10),
+        row![
+            number_field(
+                "Inactivité maximale (s)",
+                settings.inactivity_timeout,
+                "Sans aucun échange, 0 : jamais",
+                colors,
+                ProfileMessage::InputInactivityTimeout,
+            ),
+            number_field(
+                "Délai de connexion (s)",
+                settings.connect_timeout,
+                "0 : celui du système",
+                colors,
+                ProfileMessage::InputConnectTimeout,
+            ),
+        ]
+        .spacing(10),
+        toggle(
+            "TCP_NODELAY : envoyer chaque frappe immédiatement",
+            settings.tcp_nodelay,
+            ProfileMessage::TcpNodelayToggled,
+        ),
+        toggle(
+            "SO_KEEPALIVE : sondes TCP du système sur les connexions inactives",
+            settings.tcp_keepalive,
+            ProfileMessage::TcpKeepaliveToggled,
+        ),
+        text("La latence mesurée par les keepalives SSH s'affiche dans la barre d'état du terminal.")
+            .size(11)
+            .color(colors.prompt),
+        imported_settings(&app.current_profile, colors),
+    ]
+    .spacing(15)
+    .into()
+}
+
+// key, forwardings and proxy read by an import: shown, not applied yet
+fn imported_settings<'a>(profile: &crate::models::Profile, colors: TerminalColors) -> Element<'a, Message> {
+    let line = |label: &str, value: String| {
+        row![
+            text(format!("{} :", label)).size(13).width(Length::Fixed(110.0)).color(colors.text),
+            text(value).size(13).color(colors.text),
+        ]
+        .spacing(10)
+    };
+    let mut list = column![
+        text("CLÉ, REDIRECTIONS ET PROXY").size(12).color(colors.accent),
+        line("Clé privée", profile.identity_file.clone().unwrap_or_else(|| "aucune".into())),
+        line("Proxy", profile.proxy.as_ref().map_or_else(|| "aucun".into(), ToString::to_string)),
+    ]
+    .spacing(5);
+    if profile.forwards.is_empty() {
+        list = list.push(line("Redirections", "aucune".into()));
+    }
+    for forward in &profile.forwards {
+        list = list.push(line("Redirection", forward.to_string()));
+    }
+    list.push(
+        text("Repris de l'import, pas encore appliqués par RustTy : la connexion est directe, par mot de passe.")
+            .size(11)
+            .color(colors.prompt),
+    )
+    .into()
+}
+
+// SSH algorithms offered, in order, and the presets (PuTTY "SSH > Kex" and "Cipher" pages)
+pub fn advanced_form<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let settings = &app.current_profile.algorithms;
+    let current = AlgorithmPreset::matching(settings);
+    let mut presets = row![text("Préréglage :").color(colors.text)];
+    for preset in AlgorithmPreset::ALL {
+        let variant = if current == Some(preset) {
+            theme::ButtonVariant::Primary
+        } else {
+            theme::ButtonVariant::Secondary
+        };
+        presets = presets.push(
+            button(text(preset.to_string()).size(13))
+                .on_press(Message::Profile(ProfileMessage::AlgorithmPresetChosen(preset)))
+                .style(move |_, status| theme::button_style(colors, status, variant)),
+        );
+    }
+    let mut families = column![].spacing(20);
+    for kind in AlgorithmKind::ALL {
+        families = families.push(algorithm_list(settings.list(kind), kind, colors));
+    }
+
+    column![
+        text("ALGORITHMES SSH")
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        presets.spacing(10).align_y(Alignment::Center),
+        text("Les premiers de chaque liste sont préférés ; les algorithmes négociés s'affichent dans la barre d'état du terminal.")
+            .size(11)
+            .color(colors.prompt),
+        scrollable(families).height(Length::Fill),
+    ]
+    .spacing(15)
+    .into()
+}
+
+// allowed algorithms of a family in order, then the other ones this build supports
+fn algorithm_list<'a>(allowed: &'a [String], kind: AlgorithmKind, colors: TerminalColors) -> Element<'a, Message> {
+    let secondary = move |_: &iced::Theme, status| theme::button_style(colors, status, theme::ButtonVariant::Secondary);
+    let mut list = column![text(kind.to_string()).size(13).color(colors.accent)].spacing(5);
+    for (index, name) in allowed.iter().enumerate() {
+        list = list.push(
+            row![
+                text(format!("{}. {}", index + 1, name))
+                    .size(13)
+                    .font(Font::MONOSPACE)
+                    .color(colors.text)
+                    .width(Length::Fill),
+                button(text("↑").size(12))
+                    .on_press_maybe((index > 0).then(|| Message::Profile(ProfileMessage::AlgorithmRaised(kind, index))))
+                    .style(secondary),
+                button(text("✕").size(12))
+                    .on_press_maybe(
+                        (allowed.len() > 1)
+                            .then(|| Message::Profile(ProfileMessage::AlgorithmToggled(kind, name.clone()))),
+                    )
+                    .style(secondary),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center),
+        );
+    }
+    for name in crate::algorithms::supported(kind).into_iter().filter(|n| !allowed.iter().any(|a| a == n)) {
+        list = list.push(
+            button(text(format!("+ {}", name)).size(12).font(Font::MONOSPACE))
+                .on_press(Message::Profile(ProfileMessage::AlgorithmToggled(kind, name.to_string())))
+                .style(secondary),
+        );
+    }
+    list.into()
+}
+
+// field of the network page, in seconds or a count
+fn number_field<'a>(
+    label: &'a str,
+    value: u64,
+    hint: &'a str,
+    colors: TerminalColors,
+    msg: fn(String) -> ProfileMessage,
+) -> Element<'a, Message> {
+    column![
+        text(label).size(13).color(colors.text),
+        text_input("0", &value.to_string())
+            .on_input(move |s| Message::Profile(msg(s)))
+            .padding(10),
+        text(hint).size(11).color(colors.prompt),
+    ]
+    .spacing(5)
+    .width(Length::Fill)
+    .into()
+}
+
+// session logging form (PuTTY "Logging" page)
+pub fn logging_form<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let settings = &app.current_profile.logging;
+
+    column![
+        text("JOURNAL DE SESSION")
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        row![
+            text("Contenu du journal :")
+                .width(Length::Fill)
+                .color(colors.text),
+            pick_list(&LogMode::ALL[..], Some(settings.mode), |mode| {
+                Message::Profile(ProfileMessage::LogModeChanged(mode))
+            })
+            .padding(8),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        render_input_with_label(
+            "Fichier du journal",
+            &settings.file_template,
+            text_input::Id::new("log_file_input"),
+            colors,
+            Some(format!(
+                "&H hôte, &P port, &Y année, &M mois, &D jour, &T heure (relatif à {})",
+                crate::session_log::session_log_dir().display()
+            )),
+            false,
+            |s| Message::Profile(ProfileMessage::InputLogFile(s)),
+            None,
+        ),
+        checkbox("Ajouter à la fin si le fichier existe (sinon il est écrasé)", settings.append)
+            .on_toggle(|v| Message::Profile(ProfileMessage::LogAppendToggled(v)))
+            .text_size(13)
+            .style(move |_, _| checkbox::Style {
+                background: colors.surface.into(),
+                icon_color: colors.accent,
+                border: iced::Border {
+                    color: colors.accent,
+                    width: 1.0,
+                    radius: 3.0.into(),
+                },
+                text_color: Some(colors.text),
+            }),
+    ]
+    .spacing(15)
+    .into()
+}
+
 pub fn theme_form<'a>(app: &MyApp, colors: TerminalColors) -> Element<'a, Message> {
     let mut themes_list = column![].spacing(10);
 
     let mut row_items = row![].spacing(10);
 
     for (i, theme) in ThemeChoice::ALL.iter().enumerate() {
-        let is_selected = app.current_profile.theme == *theme;
+        let is_selected = app.display_theme == *theme;
         let theme_colors = theme.get_colors();
 
         let theme_button = button(
@@ -264,6 +710,29 @@ pub fn theme_form<'a>(app: &MyApp, colors: TerminalColors) -> Element<'a, Messag
         }
     }
 
+    // the folder (or one of its parents) has a default theme
+    let folder_theme = crate::folders::defaults_for(&app.store.settings, &app.current_profile.group).theme;
+    let inherit: Element<'a, Message> = match folder_theme {
+        Some(theme) => checkbox(
+            format!("Suivre le thème du dossier {} ({})", app.current_profile.group, theme),
+            app.current_profile.inherit_theme,
+        )
+        .on_toggle(|v| Message::Profile(ProfileMessage::InheritThemeToggled(v)))
+        .text_size(13)
+        .style(move |_, _| checkbox::Style {
+            background: colors.surface.into(),
+            icon_color: colors.accent,
+            border: iced::Border {
+                color: colors.accent,
+                width: 1.0,
+                radius: 3.0.into(),
+            },
+            text_color: Some(colors.text),
+        })
+        .into(),
+        None => column![].into(),
+    };
+
     column![
         text("Personnalisation de l'interface")
             .size(20)
@@ -278,6 +747,7 @@ pub fn theme_form<'a>(app: &MyApp, colors: TerminalColors) -> Element<'a, Messag
         text("Vous pouvez choisir un thème différent pour chaque profil.")
             .size(14)
             .color(colors.text),
+        inherit,
         scrollable(themes_list).height(Length::Fill),
     ]
     .spacing(20)
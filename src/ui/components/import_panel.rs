@@ -0,0 +1,247 @@
+use crate::import::{ImportCandidate, ImportSource, Resolution, openssh};
+use crate::messages::{ImportMessage, Message};
+use crate::ui::theme;
+use crate::ui::{MyApp, theme::TerminalColors};
+use iced::font::Weight;
+use iced::widget::{button, checkbox, column, container, pick_list, row, scrollable, text, text_input};
+use iced::{Alignment, Element, Font, Length};
+
+// profiles read from PuTTY, OpenSSH or a bundle, previewed before being added;
+// profiles written back as an ssh_config snippet or a bundle
+pub fn render<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let can_analyse = !app.import_busy && !app.import_path.trim().is_empty();
+    let placeholder = match app.import_source {
+        ImportSource::Putty => "Fichier .reg exporté de PuTTY",
+        ImportSource::OpenSsh => "Fichier de configuration OpenSSH",
+        ImportSource::Bundle => "Bundle exporté par RustTy (.json ou .toml)",
+    };
+    let file_bar = row![
+        pick_list(&ImportSource::ALL[..], Some(app.import_source), |source| {
+            Message::Import(ImportMessage::SourceChanged(source))
+        })
+        .padding(10),
+        text_input(placeholder, &app.import_path)
+            .on_input(|v| Message::Import(ImportMessage::InputPath(v)))
+            .on_submit_maybe(can_analyse.then_some(Message::Import(ImportMessage::Analyse)))
+            .padding(10)
+            .width(Length::Fill)
+            .style(move |_, status| theme::input_style(colors, status)),
+        button(text(if app.import_busy { "Analyse..." } else { "Analyser" }).center())
+            .on_press_maybe(can_analyse.then_some(Message::Import(ImportMessage::Analyse)))
+            .padding(10)
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    let status = match &app.import_status {
+        Some(Ok(summary)) => text(format!("Import terminé : {}", summary)).color(colors.prompt),
+        Some(Err(e)) => text(format!("⚠️ {}", e)).color(colors.accent),
+        None => text(match app.import_source {
+            ImportSource::Putty => {
+                "Exporter les sessions : reg export \"HKCU\\Software\\SimonTatham\\PuTTY\\Sessions\" putty.reg"
+            }
+            ImportSource::OpenSsh => "Les blocs Host * servent de valeurs par défaut, les directives non reprises sont signalées",
+            ImportSource::Bundle => "Les profils sont rapprochés par identifiant, puis par nom et hôte",
+        })
+        .color(colors.prompt),
+    };
+
+    let mut content = column![
+        text("IMPORTER DES PROFILS")
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        file_bar,
+        status.size(12),
+    ]
+    .spacing(15);
+
+    if !app.import_candidates.is_empty() {
+        let all_selected = app.import_candidates.iter().all(|c| c.selected);
+        let selected = app.import_candidates.iter().filter(|c| c.selected).count();
+
+        let mut table = column![].spacing(1);
+        for (i, candidate) in app.import_candidates.iter().enumerate() {
+            let zebra_color = if i % 2 == 0 { colors.surface } else { colors.bg };
+            table = table.push(
+                container(candidate_row(i, candidate, colors))
+                    .padding(8)
+                    .width(Length::Fill)
+                    .style(move |_| container::Style {
+                        background: Some(zebra_color.into()),
+                        ..Default::default()
+                    }),
+            );
+        }
+
+        content = content
+            .push(
+                row![
+                    styled_checkbox("Tout sélectionner", all_selected, colors)
+                        .on_toggle(|v| Message::Import(ImportMessage::ToggleAll(v))),
+                    text(format!("{} / {} sélectionnée(s)", selected, app.import_candidates.len()))
+                        .size(12)
+                        .width(Length::Fill)
+                        .color(colors.text),
+                    button(text("Importer la sélection").center())
+                        .on_press_maybe((selected > 0).then_some(Message::Import(ImportMessage::Apply)))
+                        .padding(10)
+                        .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary)),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push(scrollable(table).height(Length::Fill));
+    }
+
+    content.push(export_section(app, colors)).into()
+}
+
+// profiles picked by group or one by one, written as an ssh_config snippet or as a bundle
+fn export_section<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let mut groups: Vec<&str> = app.profiles.iter().map(|p| p.group.as_str()).collect();
+    groups.sort_unstable();
+    groups.dedup();
+
+    let mut list = column![].spacing(4);
+    for group in groups {
+        let members: Vec<_> = app.profiles.iter().filter(|p| p.group == group).collect();
+        let all_in = members.iter().all(|p| app.export_selection.contains(&p.id));
+        let name = group.to_string();
+        list = list.push(
+            styled_checkbox(group, all_in, colors)
+                .on_toggle(move |v| Message::Import(ImportMessage::ExportGroup(name.clone(), v))),
+        );
+        for profile in members {
+            let id = profile.id;
+            list = list.push(
+                container(
+                    styled_checkbox(&profile.name, app.export_selection.contains(&id), colors)
+                        .on_toggle(move |v| Message::Import(ImportMessage::ExportToggled(id, v))),
+                )
+                .padding(iced::Padding::default().left(20)),
+            );
+        }
+    }
+
+    let has_selection = !app.export_selection.is_empty();
+    let action = |label, msg| {
+        button(text(label).size(12))
+            .on_press_maybe(has_selection.then_some(Message::Import(msg)))
+            .padding([4, 10])
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary))
+    };
+    let status = match &app.export_status {
+        Some(Ok(path)) => text(format!("Écrit : {}", path.display())).color(colors.prompt),
+        Some(Err(e)) => text(format!("⚠️ {}", e)).color(colors.accent),
+        None => text(format!(
+            "ssh_config : ajoutez « Include {} » en tête de ~/.ssh/config. Les bundles ne contiennent aucun mot de passe.",
+            openssh::default_export_path().display()
+        ))
+        .color(colors.prompt),
+    };
+
+    column![
+        text("EXPORTER")
+            .size(14)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        row![
+            styled_checkbox(
+                "Tous les profils",
+                !app.profiles.is_empty() && app.export_selection.len() == app.profiles.len(),
+                colors
+            )
+            .on_toggle(|v| Message::Import(ImportMessage::ExportAll(v))),
+            action("Copier ssh_config", ImportMessage::CopySshConfig),
+            action("Enregistrer ssh_config", ImportMessage::SaveSshConfig),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        scrollable(list).height(Length::Fixed(140.0)),
+        row![
+            text_input("Fichier du bundle (.json ou .toml)", &app.bundle_path)
+                .on_input(|v| Message::Import(ImportMessage::InputBundlePath(v)))
+                .padding(6)
+                .size(12)
+                .width(Length::Fill)
+                .style(move |_, status| theme::input_style(colors, status)),
+            action("Exporter le bundle", ImportMessage::SaveBundle),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        status.size(12),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// checkbox, name, destination and what to do with a duplicate; warnings below
+fn candidate_row<'a>(row_index: usize, candidate: &'a ImportCandidate, colors: TerminalColors) -> Element<'a, Message> {
+    let profile = &candidate.profile;
+    let destination = if profile.username.is_empty() {
+        format!("{}:{}", profile.ip, profile.port)
+    } else {
+        format!("{}@{}:{}", profile.username, profile.ip, profile.port)
+    };
+
+    let duplicate: Element<_> = match candidate.duplicate_of {
+        Some(_) => row![
+            text("Existe déjà :").size(12).color(colors.accent),
+            pick_list(&Resolution::ALL[..], Some(candidate.resolution), move |resolution| {
+                Message::Import(ImportMessage::ResolutionChanged(row_index, resolution))
+            })
+            .text_size(12)
+            .padding(4),
+        ]
+        .spacing(6)
+        .align_y(Alignment::Center)
+        .into(),
+        None => text("Nouveau").size(12).color(colors.prompt).into(),
+    };
+
+    let mut block = column![
+        row![
+            styled_checkbox("", candidate.selected, colors)
+                .on_toggle(move |v| Message::Import(ImportMessage::Toggle(row_index, v))),
+            text(&profile.name)
+                .width(Length::FillPortion(2))
+                .font(Font {
+                    weight: Weight::Bold,
+                    ..Font::default()
+                })
+                .color(colors.text),
+            text(destination).width(Length::FillPortion(3)).color(colors.text),
+            container(duplicate).width(Length::FillPortion(2)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(4);
+
+    // key, forwardings and proxy are listed there too
+    for warning in &candidate.warnings {
+        block = block.push(text(format!("⚠️ {}", warning)).size(11).color(colors.accent));
+    }
+    block.into()
+}
+
+fn styled_checkbox<'a>(label: &'a str, checked: bool, colors: TerminalColors) -> iced::widget::Checkbox<'a, Message> {
+    checkbox(label, checked).text_size(12).style(move |_, _| checkbox::Style {
+        background: colors.surface.into(),
+        icon_color: colors.accent,
+        border: iced::Border {
+            color: colors.accent,
+            width: 1.0,
+            radius: 3.0.into(),
+        },
+        text_color: Some(colors.text),
+    })
+}
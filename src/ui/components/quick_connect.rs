@@ -0,0 +1,67 @@
+use crate::messages::{Message, QuickConnectMessage};
+use crate::ui::theme;
+use crate::ui::{MyApp, theme::TerminalColors};
+use iced::widget::{button, column, row, text, text_input};
+use iced::{Alignment, Element, Length};
+
+// user@host:port typed in, connected without a profile; saving it is offered once connected
+pub fn render<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let can_connect = !app.quick_input.trim().is_empty();
+    let connect = can_connect.then_some(Message::QuickConnect(QuickConnectMessage::Connect));
+
+    let bar = row![
+        text_input("⚡ Connexion rapide : utilisateur@hôte:port, [ipv6]:port ou ssh://…", &app.quick_input)
+            .on_input(|v| Message::QuickConnect(QuickConnectMessage::Input(v)))
+            .on_submit_maybe(connect.clone())
+            .padding(10)
+            .width(Length::Fill)
+            .style(move |_, status| theme::input_style(colors, status)),
+        text_input("Mot de passe", &app.quick_password)
+            .on_input(|v| Message::QuickConnect(QuickConnectMessage::InputPass(v)))
+            .on_submit_maybe(connect.clone())
+            .secure(true)
+            .padding(10)
+            .width(Length::Fixed(170.0))
+            .style(move |_, status| theme::input_style(colors, status)),
+        button(text("Connecter").center())
+            .on_press_maybe(connect)
+            .padding(10)
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary)),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    let status: Option<Element<_>> = match (&app.quick_error, &app.quick_profile) {
+        (Some(error), _) => Some(text(format!("⚠️ {}", error)).size(12).color(colors.accent).into()),
+        (None, Some(profile)) if app.quick_connected => Some(
+            row![
+                text(format!(
+                    "Connecté à {}@{}:{}",
+                    profile.username, profile.ip, profile.port
+                ))
+                .size(12)
+                .color(colors.prompt),
+                action("Enregistrer comme profil", QuickConnectMessage::SaveAsProfile, colors),
+                action("✕", QuickConnectMessage::Dismiss, colors),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into(),
+        ),
+        (None, Some(_)) => Some(text("Connexion...").size(12).color(colors.prompt).into()),
+        (None, None) => None,
+    };
+
+    match status {
+        Some(status) => column![bar, status].spacing(6).into(),
+        None => bar.into(),
+    }
+}
+
+fn action<'a>(label: &'a str, msg: QuickConnectMessage, colors: TerminalColors) -> Element<'a, Message> {
+    button(text(label).size(12))
+        .on_press(Message::QuickConnect(msg))
+        .padding([3, 10])
+        .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary))
+        .into()
+}
@@ -0,0 +1,76 @@
+use crate::messages::{Message, PlaybackMessage};
+use crate::recording::recordings_dir;
+use crate::ui::theme;
+use crate::ui::{MyApp, theme::TerminalColors};
+use iced::font::Weight;
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Font, Length};
+
+// recorded sessions (asciicast), each one can be replayed in its own window
+pub fn render<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let mut list = column![].spacing(1);
+
+    for (i, path) in app.recordings.iter().enumerate() {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let zebra_color = if i % 2 == 0 { colors.surface } else { colors.bg };
+
+        list = list.push(
+            container(
+                row![
+                    text(name).width(Length::Fill).color(colors.text),
+                    button(text("▶ Lire").size(12))
+                        .on_press(Message::Playback(PlaybackMessage::Open(path.clone())))
+                        .padding([4, 10])
+                        .style(move |_, s| {
+                            theme::button_style(colors, s, theme::ButtonVariant::Primary)
+                        }),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .padding(8)
+            .style(move |_| container::Style {
+                background: Some(zebra_color.into()),
+                ..Default::default()
+            }),
+        );
+    }
+
+    if app.recordings.is_empty() {
+        list = list.push(
+            text(format!(
+                "Aucun enregistrement dans « {} ». Utilisez le bouton ⏺ REC d'un terminal.",
+                recordings_dir().display()
+            ))
+            .size(13)
+            .color(colors.prompt),
+        );
+    }
+
+    column![
+        row![
+            text("ENREGISTREMENTS DE SESSION")
+                .size(14)
+                .width(Length::Fill)
+                .font(Font {
+                    weight: Weight::Bold,
+                    ..Font::default()
+                })
+                .color(colors.accent),
+            button(text("Actualiser").size(12))
+                .on_press(Message::Playback(PlaybackMessage::RefreshList))
+                .padding([4, 10])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+        ]
+        .align_y(Alignment::Center),
+        text("Lecture hors ligne au format asciicast v2, aucune connexion nécessaire.")
+            .size(13)
+            .color(colors.text),
+        scrollable(list).height(Length::Fill),
+    ]
+    .spacing(15)
+    .into()
+}
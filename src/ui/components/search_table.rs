@@ -1,99 +1,384 @@
-use crate::messages::{Message, ProfileMessage};
+use std::collections::HashSet;
+
+use crate::folders::{self, FolderNode};
+use crate::fuzzy::{self, ProfileMatch};
+use crate::messages::{FolderMessage, Message, ProfileMessage};
+use crate::models::{self, Profile, ProfileView, SortColumn};
+use crate::ui::constants::ID_PROFILE_LIST;
 use crate::ui::theme;
 use crate::ui::{MyApp, theme::TerminalColors};
-use iced::widget::{button, column, container, row, scrollable, text, text_input};
-use iced::{Element, Font, Length, font};
+use chrono::{Datelike, TimeZone};
+use iced::widget::{button, column, container, mouse_area, row, scrollable, text, text_input, Row};
+use iced::{Alignment, Element, Font, Length, Padding, font, mouse};
 
 pub fn header<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    // views, then one chip per tag in use
+    let mut tags: Vec<&str> = app.profiles.iter().flat_map(|p| p.tags.iter().map(String::as_str)).collect();
+    tags.sort_unstable();
+    tags.dedup();
+
+    let mut filters = Row::new().spacing(6).align_y(Alignment::Center);
+    for view in ProfileView::ALL {
+        filters = filters.push(chip(view.to_string(), app.profile_view == view, ProfileMessage::ViewChanged(view), colors));
+    }
+    if !tags.is_empty() {
+        filters = filters.push(text("│").color(colors.prompt));
+    }
+    for tag in tags {
+        filters = filters.push(chip(
+            format!("#{}", tag),
+            app.tag_filter.contains(tag),
+            ProfileMessage::TagFilterToggled(tag.to_string()),
+            colors,
+        ));
+    }
+
+    // a click sorts by the column, the arrow shows the direction
+    let sort_header = |label: &str, column: SortColumn, portion: u16| {
+        let arrow = match app.sort_column {
+            Some(sorted) if sorted == column && app.sort_descending => " ▼",
+            Some(sorted) if sorted == column => " ▲",
+            _ => "",
+        };
+        button(bold_text(format!("{}{}", label, arrow)).color(colors.text))
+            .on_press(Message::Profile(ProfileMessage::SortBy(column)))
+            .padding(0)
+            .width(Length::FillPortion(portion))
+            .style(|_, _| button::Style::default())
+    };
+    let mut columns = row![
+        sort_header("GROUPE", SortColumn::Group, 2),
+        sort_header("NOM", SortColumn::Name, 3),
+        sort_header("UTILISATEUR", SortColumn::Username, 2),
+        sort_header("ADRESSE IP", SortColumn::Ip, 2),
+    ]
+    .spacing(10);
+    if app.profile_view == ProfileView::Recent {
+        columns = columns.push(bold_text("DERNIÈRE CONNEXION").width(Length::FillPortion(2)));
+    }
+
     column![
+        filters.wrap(),
 
-        text_input("🔍 Recherche rapide sur nom, groupe, ip, utilisateur", &app.search_query)
+        text_input("🔍 Recherche floue sur nom, dossier, ip, utilisateur, tag — ↑↓ puis Entrée pour se connecter", &app.search_query)
             .on_input(|v| Message::Profile(ProfileMessage::SearchChanged(v)))
+            .on_submit(Message::Profile(ProfileMessage::ConnectSelected))
             .padding(10)
-            .style(move |theme: &iced::Theme, status| {
+            .style(move |_theme: &iced::Theme, status| {
                 theme::input_style(colors, status)
             }),
 
-        container(
-            row![
-                bold_text("GROUPE").width(Length::FillPortion(1)),
-                bold_text("NOM").width(Length::FillPortion(2)),
-                bold_text("UTILISATEUR").width(Length::FillPortion(1)),
-                bold_text("ADRESSE IP").width(Length::FillPortion(2)),
-            ]
-            .spacing(10)
-        )
+        container(columns)
         .padding(10)
         .style(move |_theme| {
             container::Style {
                 background: Some(colors.bg.into()),
-                text_color: Some(colors.text.into()),
+                text_color: Some(colors.text),
                 ..Default::default()
             }
         })
     ]
-    .spacing(20) 
+    .spacing(12)
     .into()
 }
 
+/// Rows of the table: the folder tree, or a flat list while searching, sorting or in the Recent view
+pub enum Listing {
+    /// Every folder is open when `expanded` (a filter is active)
+    Tree { root: FolderNode, expanded: bool },
+    /// Best matches first, or in the order of the sorted column
+    Flat(Vec<(usize, Option<ProfileMatch>)>),
+}
+
+impl Listing {
+    pub fn new(app: &MyApp) -> Self {
+        let query = app.search_query.trim();
+        let keep = |profile: &Profile| {
+            app.tag_filter.iter().all(|tag| profile.tags.contains(tag))
+                && (app.profile_view != ProfileView::Favourites || profile.favourite)
+        };
+
+        if query.is_empty() && app.sort_column.is_none() && app.profile_view != ProfileView::Recent {
+            return Listing::Tree {
+                root: folders::tree(&app.profiles, keep),
+                expanded: !app.tag_filter.is_empty() || app.profile_view != ProfileView::All,
+            };
+        }
+
+        let candidates = match app.profile_view {
+            ProfileView::Recent => models::recent(&app.profiles, usize::MAX),
+            _ => (0..app.profiles.len()).collect(),
+        };
+        let mut entries: Vec<(usize, Option<ProfileMatch>)> = candidates
+            .into_iter()
+            .filter(|&index| keep(&app.profiles[index]))
+            .filter_map(|index| {
+                if query.is_empty() {
+                    Some((index, None))
+                } else {
+                    fuzzy::match_profile(&app.profiles[index], query).map(|found| (index, Some(found)))
+                }
+            })
+            .collect();
+        if app.profile_view == ProfileView::Recent {
+            entries.truncate(ProfileView::RECENT_LIMIT);
+        }
+
+        // stable sorts: the clicked column first, then the best matches
+        entries.sort_by_key(|(_, found)| std::cmp::Reverse(found.as_ref().map_or(0, |f| f.score)));
+        if let Some(column) = app.sort_column {
+            entries.sort_by(|(a, _), (b, _)| {
+                let order = column.compare(&app.profiles[*a], &app.profiles[*b]);
+                if app.sort_descending { order.reverse() } else { order }
+            });
+        }
+        Listing::Flat(entries)
+    }
+
+    /// Profiles in the order of their rows, for the arrow keys
+    pub fn order(&self, collapsed: &HashSet<String>) -> Vec<usize> {
+        fn walk(node: &FolderNode, expanded: bool, collapsed: &HashSet<String>, order: &mut Vec<usize>) {
+            if !expanded && collapsed.contains(&node.path) {
+                return;
+            }
+            for sub_folder in &node.folders {
+                walk(sub_folder, expanded, collapsed, order);
+            }
+            order.extend(&node.profiles);
+        }
+
+        match self {
+            Listing::Tree { root, expanded } => {
+                let mut order = Vec::new();
+                for folder in &root.folders {
+                    walk(folder, *expanded, collapsed, &mut order);
+                }
+                order
+            }
+            Listing::Flat(entries) => entries.iter().map(|(index, _)| *index).collect(),
+        }
+    }
+}
+
 // <'a> means that provided MyApp reference 
 // must live at least as long as the produced UI element.
+// Profiles are shown as a folder tree; drag a profile onto a folder to move it.
+// Searching, sorting and the Recent view show a flat list instead.
 pub fn content<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
-    let mut content = column![].spacing(1);
-    let query = app.search_query.to_lowercase();
-
-    for (i, profile) in app.profiles.iter().enumerate() {
-        let is_match = query.is_empty() 
-            || profile.name.to_lowercase().contains(&query) 
-            || profile.group.to_lowercase().contains(&query) 
-            || profile.ip.contains(&query)          
-            || profile.username.to_lowercase().contains(&query); 
-        if is_match {
-            let is_selected = app.selected_profile_id == Some(profile.id);
-            let zebra_color = if i % 2 == 0 {
-                colors.surface
-            } else {
-                colors.bg
-            };
-            content = content.push(
-                button(
-                    container(
-                        row![
-                            text(&profile.group).width(Length::FillPortion(1)),
-                            text(&profile.name).width(Length::FillPortion(2)),
-                            text(&profile.username).width(Length::FillPortion(1)),
-                            text(format!("{}:{}", profile.ip, profile.port))
-                                .width(Length::FillPortion(2)),
-                        ]
-                        .spacing(10),
-                    )
-                    .padding(8),
-                )
+    let mut rows = Vec::new();
+    match Listing::new(app) {
+        Listing::Tree { root, expanded } => {
+            for folder in &root.folders {
+                push_folder(&mut rows, app, folder, 0, expanded, colors);
+            }
+        }
+        Listing::Flat(entries) => {
+            for (i, (index, found)) in entries.iter().enumerate() {
+                rows.push(profile_row(app, *index, found.as_ref(), i, None, colors));
+            }
+        }
+    }
+    if rows.is_empty() {
+        let empty = match app.profile_view {
+            _ if !app.search_query.trim().is_empty() => "Aucun profil ne correspond à la recherche",
+            ProfileView::Favourites => "Aucun favori : cliquez sur ☆ devant un profil",
+            ProfileView::Recent => "Aucune connexion enregistrée",
+            ProfileView::All => "Aucun profil",
+        };
+        rows.push(container(text(empty).size(13).color(colors.prompt)).padding(8).into());
+    }
+    scrollable(column(rows).spacing(1))
+        .id(scrollable::Id::new(ID_PROFILE_LIST))
+        .height(Length::Fixed(260.0))
+        .into()
+}
+
+fn push_folder<'a>(
+    rows: &mut Vec<Element<'a, Message>>,
+    app: &'a MyApp,
+    node: &FolderNode,
+    depth: u16,
+    searching: bool,
+    colors: TerminalColors,
+) {
+    let expanded = searching || !app.collapsed_folders.contains(&node.path);
+    let is_selected = app.selected_folder.as_ref() == Some(&node.path);
+    let is_target = app.dragging.is_some() && app.drop_target.as_ref() == Some(&node.path);
+    let defaults = app.store.settings.folders.contains_key(&node.path);
+
+    let label = format!(
+        "{} 📁 {} ({}){}",
+        if expanded { "▾" } else { "▸" },
+        folders::name(&node.path),
+        node.count(),
+        if defaults { " ⚙" } else { "" }
+    );
+    let path = node.path.clone();
+    rows.push(
+        mouse_area(
+            container(
+                text(label)
+                    .font(Font {
+                        weight: font::Weight::Bold,
+                        ..Default::default()
+                    })
+                    .color(if is_selected { colors.accent } else { colors.text }),
+            )
+                .padding(Padding::from([6, 8]).left(8.0 + 18.0 * depth as f32))
                 .width(Length::Fill)
-                .on_press(Message::Profile(ProfileMessage::Selected(profile.id)))
-                .style(move |_, status| {
-                    let mut st = theme::button_style(colors, status,theme::ButtonVariant::Secondary);
-                    if is_selected {
-                        st.background = Some(colors.prompt.into());
-                        st.text_color = colors.accent;
-                        st.border.width = 2.0;
-                        st.border.color = colors.accent;
-                    } else {
-                        st.background = Some(zebra_color.into());
-                        st.text_color = colors.text;
-                        st.border.width = 0.0;
-                    }
-                    st
+                .style(move |_| container::Style {
+                    background: Some(if is_target { colors.prompt } else { colors.bg }.into()),
+                    border: iced::Border {
+                        width: if is_target || is_selected { 1.0 } else { 0.0 },
+                        color: colors.accent,
+                        radius: 3.0.into(),
+                    },
+                    ..Default::default()
                 }),
-            );
+        )
+        .on_press(Message::Folder(FolderMessage::Toggle(path.clone())))
+        .on_release(Message::Folder(FolderMessage::DropOn(path.clone())))
+        .on_enter(Message::Folder(FolderMessage::Hover(path.clone())))
+        .on_exit(Message::Folder(FolderMessage::Unhover(path)))
+        .interaction(mouse::Interaction::Pointer)
+        .into(),
+    );
+    if !expanded {
+        return;
+    }
+
+    for sub_folder in &node.folders {
+        push_folder(rows, app, sub_folder, depth + 1, searching, colors);
+    }
+    for (i, &index) in node.profiles.iter().enumerate() {
+        rows.push(profile_row(app, index, None, i, Some(26.0 + 18.0 * depth as f32), colors));
+    }
+}
+
+// star, name, user, address; the folder in the flat list, the last connection in the Recent view.
+// `indent` is the depth in the tree, None in the flat list
+fn profile_row<'a>(
+    app: &'a MyApp,
+    index: usize,
+    found: Option<&ProfileMatch>,
+    i: usize,
+    indent: Option<f32>,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    let profile = &app.profiles[index];
+    let is_selected = app.selected_profile_id == Some(profile.id);
+    let zebra_color = if i.is_multiple_of(2) { colors.surface } else { colors.bg };
+    let matched = |field: fn(&ProfileMatch) -> &Vec<usize>| found.map_or(&[][..], |f| &field(f)[..]);
+
+    let star = button(text(if profile.favourite { "★" } else { "☆" }).color(colors.accent))
+        .on_press(Message::Profile(ProfileMessage::FavouriteToggled(profile.id)))
+        .padding(0)
+        .style(|_, _| button::Style::default());
+    let group: Element<_> = match indent {
+        Some(_) => text("").into(),
+        None => highlighted(&profile.group, matched(|f| &f.group), colors),
+    };
+
+    let mut cells = row![
+        container(group).width(Length::FillPortion(2)),
+        row![star, highlighted(&profile.name, matched(|f| &f.name), colors)]
+            .spacing(6)
+            .width(Length::FillPortion(3)),
+        container(highlighted(&profile.username, matched(|f| &f.username), colors)).width(Length::FillPortion(2)),
+        // the positions of the address are kept since it comes first
+        container(highlighted(&profile.endpoint(), matched(|f| &f.ip), colors))
+            .width(Length::FillPortion(2)),
+    ]
+    .spacing(10);
+    if app.profile_view == ProfileView::Recent {
+        let usage = profile
+            .last_connected
+            .map(|timestamp| format!("{} · {}×", format_timestamp(timestamp), profile.connection_count))
+            .unwrap_or_default();
+        cells = cells.push(text(usage).width(Length::FillPortion(2)));
+    }
+
+    mouse_area(
+        container(cells)
+        .padding(Padding::from(8).left(8.0 + indent.unwrap_or(0.0)))
+        .width(Length::Fill)
+        .style(move |_| {
+            if is_selected {
+                container::Style {
+                    background: Some(colors.prompt.into()),
+                    text_color: Some(colors.accent),
+                    border: iced::Border {
+                        width: 2.0,
+                        color: colors.accent,
+                        radius: 3.0.into(),
+                    },
+                    ..Default::default()
+                }
+            } else {
+                container::Style {
+                    background: Some(zebra_color.into()),
+                    text_color: Some(colors.text),
+                    ..Default::default()
+                }
+            }
+        }),
+    )
+    .on_press(Message::Folder(FolderMessage::Grab(profile.id)))
+    .interaction(if app.dragging.is_some() {
+        mouse::Interaction::Grabbing
+    } else {
+        mouse::Interaction::Pointer
+    })
+    .into()
+}
+
+// matched characters in bold and in the accent colour, one text per run
+fn highlighted<'a>(value: &str, positions: &[usize], colors: TerminalColors) -> Element<'a, Message> {
+    if positions.is_empty() {
+        return text(value.to_string()).into();
+    }
+    let mut runs: Vec<(bool, String)> = Vec::new();
+    for (i, c) in value.chars().enumerate() {
+        let hit = positions.binary_search(&i).is_ok();
+        match runs.last_mut() {
+            Some((run_hit, run)) if *run_hit == hit => run.push(c),
+            _ => runs.push((hit, c.to_string())),
+        }
+    }
+    Row::with_children(runs.into_iter().map(|(hit, run)| {
+        if hit {
+            bold_text(run).color(colors.accent).into()
+        } else {
+            text(run).into()
         }
+    }))
+    .into()
+}
+
+// "19/10 14:32", with the year when it isn't the current one
+pub fn format_timestamp(timestamp: i64) -> String {
+    let Some(time) = chrono::Local.timestamp_opt(timestamp, 0).single() else {
+        return String::new();
+    };
+    if time.year() == chrono::Local::now().year() {
+        time.format("%d/%m %H:%M").to_string()
+    } else {
+        time.format("%d/%m/%Y %H:%M").to_string()
     }
-    scrollable(content).height(Length::Fixed(150.0)).into()
 }
 
+// view or tag toggle above the list
+fn chip<'a>(label: String, active: bool, msg: ProfileMessage, colors: TerminalColors) -> Element<'a, Message> {
+    let variant = if active { theme::ButtonVariant::Primary } else { theme::ButtonVariant::Secondary };
+    button(text(label).size(12))
+        .on_press(Message::Profile(msg))
+        .padding([3, 10])
+        .style(move |_, s| theme::button_style(colors, s, variant))
+        .into()
+}
 
 // helper for bold text in the header
-fn bold_text(content: &str) -> text::Text {
+fn bold_text<'a>(content: impl text::IntoFragment<'a>) -> text::Text<'a> {
     text(content).font(Font {
         weight: font::Weight::Bold,
         ..Default::default()
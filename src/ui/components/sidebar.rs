@@ -1,5 +1,5 @@
 use iced::widget::{button, column, container, text, vertical_space};
-use iced::{Alignment, Border, Element, Length};
+use iced::{Border, Element, Length};
 use crate::messages::{ConfigMessage, Message};
 use crate::ui::components::brand;
 use crate::ui::{ EditSection, theme::{self, TerminalColors}};
@@ -17,11 +17,16 @@ pub fn render<'a>(active_section: EditSection, colors: TerminalColors) -> Elemen
             
             nav_button("Général", EditSection::General, active_section, colors),
             //nav_button("Sécurité", EditSection::Auth, active_section, colors),
-            //nav_button("Réseau", EditSection::Network, active_section, colors),
+            nav_button("Réseau", EditSection::Network, active_section, colors),
             
             vertical_space().height(Length::Fill),
             
-            //nav_button("Avancé", EditSection::Advanced, active_section, colors),
+            nav_button("Avancé", EditSection::Advanced, active_section, colors),
+            nav_button("Commandes", EditSection::Exec, active_section, colors),
+            nav_button("Coffre", EditSection::Vault, active_section, colors),
+            nav_button("Importer", EditSection::Import, active_section, colors),
+            nav_button("Journal", EditSection::Logging, active_section, colors),
+            nav_button("Enregistrements", EditSection::Recordings, active_section, colors),
             nav_button("Thèmes", EditSection::Themes, active_section, colors),
             
            
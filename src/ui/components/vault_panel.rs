@@ -0,0 +1,168 @@
+use crate::messages::{Message, VaultMessage};
+use crate::models::Settings;
+use crate::ui::theme;
+use crate::ui::{MyApp, theme::TerminalColors};
+use crate::vault::Vault;
+use iced::font::Weight;
+use iced::widget::{button, column, pick_list, row, text, text_input};
+use iced::{Alignment, Element, Font, Length};
+
+// encrypted vault: create, unlock, lock and store the secrets of the selected profile
+pub fn render<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    let title = text("COFFRE DE MOTS DE PASSE")
+        .size(14)
+        .font(Font {
+            weight: Weight::Bold,
+            ..Font::default()
+        })
+        .color(colors.accent);
+
+    let body = match &app.vault {
+        Some(vault) => unlocked(app, vault, colors),
+        None if Vault::default_path().exists() => locked(app, colors),
+        None => creation(app, colors),
+    };
+
+    let mut content = column![title, body].spacing(15);
+    if let Some(error) = &app.vault_error {
+        content = content.push(text(format!("⚠️ {}", error)).size(13).color(colors.accent));
+    }
+    content.into()
+}
+
+fn creation<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    column![
+        text("Aucun coffre. Choisissez un mot de passe maître : il chiffre les mots de passe et phrases de passe enregistrés, il ne peut pas être récupéré.")
+            .size(13)
+            .color(colors.text),
+        secure_input("Mot de passe maître", &app.vault_master, colors, VaultMessage::InputMaster, None),
+        secure_input(
+            "Confirmation",
+            &app.vault_confirm,
+            colors,
+            VaultMessage::InputConfirm,
+            Some(VaultMessage::Create)
+        ),
+        action("Créer le coffre", (!app.vault_busy).then_some(VaultMessage::Create), colors),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn locked<'a>(app: &'a MyApp, colors: TerminalColors) -> Element<'a, Message> {
+    column![
+        text("🔒 Coffre verrouillé").size(13).color(colors.text),
+        secure_input(
+            "Mot de passe maître",
+            &app.vault_master,
+            colors,
+            VaultMessage::InputMaster,
+            Some(VaultMessage::Unlock)
+        ),
+        action(
+            if app.vault_busy { "Déverrouillage..." } else { "Déverrouiller" },
+            (!app.vault_busy).then_some(VaultMessage::Unlock),
+            colors
+        ),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn unlocked<'a>(app: &'a MyApp, vault: &'a Vault, colors: TerminalColors) -> Element<'a, Message> {
+    let profile = app
+        .selected_profile_id
+        .and_then(|id| app.profiles.iter().find(|p| p.id == id));
+
+    let selected: Element<_> = match profile {
+        Some(profile) => column![
+            text(format!(
+                "Profil « {} » : {}",
+                profile.name,
+                if app.has_vault_entry() {
+                    "secrets enregistrés"
+                } else {
+                    "rien d'enregistré"
+                }
+            ))
+            .size(13)
+            .color(colors.text),
+            text("Le mot de passe enregistré est celui saisi dans le formulaire Général.")
+                .size(11)
+                .color(colors.prompt),
+            secure_input(
+                "Phrase de passe de la clé privée",
+                &app.vault_passphrase,
+                colors,
+                VaultMessage::InputPassphrase,
+                None
+            ),
+            row![
+                action("Enregistrer dans le coffre", Some(VaultMessage::StoreSecrets), colors),
+                action(
+                    "Retirer du coffre",
+                    app.has_vault_entry().then_some(VaultMessage::ForgetSecrets),
+                    colors
+                ),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10)
+        .into(),
+        None => text("Sélectionnez un profil pour gérer ses secrets.")
+            .size(13)
+            .color(colors.prompt)
+            .into(),
+    };
+
+    column![
+        row![
+            text(format!("🔓 Coffre déverrouillé ({})", vault.path().display()))
+                .size(13)
+                .width(Length::Fill)
+                .color(colors.text),
+            action("Verrouiller", Some(VaultMessage::Lock), colors),
+        ]
+        .align_y(Alignment::Center),
+        row![
+            text("Verrouillage automatique après :").size(13).color(colors.text),
+            pick_list(
+                &Settings::AUTO_LOCK_CHOICES[..],
+                Some(app.store.settings.vault_auto_lock_minutes),
+                |minutes| Message::Vault(VaultMessage::AutoLockChanged(minutes))
+            )
+            .text_size(13)
+            .padding(5),
+            text("min (0 = jamais)").size(13).color(colors.text),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+        selected,
+    ]
+    .spacing(15)
+    .into()
+}
+
+fn secure_input<'a>(
+    placeholder: &'a str,
+    value: &'a str,
+    colors: TerminalColors,
+    on_input: fn(String) -> VaultMessage,
+    on_submit: Option<VaultMessage>,
+) -> Element<'a, Message> {
+    text_input(placeholder, value)
+        .on_input(move |v| Message::Vault(on_input(v)))
+        .on_submit_maybe(on_submit.map(Message::Vault))
+        .secure(true)
+        .padding(10)
+        .style(move |_, status| theme::input_style(colors, status))
+        .into()
+}
+
+fn action<'a>(label: &'a str, msg: Option<VaultMessage>, colors: TerminalColors) -> Element<'a, Message> {
+    button(text(label).center())
+        .on_press_maybe(msg.map(Message::Vault))
+        .padding(10)
+        .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary))
+        .into()
+}
@@ -4,4 +4,5 @@ pub const ID_GROUP: &str = "group_input";
 pub const ID_IP: &str = "ip_input";
 pub const ID_PORT: &str = "port_input";
 pub const ID_USER: &str = "user_input";
-pub const ID_PASS: &str = "pass_input";
\ No newline at end of file
+pub const ID_PASS: &str = "pass_input";// scrollable of the profile table, kept on the selected row
+pub const ID_PROFILE_LIST: &str = "profile_list";
@@ -1,14 +1,14 @@
 use iced::{
-    Alignment, Color, Element, Font, Length, Task,
+    Color, Element, Font, Length,
     font::Weight,
-    widget::{column, container, horizontal_rule, row, text, text_input, vertical_space},
+    widget::{button, column, container, horizontal_rule, row, text, vertical_space},
 };
 
-use crate::{messages::{ConfigMessage, LoginMessage, Message, ProfileMessage}, ui::{EditSection, MyApp, Profile, components::{forms::{general_form, theme_form}, search_table::{content, header}}, theme}};
-use crate::ui::components::{actions_bar, sidebar};
+use crate::{connect_error::ConnectError, connect_progress::{ConnectProgress, StepState}, messages::{ConfigMessage, Message, SshMessage}, ui::{EditSection, MyApp, components::{forms::{advanced_form, general_form, logging_form, network_form, theme_form}, search_table::{content, header}}, theme}};
+use crate::ui::components::{actions_bar, exec_panel, folder_panel, import_panel, quick_connect, recordings_list, sidebar, vault_panel};
 
 pub fn render(app: &MyApp) -> Element<'_, Message> {
-    let colors = app.current_profile.theme.get_colors();
+    let colors = app.display_theme.get_colors();
 
     let side_menu = sidebar::render(app.active_section, colors);
 
@@ -40,11 +40,17 @@ pub fn render(app: &MyApp) -> Element<'_, Message> {
     // dynamic content based on active section
     let dynamic_content: Element<_> = match app.active_section {
         EditSection::General => {
+            // a selected folder shows its actions instead of the profile form
+            let editor = match &app.selected_folder {
+                Some(folder) => folder_panel::render(app, folder, colors),
+                None => general_form(app, colors),
+            };
             column![
+                quick_connect::render(app, colors),
                 header(app, colors),
                 content(app, colors),
                 horizontal_rule(1),
-                general_form(app, colors),
+                editor,
             ]
             .spacing(20)
             .into()
@@ -58,11 +64,52 @@ pub fn render(app: &MyApp) -> Element<'_, Message> {
         .into(),*/
         EditSection::Themes => column![theme_form(app, colors),].spacing(20).into(),
 
+        EditSection::Logging => column![logging_form(app, colors),].spacing(20).into(),
+
+        EditSection::Network => column![network_form(app, colors),].spacing(20).into(),
+
+        EditSection::Advanced => column![advanced_form(app, colors),].spacing(20).into(),
+
+        EditSection::Recordings => recordings_list::render(app, colors),
+
+        EditSection::Exec => exec_panel::render(app, colors),
+
+        EditSection::Vault => vault_panel::render(app, colors),
+
+        EditSection::Import => import_panel::render(app, colors),
+
         _ => column![text("Section en cours de développement...").color(colors.text),]
             .spacing(20)
             .into(),
     };
 
+    // problem with profiles.json, shown until dismissed
+    let dynamic_content: Element<_> = match &app.store_error {
+        Some(error) => column![store_error_banner(error, colors), dynamic_content]
+            .spacing(15)
+            .into(),
+        None => dynamic_content,
+    };
+
+    // SSH connections on their way, step by step
+    let dynamic_content: Element<_> = if app.connections.is_empty() {
+        dynamic_content
+    } else {
+        let mut panels = column![].spacing(10);
+        for (id, progress) in &app.connections {
+            panels = panels.push(connect_progress_panel(*id, progress, colors));
+        }
+        column![panels, dynamic_content].spacing(15).into()
+    };
+
+    // last failed connection, with what to do about it
+    let dynamic_content: Element<_> = match &app.connect_error {
+        Some(error) => column![connect_error_panel(error, colors), dynamic_content]
+            .spacing(15)
+            .into(),
+        None => dynamic_content,
+    };
+
     // actions bar (Save, Start SSH, ...)
     let actions_bar = actions_bar::buttons_form(colors, app.selected_profile_id.is_some());
     // FINAL ASSEMBLY
@@ -85,4 +132,112 @@ pub fn render(app: &MyApp) -> Element<'_, Message> {
     .into()
 }
 
+fn store_error_banner<'a>(error: &'a str, colors: theme::TerminalColors) -> Element<'a, Message> {
+    container(
+        row![
+            text(format!("⚠️ {}", error)).size(13).width(Length::Fill).color(colors.text),
+            button(text("✕").size(12))
+                .on_press(Message::Config(ConfigMessage::DismissStoreError))
+                .padding([3, 8])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+    )
+    .padding(10)
+    .width(Length::Fill)
+    .style(move |_| container::Style {
+        background: Some(colors.surface.into()),
+        border: iced::Border {
+            width: 1.0,
+            color: colors.accent,
+            radius: 5.0.into(),
+        },
+        ..Default::default()
+    })
+    .into()
+}
+
+fn connect_error_panel<'a>(error: &ConnectError, colors: theme::TerminalColors) -> Element<'a, Message> {
+    container(
+        row![
+            column![
+                text(format!("⚠️ {}", error)).size(14).color(colors.text),
+                text(error.suggestion()).size(12).color(colors.prompt),
+            ]
+            .spacing(5)
+            .width(Length::Fill),
+            button(text("✕").size(12))
+                .on_press(Message::Ssh(SshMessage::DismissConnectError))
+                .padding([3, 8])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+    )
+    .padding(12)
+    .width(Length::Fill)
+    .style(move |_| container::Style {
+        background: Some(colors.surface.into()),
+        border: iced::Border {
+            width: 1.0,
+            color: colors.accent,
+            radius: 5.0.into(),
+        },
+        ..Default::default()
+    })
+    .into()
+}
 
+fn connect_progress_panel<'a>(
+    id: uuid::Uuid,
+    progress: &'a ConnectProgress,
+    colors: theme::TerminalColors,
+) -> Element<'a, Message> {
+    let title = text(format!("Connexion à {}", progress.target)).size(13).color(colors.text);
+    // a failed connection stays until dismissed, to show where it stopped
+    let header: Element<_> = if progress.failed() {
+        row![
+            title.width(Length::Fill),
+            button(text("✕").size(12))
+                .on_press(Message::Ssh(SshMessage::DismissProgress(id)))
+                .padding([3, 8])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into()
+    } else {
+        title.into()
+    };
+
+    let mut steps = column![].spacing(3);
+    for (step, state) in &progress.steps {
+        let (mark, detail, color) = match state {
+            StepState::Running => ("…", String::new(), colors.prompt),
+            StepState::Done(detail) => ("✓", detail.clone(), colors.text),
+            StepState::Failed(reason) => ("✗", reason.clone(), colors.accent),
+        };
+        steps = steps.push(
+            row![
+                text(mark).size(12).width(16).color(color),
+                text(step.to_string()).size(12).width(230).color(color),
+                text(detail).size(11).color(colors.prompt),
+            ]
+            .spacing(6),
+        );
+    }
+
+    container(column![header, steps].spacing(8))
+        .padding(12)
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(colors.surface.into()),
+            border: iced::Border {
+                width: 1.0,
+                color: colors.prompt,
+                radius: 5.0.into(),
+            },
+            ..Default::default()
+        })
+        .into()
+}
@@ -0,0 +1,119 @@
+use iced::widget::{button, column, container, pick_list, row, scrollable, text};
+use iced::{Alignment, Color, Element, Font, Length};
+
+use crate::event_log::{self, Level};
+use crate::messages::{EventLogMessage, Message};
+use crate::models::LogLevel;
+use crate::ui::MyApp;
+use crate::ui::theme::{self, TerminalColors};
+
+/// Event log window: the events of the app, newest last
+pub fn render(app: &MyApp) -> Element<'_, Message> {
+    let colors = app.display_theme.get_colors();
+    let events = event_log::events(app.event_log_level);
+
+    // --- 1. HEADER : level filter and actions ---
+    let mut levels = row![].spacing(4);
+    for level in Level::ALL {
+        let is_active = app.event_log_level == level;
+        levels = levels.push(
+            button(text(filter_label(level)).size(11))
+                .on_press(Message::EventLog(EventLogMessage::LevelChanged(level)))
+                .padding([4, 8])
+                .style(move |_, s| {
+                    let mut st = theme::button_style(colors, s, theme::ButtonVariant::Secondary);
+                    if is_active {
+                        st.background = Some(colors.accent.into());
+                        st.text_color = colors.bg;
+                    }
+                    st
+                }),
+        );
+    }
+    let header = container(
+        row![
+            text(format!(" Journal des événements ({}) ", events.len()))
+                .size(13)
+                .color(colors.text)
+                .width(Length::Fill),
+            levels,
+            text("Verbosité").size(11).color(colors.prompt),
+            pick_list(LogLevel::ALL, Some(app.store.settings.log_level), |level| {
+                Message::EventLog(EventLogMessage::VerbosityChanged(level))
+            })
+            .text_size(11),
+            button(text("Copier").size(11))
+                .on_press(Message::EventLog(EventLogMessage::Copy))
+                .padding([4, 8])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary)),
+            button(text("Effacer").size(11))
+                .on_press(Message::EventLog(EventLogMessage::Clear))
+                .padding([4, 8])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    )
+    .padding([8, 18])
+    .width(Length::Fill)
+    .style(move |_| container::Style {
+        background: Some(colors.surface.into()),
+        ..Default::default()
+    });
+
+    // --- 2. EVENTS ---
+    let lines = events.into_iter().map(|event| {
+        row![
+            text(event.time.format("%H:%M:%S%.3f").to_string())
+                .size(12)
+                .font(Font::MONOSPACE)
+                .color(colors.prompt),
+            text(format!("{:<6}", event.level))
+                .size(12)
+                .font(Font::MONOSPACE)
+                .color(level_color(event.level, colors)),
+            text(event.message)
+                .size(12)
+                .font(Font::MONOSPACE)
+                .color(colors.text),
+        ]
+        .spacing(10)
+        .into()
+    });
+    let list = scrollable(
+        container(column(lines).spacing(2))
+            .padding(15)
+            .width(Length::Fill),
+    )
+    // follow the new events unless scrolled up
+    .anchor_bottom()
+    .height(Length::Fill);
+
+    container(column![header, list])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(colors.bg.into()),
+            ..Default::default()
+        })
+        .into()
+}
+
+/// The filter shows the level and the ones above it
+fn filter_label(level: Level) -> &'static str {
+    match level {
+        Level::Debug => "Tout",
+        Level::Info => "Infos",
+        Level::Warn => "Avertissements",
+        Level::Error => "Erreurs",
+    }
+}
+
+fn level_color(level: Level, colors: TerminalColors) -> Color {
+    match level {
+        Level::Debug => colors.prompt,
+        Level::Info => colors.text,
+        Level::Warn => Color::from_rgb(0.95, 0.7, 0.25),
+        Level::Error => Color::from_rgb(0.92, 0.35, 0.35),
+    }
+}
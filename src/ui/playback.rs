@@ -0,0 +1,114 @@
+use iced::widget::{button, column, container, row, scrollable, slider, text};
+use iced::{Alignment, Element, Length};
+
+use crate::messages::{Message, PlaybackMessage};
+use crate::recording::Player;
+use crate::ui::terminal::screen_view;
+use crate::ui::theme::{self, TerminalColors};
+use crate::ui::MyApp;
+
+pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message> {
+    let colors = app.display_theme.get_colors();
+
+    let Some(player) = app.players.get(&window_id) else {
+        return text("").into();
+    };
+
+    // --- 1. HEADER : title of the recording ---
+    let header = container(
+        text(format!(" ▶ {} ", player.recording.title()))
+            .size(13)
+            .font(iced::Font::MONOSPACE)
+            .color(colors.text),
+    )
+    .padding([8, 18])
+    .width(Length::Fill)
+    .style(move |_| container::Style {
+        background: Some(colors.surface.into()),
+        ..Default::default()
+    });
+
+    // --- 2. SCREEN ---
+    let screen = scrollable(
+        container(screen_view(player.screen(), colors, true))
+            .padding(20)
+            .width(Length::Fill)
+            .style(move |_| container::Style {
+                background: Some(colors.bg.into()),
+                ..Default::default()
+            }),
+    )
+    .height(Length::Fill);
+
+    column![header, screen, controls(player, window_id, colors)].into()
+}
+
+// play/pause, speed and seek bar
+fn controls<'a>(
+    player: &Player,
+    window_id: iced::window::Id,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    let duration = player.recording.duration();
+
+    let play_label = if player.paused { "▶ Lecture" } else { "⏸ Pause" };
+    let play_button = button(text(play_label).size(12))
+        .on_press(Message::Playback(PlaybackMessage::TogglePause(window_id)))
+        .padding(6)
+        .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary));
+
+    let mut speeds = row![].spacing(4);
+    for speed in Player::SPEEDS {
+        let is_active = player.speed == speed;
+        speeds = speeds.push(
+            button(text(format!("x{}", speed)).size(11))
+                .on_press(Message::Playback(PlaybackMessage::SpeedChanged(window_id, speed)))
+                .padding([4, 8])
+                .style(move |_, s| {
+                    let mut st = theme::button_style(colors, s, theme::ButtonVariant::Secondary);
+                    if is_active {
+                        st.background = Some(colors.accent.into());
+                        st.text_color = colors.bg;
+                    }
+                    st
+                }),
+        );
+    }
+
+    let seek_bar = slider(0.0..=duration.max(0.1), player.position(), move |t| {
+        Message::Playback(PlaybackMessage::Seek(window_id, t))
+    })
+    .step(0.1)
+    .width(Length::Fill);
+
+    container(
+        row![
+            play_button,
+            speeds,
+            seek_bar,
+            text(format!(
+                "{} / {}",
+                format_time(player.position()),
+                format_time(duration)
+            ))
+            .size(11)
+            .font(iced::Font::MONOSPACE)
+            .color(colors.accent),
+        ]
+        .spacing(12)
+        .align_y(Alignment::Center)
+        .padding(10),
+    )
+    .width(Length::Fill)
+    .style(move |_| container::Style {
+        background: Some(colors.surface.into()),
+        ..Default::default()
+    })
+    .into()
+}
+
+// seconds -> mm:ss
+fn format_time(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
@@ -0,0 +1,469 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use iced::font::Weight;
+use iced::widget::{
+    button, column, container, progress_bar, row, scrollable, text, text_input,
+};
+use iced::{Alignment, Element, Font, Length};
+
+use crate::messages::{Message, SftpMessage};
+use crate::sftp::{CwdTracker, RemoteEntry, SftpSessionArc, TransferKind, Uploader};
+use crate::ui::MyApp;
+use crate::ui::theme::{self, TerminalColors};
+
+/// State of an SFTP window, bound to the SSH connection of a terminal window
+pub struct SftpPanel {
+    /// user@host, shown in the header
+    pub title: String,
+    /// None until the subsystem is open
+    pub session: Option<SftpSessionArc>,
+    pub cwd: String,
+    pub entries: Vec<RemoteEntry>,
+    pub selected: Option<String>,
+    pub rename_input: String,
+    pub chmod_input: String,
+    pub mkdir_input: String,
+    pub upload_input: String,
+    /// Last error reported by the server or the local disk
+    pub error: Option<String>,
+    pub transfers: Vec<Transfer>,
+}
+
+pub struct Transfer {
+    pub id: u64,
+    pub name: String,
+    pub kind: TransferKind,
+    pub done: u64,
+    pub total: u64,
+    pub cancel: Arc<AtomicBool>,
+    /// None while running
+    pub result: Option<Result<(), String>>,
+}
+
+impl Transfer {
+    pub fn new(id: u64, name: String, kind: TransferKind) -> Self {
+        Self {
+            id,
+            name,
+            kind,
+            done: 0,
+            total: 0,
+            cancel: Arc::new(AtomicBool::new(false)),
+            result: None,
+        }
+    }
+}
+
+/// Uploads of the files dropped on a terminal window
+#[derive(Default)]
+pub struct DropUploads {
+    /// Remote working directory announced by the shell (OSC 7)
+    pub cwd: CwdTracker,
+    /// Opened on the first drop, then reused
+    pub uploader: Option<Uploader>,
+    pub opening: bool,
+    /// Dropped files waiting for the uploader or for a destination
+    pub queued: Vec<PathBuf>,
+    /// Destination typed in the prompt for the queued files
+    pub target_dir: Option<String>,
+    /// Some while the destination is asked to the user
+    pub prompt: Option<String>,
+    pub transfers: Vec<Transfer>,
+}
+
+impl SftpPanel {
+    pub fn new(title: String) -> Self {
+        Self {
+            title,
+            session: None,
+            cwd: String::new(),
+            entries: Vec::new(),
+            selected: None,
+            rename_input: String::new(),
+            chmod_input: String::new(),
+            mkdir_input: String::new(),
+            upload_input: String::new(),
+            error: None,
+            transfers: Vec::new(),
+        }
+    }
+
+    /// Add a running transfer, returns its cancellation flag
+    pub fn start_transfer(&mut self, id: u64, name: String, kind: TransferKind) -> Arc<AtomicBool> {
+        let transfer = Transfer::new(id, name, kind);
+        let cancel = transfer.cancel.clone();
+        self.transfers.push(transfer);
+        cancel
+    }
+
+    pub fn selected_entry(&self) -> Option<&RemoteEntry> {
+        let name = self.selected.as_ref()?;
+        self.entries.iter().find(|e| &e.name == name)
+    }
+}
+
+pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message> {
+    let colors = app.display_theme.get_colors();
+
+    let Some(panel) = app.sftp_panels.get(&window_id) else {
+        return text("").into();
+    };
+
+    let content: Element<_> = if panel.session.is_none() {
+        text(panel.error.as_deref().unwrap_or("Ouverture de la session SFTP..."))
+            .color(colors.text)
+            .into()
+    } else {
+        column![
+            path_bar(panel, window_id, colors),
+            entries_list(panel, window_id, colors),
+            actions(panel, window_id, colors),
+            transfers_list(&panel.transfers, window_id, colors),
+        ]
+        .spacing(12)
+        .into()
+    };
+
+    let mut body = column![
+        text(format!("📁 SFTP — {}", panel.title))
+            .size(16)
+            .font(Font {
+                weight: Weight::Bold,
+                ..Font::default()
+            })
+            .color(colors.accent),
+        content,
+    ]
+    .spacing(12);
+
+    if let (Some(error), Some(_)) = (&panel.error, &panel.session) {
+        body = body.push(text(format!("⚠️ {}", error)).size(12).color(colors.prompt));
+    }
+
+    container(body)
+        .padding(15)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |_| theme::main_container_style(colors))
+        .into()
+}
+
+// current folder, parent and refresh buttons
+fn path_bar<'a>(
+    panel: &'a SftpPanel,
+    window_id: iced::window::Id,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    row![
+        button(text("⬆").size(13))
+            .on_press(Message::Sftp(SftpMessage::Navigate(
+                window_id,
+                crate::sftp::parent(&panel.cwd)
+            )))
+            .padding([4, 10])
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+        text(&panel.cwd)
+            .font(Font::MONOSPACE)
+            .width(Length::Fill)
+            .color(colors.text),
+        button(text("Actualiser").size(12))
+            .on_press(Message::Sftp(SftpMessage::Navigate(window_id, panel.cwd.clone())))
+            .padding([4, 10])
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Secondary)),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+// click selects, a second click on a selected folder opens it
+fn entries_list<'a>(
+    panel: &'a SftpPanel,
+    window_id: iced::window::Id,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    let mut list = column![].spacing(1);
+
+    for (i, entry) in panel.entries.iter().enumerate() {
+        let is_selected = panel.selected.as_deref() == Some(entry.name.as_str());
+        let zebra_color = if i % 2 == 0 { colors.surface } else { colors.bg };
+        let icon = if entry.is_dir { "📁" } else { "📄" };
+        let size = if entry.is_dir {
+            String::new()
+        } else {
+            format_size(entry.size)
+        };
+
+        list = list.push(
+            button(
+                row![
+                    text(format!("{} {}", icon, entry.name)).width(Length::FillPortion(4)),
+                    text(entry.mode_string())
+                        .font(Font::MONOSPACE)
+                        .width(Length::FillPortion(2)),
+                    text(size).width(Length::FillPortion(1)),
+                ]
+                .spacing(10),
+            )
+            .width(Length::Fill)
+            .padding(6)
+            .on_press(Message::Sftp(SftpMessage::EntryClicked(
+                window_id,
+                entry.name.clone(),
+            )))
+            .style(move |_, status| {
+                let mut st = theme::button_style(colors, status, theme::ButtonVariant::Secondary);
+                if is_selected {
+                    st.background = Some(colors.prompt.into());
+                    st.text_color = colors.bg;
+                } else {
+                    st.background = Some(zebra_color.into());
+                    st.text_color = colors.text;
+                    st.border.width = 0.0;
+                }
+                st
+            }),
+        );
+    }
+
+    scrollable(list).height(Length::Fill).into()
+}
+
+// rename / chmod / delete / download on the selection, new folder and upload
+fn actions<'a>(
+    panel: &'a SftpPanel,
+    window_id: iced::window::Id,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    let selected = panel.selected_entry();
+    let has_selection = selected.is_some();
+    let is_file = selected.is_some_and(|e| !e.is_dir);
+
+    let action_button = |label: &'a str, msg: Message, enabled: bool, variant| {
+        button(text(label).size(12))
+            .on_press_maybe(enabled.then_some(msg))
+            .padding([6, 10])
+            .style(move |_, s| theme::button_style(colors, s, variant))
+    };
+    let input = |placeholder: &'a str, value: &'a str, on_input: fn(iced::window::Id, String) -> SftpMessage| {
+        text_input(placeholder, value)
+            .on_input(move |v| Message::Sftp(on_input(window_id, v)))
+            .padding(6)
+            .size(13)
+            .style(move |_, status| theme::input_style(colors, status))
+    };
+
+    column![
+        row![
+            input("Nouveau nom", &panel.rename_input, SftpMessage::InputRename),
+            action_button(
+                "Renommer",
+                Message::Sftp(SftpMessage::Rename(window_id)),
+                has_selection,
+                theme::ButtonVariant::Secondary
+            ),
+            input("Droits (ex. 755)", &panel.chmod_input, SftpMessage::InputChmod).width(120),
+            action_button(
+                "chmod",
+                Message::Sftp(SftpMessage::Chmod(window_id)),
+                has_selection,
+                theme::ButtonVariant::Secondary
+            ),
+            action_button(
+                "Supprimer",
+                Message::Sftp(SftpMessage::Delete(window_id)),
+                has_selection,
+                theme::ButtonVariant::Danger
+            ),
+            action_button(
+                "Télécharger",
+                Message::Sftp(SftpMessage::Download(window_id)),
+                is_file,
+                theme::ButtonVariant::Primary
+            ),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+        row![
+            input("Nouveau dossier", &panel.mkdir_input, SftpMessage::InputMkdir),
+            action_button(
+                "Créer",
+                Message::Sftp(SftpMessage::Mkdir(window_id)),
+                !panel.mkdir_input.trim().is_empty(),
+                theme::ButtonVariant::Secondary
+            ),
+            input(
+                "Chemin du fichier local à envoyer",
+                &panel.upload_input,
+                SftpMessage::InputUpload
+            ),
+            action_button(
+                "Envoyer",
+                Message::Sftp(SftpMessage::Upload(window_id)),
+                !panel.upload_input.trim().is_empty(),
+                theme::ButtonVariant::Primary
+            ),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(8)
+    .into()
+}
+
+// progress of the uploads and downloads of this window
+fn transfers_list<'a>(
+    transfers: &'a [Transfer],
+    window_id: iced::window::Id,
+    colors: TerminalColors,
+) -> Element<'a, Message> {
+    let mut list = column![].spacing(6);
+
+    for transfer in transfers.iter().rev() {
+        let arrow = match transfer.kind {
+            TransferKind::Upload => "⬆",
+            TransferKind::Download => "⬇",
+        };
+        let ratio = if transfer.total == 0 {
+            0.0
+        } else {
+            transfer.done as f32 / transfer.total as f32
+        };
+
+        let state: Element<_> = match &transfer.result {
+            None => button(text("Annuler").size(11))
+                .on_press(Message::Sftp(SftpMessage::CancelTransfer(
+                    window_id,
+                    transfer.id,
+                )))
+                .padding([3, 8])
+                .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Danger))
+                .into(),
+            Some(Ok(())) => text("✔ Terminé").size(11).color(colors.prompt).into(),
+            Some(Err(e)) => text(format!("✖ {}", e)).size(11).color(colors.accent).into(),
+        };
+
+        list = list.push(
+            row![
+                text(format!("{} {}", arrow, transfer.name))
+                    .size(12)
+                    .width(Length::FillPortion(2))
+                    .color(colors.text),
+                progress_bar(0.0..=1.0, ratio)
+                    .height(8)
+                    .width(Length::FillPortion(2)),
+                text(format!("{} / {}", format_size(transfer.done), format_size(transfer.total)))
+                    .size(11)
+                    .width(Length::FillPortion(1))
+                    .color(colors.text),
+                state,
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        );
+    }
+
+    list.into()
+}
+
+/// Toast shown over a terminal window while dropped files are uploaded, None when there is nothing to show
+pub fn drop_toast(app: &MyApp, window_id: iced::window::Id) -> Option<Element<'_, Message>> {
+    let colors = app.display_theme.get_colors();
+    let drops = app.drop_uploads.get(&window_id)?;
+    if drops.prompt.is_none() && drops.transfers.is_empty() {
+        return None;
+    }
+
+    let mut body = column![].spacing(10);
+
+    // no OSC 7 from the shell: ask where the files go
+    if let Some(dir) = &drops.prompt {
+        body = body.push(
+            text(format!(
+                "Dossier distant pour {} fichier(s) (répertoire courant inconnu) :",
+                drops.queued.len()
+            ))
+            .size(12)
+            .color(colors.text),
+        );
+        body = body.push(
+            row![
+                text_input("~ (dossier personnel)", dir)
+                    .on_input(move |v| Message::Sftp(SftpMessage::InputDropDir(window_id, v)))
+                    .on_submit(Message::Sftp(SftpMessage::ConfirmDropDir(window_id)))
+                    .padding(6)
+                    .size(13)
+                    .style(move |_, status| theme::input_style(colors, status)),
+                button(text("Envoyer").size(12))
+                    .on_press(Message::Sftp(SftpMessage::ConfirmDropDir(window_id)))
+                    .padding([6, 10])
+                    .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary)),
+                button(text("Annuler").size(12))
+                    .on_press(Message::Sftp(SftpMessage::CancelDropDir(window_id)))
+                    .padding([6, 10])
+                    .style(move |_, s| {
+                        theme::button_style(colors, s, theme::ButtonVariant::Secondary)
+                    }),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+        );
+    }
+
+    if !drops.transfers.is_empty() {
+        let running = drops.transfers.iter().any(|t| t.result.is_none());
+        body = body.push(
+            row![
+                text("⬆ Envoi des fichiers déposés")
+                    .size(13)
+                    .width(Length::Fill)
+                    .color(colors.accent),
+                button(text("✕").size(11))
+                    .on_press_maybe(
+                        (!running).then_some(Message::Sftp(SftpMessage::DismissDrops(window_id)))
+                    )
+                    .padding([3, 8])
+                    .style(move |_, s| {
+                        theme::button_style(colors, s, theme::ButtonVariant::Secondary)
+                    }),
+            ]
+            .align_y(Alignment::Center),
+        );
+        body = body.push(transfers_list(&drops.transfers, window_id, colors));
+    }
+
+    Some(
+        container(container(body).width(520).padding(12).style(move |_| container::Style {
+            background: Some(colors.surface.into()),
+            border: iced::Border {
+                width: 1.0,
+                color: colors.accent,
+                radius: 6.0.into(),
+            },
+            ..Default::default()
+        }))
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_right(Length::Fill)
+        .align_bottom(Length::Fill)
+        .into(),
+    )
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["o", "Ko", "Mo", "Go"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -1,19 +1,15 @@
-use std::os::linux::raw;
-
-use crate::messages::{ConfigMessage, SshMessage};
+use crate::messages::{ConfigMessage, SftpMessage, SshMessage};
 use crate::ui::theme::{self, TerminalColors, ThemeChoice};
 use crate::ui::{
-    ID_IP, ID_PASS, ID_PORT, ID_USER, MAX_TERMINAL_LINES, Message, MyApp,
+    Message, MyApp,
 };
 
-use iced::keyboard::key::Named;
-use iced::keyboard::{Key, Modifiers};
-use iced::widget::{button, column, container, pick_list, row, scrollable, text, text_input};
-use iced::{Alignment, Element, Length, Task};
+use iced::widget::{button, column, container, pick_list, row, scrollable, stack, text};
+use iced::{Alignment, Element, Length};
 use vt100;
 
 pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message> {
-    let colors = app.current_profile.theme.get_colors();
+    let colors = app.display_theme.get_colors();
 
     let Some(parser) = app.parsers.get(&window_id) else {
         return container(text("Connexion en cours...").color(colors.text))
@@ -26,19 +22,23 @@ pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message>
             .into();
     };
 
+    let profile = app.session_profile(window_id);
     let screen = parser.screen();
     let (rows, cols) = screen.size();
-    let (cursor_row, cursor_col) = screen.cursor_position();
+    let is_recording = app.recorders.contains_key(&window_id);
+    // only SSH has SFTP
+    let host = profile.host();
+    let has_sftp = app.session_handles.contains_key(&window_id);
 
-    let tab_colors = colors.clone();
-    let status_colors = colors.clone();
+    let tab_colors = colors;
+    let status_colors = colors;
     let bg_color_final = colors.bg;
 
     // --- 1. TAB ZONE ---
     let tab_bar = container(
         row![
             container(
-                text(format!(" 🐚 {} ", &app.current_profile.ip))
+                text(format!(" 🐚 {} ", host))
                     .size(13)
                     .font(iced::Font::MONOSPACE)
                     .color(colors.text)
@@ -59,11 +59,28 @@ pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message>
             }),
             pick_list(
                 &ThemeChoice::ALL[..],
-                Some(app.current_profile.theme),
+                Some(app.display_theme),
                 |theme| Message::Config(ConfigMessage::ThemeChanged(theme)) // Correction ici
             )
             .text_size(12)
             .padding(5),
+            button(text(if is_recording { "⏹ REC" } else { "⏺ REC" }).size(12))
+                .on_press(Message::Ssh(SshMessage::ToggleRecording(window_id)))
+                .style(move |_, status| {
+                    let variant = if is_recording {
+                        theme::ButtonVariant::Danger
+                    } else {
+                        theme::ButtonVariant::Secondary
+                    };
+                    theme::button_style(tab_colors, status, variant)
+                })
+                .padding(5),
+            button(text("📁 SFTP").size(12))
+                .on_press_maybe(has_sftp.then_some(Message::Sftp(SftpMessage::Open(window_id))))
+                .style(move |_, status| {
+                    theme::button_style(tab_colors, status, theme::ButtonVariant::Secondary)
+                })
+                .padding(5),
             button(text("+").size(16))
                 .style(iced::widget::button::text)
                 .padding(10),
@@ -78,58 +95,7 @@ pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message>
     });
 
     // --- 2. TERMINAL ZONE ---
-    let terminal_content = column(
-        (0..rows)
-            .map(|row_idx| {
-                let mut line_elements = Vec::new();
-                let mut current_text = String::new();
-                let mut current_fg = vt100::Color::Default;
-
-                for col_idx in 0..cols {
-                    let is_cursor = row_idx as u16 == cursor_row && col_idx as u16 == cursor_col;
-
-                    if let Some(cell) = screen.cell(row_idx, col_idx) {
-                        let fg = cell.fgcolor();
-                        let content = cell.contents();
-                        let display_char = if content.is_empty() {
-                            " "
-                        } else {
-                            content.as_str()
-                        };
-
-                        if (fg != current_fg || is_cursor) && !current_text.is_empty() {
-
-                            line_elements.push(render_text_chunk(
-                                current_text.clone(),
-                                current_fg,
-                                colors.clone(),
-                            ));
-                            current_text.clear();
-                        }
-
-                        if is_cursor {
-                            line_elements.push(render_cursor(
-                                display_char.to_string(),
-                                fg,
-                                colors.clone(),
-                            ));
-                            current_fg = fg;
-                        } else {
-                            current_fg = fg;
-                            current_text.push_str(display_char);
-                        }
-                    }
-                }
-
-                if !current_text.is_empty() {
-                    line_elements.push(render_text_chunk(current_text, current_fg, colors.clone()));
-                }
-
-                row(line_elements).spacing(0).into()
-            })
-            .collect::<Vec<_>>(),
-    )
-    .spacing(0);
+    let terminal_content = screen_view(screen, colors, true);
 
     let terminal_scroll = scrollable(
         container(terminal_content)
@@ -152,7 +118,7 @@ pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message>
     let status_bar = container(
         row![
             container(
-                text(format!(" ● {} ", app.current_profile.username))
+                text(format!(" ● {} ", profile.username))
                     .size(11)
                     .color(status_colors.bg)
             )
@@ -170,6 +136,18 @@ pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message>
                 .color(status_colors.accent)
                 .font(iced::Font::MONOSPACE)
         ]
+        .push_maybe(app.session_algorithms.get(&window_id).map(|algorithms| {
+            text(format!(" 🔐 {} ", algorithms))
+                .size(11)
+                .color(status_colors.text)
+                .font(iced::Font::MONOSPACE)
+        }))
+        .push_maybe(app.latencies.get(&window_id).map(|latency| {
+            text(format!(" Latence : {} ms ", latency.as_millis()))
+                .size(11)
+                .color(status_colors.accent)
+                .font(iced::Font::MONOSPACE)
+        }))
         .spacing(12)
         .align_y(Alignment::Center)
         .padding(10),
@@ -180,11 +158,149 @@ pub fn render(app: &MyApp, window_id: iced::window::Id) -> Element<'_, Message>
         ..Default::default()
     });
 
-    column![tab_bar, interactive_terminal, status_bar].into()
+    // over the terminal: session lost, then files dropped on the window (destination prompt and upload progress)
+    let mut terminal_zone = stack![interactive_terminal];
+    if let Some(banner) = disconnect_banner(app, window_id, colors) {
+        terminal_zone = terminal_zone.push(banner);
+    }
+    if let Some(toast) = crate::ui::sftp::drop_toast(app, window_id) {
+        terminal_zone = terminal_zone.push(toast);
+    }
+
+    column![tab_bar, terminal_zone, status_bar].into()
 }
 
 // --- HELPERS
 
+// "disconnected, press R" over the last screen of a lost session
+fn disconnect_banner(app: &MyApp, window_id: iced::window::Id, colors: TerminalColors) -> Option<Element<'_, Message>> {
+    let state = app.disconnected.get(&window_id)?;
+    let max_attempts = app.session_profile(window_id).reconnect.max_attempts;
+    let detail = if state.connecting {
+        format!("Reconnexion en cours (tentative {})...", state.attempts)
+    } else if let Some(delay) = state.retry_in {
+        format!(
+            "Nouvelle tentative automatique dans {} s ({}/{})",
+            delay.as_secs(),
+            state.attempts + 1,
+            max_attempts
+        )
+    } else {
+        "Appuyez sur R pour vous reconnecter".to_string()
+    };
+
+    let body = row![
+        column![
+            text(format!("⚠️ Déconnecté : {}", state.reason)).size(13).color(colors.accent),
+            text(detail).size(12).color(colors.text),
+        ]
+        .spacing(4)
+        .width(Length::Fill),
+        button(text("Reconnecter").size(12))
+            .on_press_maybe((!state.connecting).then_some(Message::Ssh(SshMessage::Reconnect(window_id))))
+            .padding([6, 10])
+            .style(move |_, s| theme::button_style(colors, s, theme::ButtonVariant::Primary)),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center);
+
+    Some(
+        container(container(body).width(520).padding(12).style(move |_| container::Style {
+            background: Some(colors.surface.into()),
+            border: iced::Border {
+                width: 1.0,
+                color: colors.accent,
+                radius: 6.0.into(),
+            },
+            ..Default::default()
+        }))
+        .padding(20)
+        .width(Length::Fill)
+        .center_x(Length::Fill)
+        .into(),
+    )
+}
+
+// Size of a grid cell (monospace 15px, line height 1.0) and of the space around the grid:
+// tab bar + status bar, and the padding of the terminal container
+const CELL_WIDTH: f32 = 9.0;
+const CELL_HEIGHT: f32 = 15.0;
+const BARS_HEIGHT: f32 = 80.0;
+const GRID_PADDING: f32 = 20.0;
+
+/// Number of (rows, cols) that fit in a terminal window of the given size
+pub fn grid_size(window: iced::Size) -> (u16, u16) {
+    let cols = ((window.width - 2.0 * GRID_PADDING) / CELL_WIDTH).floor().max(20.0);
+    let rows = ((window.height - BARS_HEIGHT - 2.0 * GRID_PADDING) / CELL_HEIGHT)
+        .floor()
+        .max(5.0);
+    (rows as u16, cols as u16)
+}
+
+/// Render the cells of a VT100 screen, shared by live terminals and recording playback
+pub fn screen_view<'a>(
+    screen: &vt100::Screen,
+    colors: TerminalColors,
+    show_cursor: bool,
+) -> Element<'a, Message> {
+    let (rows, cols) = screen.size();
+    let (cursor_row, cursor_col) = screen.cursor_position();
+
+    column(
+        (0..rows)
+            .map(|row_idx| {
+                let mut line_elements = Vec::new();
+                let mut current_text = String::new();
+                let mut current_fg = vt100::Color::Default;
+
+                for col_idx in 0..cols {
+                    let is_cursor = show_cursor && row_idx == cursor_row && col_idx == cursor_col;
+
+                    if let Some(cell) = screen.cell(row_idx, col_idx) {
+                        let fg = cell.fgcolor();
+                        let content = cell.contents();
+                        let display_char = if content.is_empty() {
+                            " "
+                        } else {
+                            content.as_str()
+                        };
+
+                        if (fg != current_fg || is_cursor) && !current_text.is_empty() {
+
+                            line_elements.push(render_text_chunk(
+                                current_text.clone(),
+                                current_fg,
+                                colors,
+                            ));
+                            current_text.clear();
+                        }
+
+                        if is_cursor {
+                            line_elements.push(render_cursor(
+                                display_char.to_string(),
+                                fg,
+                                colors,
+                            ));
+                            current_fg = fg;
+                        } else {
+                            current_fg = fg;
+                            current_text.push_str(display_char);
+                        }
+                    }
+                }
+
+                if !current_text.is_empty() {
+                    line_elements.push(render_text_chunk(current_text, current_fg, colors));
+                }
+
+                row(line_elements).spacing(0).into()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .spacing(0)
+    .into()
+}
+
 fn render_text_chunk(
     txt: String,
     vt_color: vt100::Color,
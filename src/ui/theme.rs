@@ -1,5 +1,5 @@
 use iced::widget::{button, container, text_input};
-use iced::{Border, Color, Shadow};
+use iced::{Border, Color};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -43,6 +43,24 @@ impl ThemeChoice {
         ThemeChoice::AyuMirage,
     ];
 
+    /// Theme whose background and text colours are the nearest to the given ones
+    pub fn closest(bg: Color, text: Color) -> ThemeChoice {
+        let distance = |a: Color, b: Color| {
+            (a.r - b.r).powi(2) + (a.g - b.g).powi(2) + (a.b - b.b).powi(2)
+        };
+        ThemeChoice::ALL
+            .into_iter()
+            .min_by(|a, b| {
+                let score = |t: &ThemeChoice| {
+                    let colors = t.get_colors();
+                    // the background weighs more: it is what the eye notices first
+                    2.0 * distance(colors.bg, bg) + distance(colors.text, text)
+                };
+                score(a).total_cmp(&score(b))
+            })
+            .unwrap_or_default()
+    }
+
     pub fn get_colors(&self) -> TerminalColors {
         match self {
             ThemeChoice::Slate => TerminalColors {
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chacha20poly1305::{
+    Key, XChaCha20Poly1305, XNonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload, rand_core::RngCore},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Version of the vault file layout
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// Argon2id cost, stored in the file so it can be raised later without breaking old vaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    // 64 MiB, 3 passes: a few hundred milliseconds on a desktop
+    fn default() -> Self {
+        Self {
+            m_cost: 64 * 1024,
+            t_cost: 3,
+            p_cost: 1,
+        }
+    }
+}
+
+/// Layout of vault.json, only the ciphertext holds secrets
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets of one profile
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct VaultEntry {
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub key_passphrase: String,
+}
+
+impl VaultEntry {
+    pub fn is_empty(&self) -> bool {
+        self.password.is_empty() && self.key_passphrase.is_empty()
+    }
+}
+
+/// Decrypted content of the vault
+#[derive(Default, Serialize, Deserialize)]
+struct Secrets {
+    entries: HashMap<Uuid, VaultEntry>,
+}
+
+/// An unlocked vault: the derived key and the secrets stay in memory until it is dropped (locked),
+/// then they are overwritten with zeros.
+#[derive(Clone)]
+pub struct Vault {
+    path: PathBuf,
+    key: Zeroizing<[u8; 32]>,
+    kdf: KdfParams,
+    salt: [u8; SALT_LEN],
+    entries: HashMap<Uuid, VaultEntry>,
+}
+
+// never print the secrets
+impl std::fmt::Debug for Vault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Vault({}, {} entrées)", self.path.display(), self.entries.len())
+    }
+}
+
+impl Vault {
+    /// vault.json in the per-user config folder
+    pub fn default_path() -> PathBuf {
+        crate::storage::config_dir().join("vault.json")
+    }
+
+    /// Create an empty vault protected by `master`, the KDF runs on a blocking thread
+    pub async fn create(path: PathBuf, master: Zeroizing<String>) -> Result<Self, String> {
+        tokio::task::spawn_blocking(move || Self::create_with(path, &master, KdfParams::default()))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    /// Decrypt an existing vault, the KDF runs on a blocking thread
+    pub async fn unlock(path: PathBuf, master: Zeroizing<String>) -> Result<Self, String> {
+        tokio::task::spawn_blocking(move || Self::unlock_sync(path, &master))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    fn create_with(path: PathBuf, master: &str, kdf: KdfParams) -> Result<Self, String> {
+        if path.exists() {
+            return Err(format!("{} existe déjà", path.display()));
+        }
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let vault = Self {
+            key: derive_key(master, &salt, kdf)?,
+            path,
+            kdf,
+            salt,
+            entries: HashMap::new(),
+        };
+        vault.save()?;
+        Ok(vault)
+    }
+
+    fn unlock_sync(path: PathBuf, master: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let file: VaultFile =
+            serde_json::from_str(&data).map_err(|e| format!("Coffre illisible : {}", e))?;
+        if file.version != VAULT_VERSION {
+            return Err(format!("Version de coffre {} non supportée", file.version));
+        }
+
+        let decode = |field: &str| BASE64.decode(field).map_err(|e| format!("Coffre illisible : {}", e));
+        let salt: [u8; SALT_LEN] = decode(&file.salt)?
+            .try_into()
+            .map_err(|_| "Coffre illisible : sel invalide".to_string())?;
+        let nonce = decode(&file.nonce)?;
+        if nonce.len() != 24 {
+            return Err("Coffre illisible : nonce invalide".into());
+        }
+        let ciphertext = decode(&file.ciphertext)?;
+
+        let key = derive_key(master, &salt, file.kdf)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&*key));
+        // a wrong password and a modified file both fail the authentication tag
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &associated_data(file.kdf, &salt),
+                    },
+                )
+                .map_err(|_| "Mot de passe maître incorrect ou coffre altéré".to_string())?,
+        );
+        let secrets: Secrets =
+            serde_json::from_slice(&plaintext).map_err(|e| format!("Coffre illisible : {}", e))?;
+
+        Ok(Self {
+            path,
+            key,
+            kdf: file.kdf,
+            salt,
+            entries: secrets.entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entry(&self, profile_id: &Uuid) -> Option<&VaultEntry> {
+        self.entries.get(profile_id)
+    }
+
+    /// Store the secrets of a profile, an empty entry removes it
+    pub fn set_entry(&mut self, profile_id: Uuid, entry: VaultEntry) {
+        if entry.is_empty() {
+            self.entries.remove(&profile_id);
+        } else {
+            self.entries.insert(profile_id, entry);
+        }
+    }
+
+    pub fn remove_entry(&mut self, profile_id: &Uuid) -> bool {
+        self.entries.remove(profile_id).is_some()
+    }
+
+    /// Encrypt everything again with a fresh nonce and replace the file
+    pub fn save(&self) -> Result<(), String> {
+        let secrets = Secrets {
+            entries: self.entries.clone(),
+        };
+        let plaintext = Zeroizing::new(serde_json::to_vec(&secrets).map_err(|e| e.to_string())?);
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&*self.key));
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: &associated_data(self.kdf, &self.salt),
+                },
+            )
+            .map_err(|_| "Chiffrement du coffre impossible".to_string())?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            kdf: self.kdf,
+            salt: BASE64.encode(self.salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        crate::storage::write_atomic(&self.path, json.as_bytes())
+            .map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+fn derive_key(master: &str, salt: &[u8], kdf: KdfParams) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| format!("Paramètres du coffre invalides : {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(master.as_bytes(), salt, &mut *key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+// the clear header is authenticated too: changing the KDF cost or the salt breaks the tag
+fn associated_data(kdf: KdfParams, salt: &[u8]) -> Vec<u8> {
+    format!(
+        "rustty-vault:{}:{}:{}:{}:{}",
+        VAULT_VERSION,
+        kdf.m_cost,
+        kdf.t_cost,
+        kdf.p_cost,
+        BASE64.encode(salt)
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cheap parameters, the default ones take too long for a test
+    const TEST_KDF: KdfParams = KdfParams {
+        m_cost: 256,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustty-vault-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("vault.json")
+    }
+
+    #[test]
+    fn round_trip_and_wrong_password() {
+        let path = temp_path("roundtrip");
+        let id = Uuid::new_v4();
+
+        let mut vault = Vault::create_with(path.clone(), "correct horse", TEST_KDF).unwrap();
+        vault.set_entry(
+            id,
+            VaultEntry {
+                password: "s3cret".into(),
+                key_passphrase: "phrase".into(),
+            },
+        );
+        vault.save().unwrap();
+
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("s3cret"));
+
+        let reopened = Vault::unlock_sync(path.clone(), "correct horse").unwrap();
+        assert_eq!(reopened.entry(&id).unwrap().password, "s3cret");
+        assert_eq!(reopened.entry(&id).unwrap().key_passphrase, "phrase");
+
+        assert!(Vault::unlock_sync(path.clone(), "wrong").is_err());
+        assert!(Vault::create_with(path.clone(), "again", TEST_KDF).is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn tampered_header_is_rejected() {
+        let path = temp_path("tamper");
+        Vault::create_with(path.clone(), "master", TEST_KDF).unwrap();
+
+        let mut file: VaultFile = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        file.kdf.t_cost = 2;
+        std::fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        assert!(Vault::unlock_sync(path.clone(), "master").is_err());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}